@@ -1,10 +1,22 @@
 #![allow(unused_imports, dead_code, unused_variables)]
 
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
 use std::{env, fs, io};
 
-use bindgen::callbacks::IntKind;
+use bindgen::callbacks::{EnumVariantValue, IntKind};
+
+// Every integer macro and enum variant bindgen comes across, so that families
+// of flags can be grouped into types of their own once the bindings have been
+// generated.
+static CONSTANTS: Mutex<BTreeMap<String, i64>> = Mutex::new(BTreeMap::new());
+
+fn record_constant(name: &str, value: i64) {
+    CONSTANTS.lock().unwrap().insert(name.to_owned(), value);
+}
 
 #[derive(Debug)]
 struct SdlOverrides;
@@ -47,7 +59,23 @@ impl bindgen::callbacks::ParseCallbacks for SdlOverrides {
         .map(|s| s.to_string())
     }
 
+    fn enum_variant_name(
+        &self,
+        _enum_name: Option<&str>,
+        original_variant_name: &str,
+        variant_value: EnumVariantValue,
+    ) -> Option<String> {
+        let value = match variant_value {
+            EnumVariantValue::Signed(value) => value,
+            EnumVariantValue::Unsigned(value) => value as i64,
+        };
+        record_constant(original_variant_name, value);
+        None
+    }
+
     fn int_macro(&self, name: &str, value: i64) -> Option<IntKind> {
+        record_constant(name, value);
+
         match name {
             "SDL_PRESSED" | "SDL_RELEASED" => Some(IntKind::U8),
 
@@ -74,24 +102,54 @@ impl bindgen::callbacks::ParseCallbacks for SdlOverrides {
 fn main() {
     let target = env::var("TARGET").expect("Cargo build scripts always have TARGET");
     let host = env::var("HOST").expect("Cargo build scripts always have HOST");
+    let target_os = get_os_from_triple(target.as_str()).unwrap();
 
-    let mut include_paths: Vec<String> = vec!["/opt/homebrew/include".to_string()];
+    let mut include_paths: Vec<String> = Vec::new();
+
+    if target_os != "emscripten" {
+        include_paths.push("/opt/homebrew/include".to_string());
+    }
 
     if let Ok(include_path) = env::var("SDL_INCLUDE_PATH") {
         include_paths.push(include_path);
     };
 
-    let pkg_config_library = pkg_config::Config::new()
-        .print_system_libs(false)
-        .probe("sdl")
-        .unwrap();
-    for path in pkg_config_library.include_paths {
-        include_paths.push(format!("{}", path.display()));
+    if target_os == "emscripten" {
+        // Emscripten bundles its own copy of the SDL 1.2 headers and doesn't
+        // ship a pkg-config file for them, so we look them up in the SDK.
+        include_paths.extend(emscripten_include_paths());
+    } else {
+        let pkg_config_library = pkg_config::Config::new()
+            .print_system_libs(false)
+            .probe("sdl")
+            .unwrap();
+        for path in pkg_config_library.include_paths {
+            include_paths.push(format!("{}", path.display()));
+        }
     }
 
     generate_bindings(target.as_str(), host.as_str(), include_paths.as_slice());
     println!("cargo:include={}", include_paths.join(":"));
-    link_sdl();
+    link_sdl(target_os);
+}
+
+// Newer versions of emsdk install the system headers into the cache sysroot,
+// while older versions use them straight out of system/include, so we check
+// both.
+fn emscripten_include_paths() -> Vec<String> {
+    println!("cargo:rerun-if-env-changed=EMSCRIPTEN");
+    println!("cargo:rerun-if-env-changed=EMSDK");
+
+    let root = env::var("EMSCRIPTEN")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("EMSDK").map(|emsdk| Path::new(&emsdk).join("upstream/emscripten")))
+        .expect("EMSCRIPTEN or EMSDK must be set when building for emscripten");
+
+    vec![root.join("cache/sysroot/include"), root.join("system/include")]
+        .into_iter()
+        .filter(|path| path.exists())
+        .map(|path| format!("{}", path.display()))
+        .collect()
 }
 
 fn create_bindgen_builder(target: &str, host: &str, headers_paths: &[String]) -> bindgen::Builder {
@@ -116,9 +174,11 @@ fn create_bindgen_builder(target: &str, host: &str, headers_paths: &[String]) ->
     }
 
     // SDL is missing a default configuration for Linux, so we define one to
-    // give us access to all possible functions.
-    if target_os == "linux-gnu" {
-        bindings = bindings.clang_arg("-DSDL_VIDEO_DRIVER_X11");
+    // give us access to all possible functions. SDL_syswm.h also only
+    // declares the platform specific parts of SDL_SysWMinfo when the matching
+    // video driver is defined.
+    if let Some(driver) = syswm_video_driver(target_os) {
+        bindings = bindings.clang_arg(format!("-D{}", driver));
     }
 
     // There are a number of things which need to be blacklisted in all the
@@ -145,6 +205,10 @@ fn generate_bindings(target: &str, host: &str, headers_paths: &[String]) {
         .whitelist_function("SDL_.*")
         .whitelist_type("SDL_.*")
         .whitelist_var("SDL_.*")
+        // The audio sample formats don't have the SDL_ prefix.
+        .whitelist_var("AUDIO_.*")
+        // Neither do the CD-ROM limits and frame rate.
+        .whitelist_var("CD_.*")
         //.opaque_type("^SDL_Joystick$")
         .disable_name_namespacing()
         .generate()
@@ -156,6 +220,8 @@ fn generate_bindings(target: &str, host: &str, headers_paths: &[String]) {
         .write_to_file(out_path.join("sdl_bindings.rs"))
         .expect("Couldn't write bindings!");
 
+    fs::write(out_path.join("sdl_flags.rs"), generate_flags()).expect("Couldn't write flag types!");
+
     #[cfg(feature = "image")]
     {
         let image_bindings = create_bindgen_builder(target, host, headers_paths)
@@ -302,7 +368,131 @@ fn generate_bindings(target: &str, host: &str, headers_paths: &[String]) {
     }
 }
 
-fn link_sdl() {
+// A family of flag constants which gets its own type, generated with the
+// `sdl_flags!` macro in lib.rs.
+struct FlagFamily {
+    name: &'static str,
+    repr: &'static str,
+    doc: &'static str,
+    members: FlagMembers,
+    // Combinations SDL defines in terms of enum variants, which bindgen can't
+    // evaluate, so they're rebuilt here.
+    combined: &'static [(&'static str, &'static [&'static str])],
+}
+
+enum FlagMembers {
+    // Every constant starting with the prefix, which is stripped off.
+    Prefix(&'static str),
+    // The listed constants, with the SDL_ prefix stripped off.
+    Names(&'static [&'static str]),
+}
+
+const FLAG_FAMILIES: &[FlagFamily] = &[
+    FlagFamily {
+        name: "SDL_InitFlags",
+        repr: "u32",
+        doc: "The subsystems passed to `SDL_Init` and `SDL_InitSubSystem`.",
+        members: FlagMembers::Prefix("SDL_INIT_"),
+        combined: &[],
+    },
+    FlagFamily {
+        name: "SDL_HatFlags",
+        repr: "u8",
+        doc: "The position of a joystick hat, as returned by `SDL_JoystickGetHat`.",
+        members: FlagMembers::Prefix("SDL_HAT_"),
+        combined: &[],
+    },
+    FlagFamily {
+        name: "SDL_KeyModFlags",
+        repr: "u32",
+        doc: "The modifier keys, as in `SDL_KeyMod`.",
+        members: FlagMembers::Prefix("KMOD_"),
+        combined: &[
+            ("CTRL", &["LCTRL", "RCTRL"]),
+            ("SHIFT", &["LSHIFT", "RSHIFT"]),
+            ("ALT", &["LALT", "RALT"]),
+            ("META", &["LMETA", "RMETA"]),
+        ],
+    },
+    FlagFamily {
+        name: "SDL_SurfaceFlags",
+        repr: "u32",
+        doc: "The flags of an `SDL_Surface`, including those passed to `SDL_SetVideoMode`.",
+        members: FlagMembers::Names(&[
+            "SDL_SWSURFACE",
+            "SDL_HWSURFACE",
+            "SDL_ASYNCBLIT",
+            "SDL_ANYFORMAT",
+            "SDL_HWPALETTE",
+            "SDL_DOUBLEBUF",
+            "SDL_FULLSCREEN",
+            "SDL_OPENGL",
+            "SDL_OPENGLBLIT",
+            "SDL_RESIZABLE",
+            "SDL_NOFRAME",
+            "SDL_HWACCEL",
+            "SDL_SRCCOLORKEY",
+            "SDL_RLEACCELOK",
+            "SDL_RLEACCEL",
+            "SDL_SRCALPHA",
+            "SDL_PREALLOC",
+        ]),
+        combined: &[],
+    },
+];
+
+fn generate_flags() -> String {
+    let constants = CONSTANTS.lock().unwrap();
+    let mut out = String::new();
+
+    for family in FLAG_FAMILIES {
+        let mut flags: Vec<(String, i64)> = match family.members {
+            FlagMembers::Prefix(prefix) => constants
+                .iter()
+                .filter_map(|(name, value)| Some((name.strip_prefix(prefix)?.to_owned(), *value)))
+                .collect(),
+            FlagMembers::Names(names) => names
+                .iter()
+                .filter_map(|name| {
+                    let value = constants.get(*name)?;
+                    Some((name.trim_start_matches("SDL_").to_owned(), *value))
+                })
+                .collect(),
+        };
+
+        for (name, parts) in family.combined {
+            if flags.iter().any(|(flag, _)| flag == name) {
+                continue;
+            }
+            let value = flags
+                .iter()
+                .filter(|(flag, _)| parts.contains(&flag.as_str()))
+                .fold(0, |value, (_, part)| value | part);
+            flags.push((name.to_string(), value));
+        }
+
+        writeln!(out, "sdl_flags! {{").unwrap();
+        writeln!(out, "    #[doc = {:?}]", family.doc).unwrap();
+        writeln!(out, "    {}: {} {{", family.name, family.repr).unwrap();
+        for (name, value) in flags {
+            writeln!(out, "        {} = {},", name, value).unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+
+    out
+}
+
+fn link_sdl(target_os: &str) {
+    // Emscripten ships its own implementation of SDL 1.2 which emcc links in
+    // place of the native libraries, but the port has to be enabled
+    // explicitly.
+    if target_os == "emscripten" {
+        println!("cargo:rustc-link-arg=-s");
+        println!("cargo:rustc-link-arg=USE_SDL=1");
+    }
+
     println!("cargo:rustc-flags=-l SDL");
 
     #[cfg(feature = "mixer")]
@@ -318,6 +508,23 @@ fn link_sdl() {
     println!("cargo:rustc-flags=-l SDL_gfx");
 }
 
+// The video driver whose window manager info we want for a target. Other
+// targets, like macOS, get the generic SDL_SysWMinfo which only holds the
+// version.
+fn syswm_video_driver(target_os: &str) -> Option<&'static str> {
+    if target_os.starts_with("windows") {
+        Some("SDL_VIDEO_DRIVER_WINDIB")
+    } else if target_os.starts_with("linux")
+        || target_os.ends_with("bsd")
+        || target_os == "dragonfly"
+        || target_os == "solaris"
+    {
+        Some("SDL_VIDEO_DRIVER_X11")
+    } else {
+        None
+    }
+}
+
 fn get_os_from_triple(triple: &str) -> Option<&str> {
     triple.splitn(3, "-").nth(2)
 }
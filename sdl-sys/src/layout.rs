@@ -0,0 +1,45 @@
+// Compile time checks that the generated bindings match the SDL 1.2 ABI.
+//
+// bindgen's own layout tests only check the bindings against the headers they
+// were generated from, so they can't catch headers which don't match the
+// library they're used with. These check the layouts of the types the safe
+// crate reads directly against the sizes SDL 1.2 has always had, so a
+// mismatch fails the build rather than corrupting memory at runtime.
+
+use core::mem::{align_of, offset_of, size_of};
+
+use crate::*;
+
+const PTR: usize = size_of::<*mut libc::c_void>();
+
+// Rounds `offset` up to the next multiple of `align`.
+const fn align_up(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+const _: () = assert!(size_of::<SDL_Rect>() == 8 && align_of::<SDL_Rect>() == 2);
+const _: () = assert!(size_of::<SDL_Color>() == 4);
+
+const _: () = assert!(size_of::<SDL_keysym>() == 16 && align_of::<SDL_keysym>() == 4);
+const _: () = assert!(offset_of!(SDL_keysym, sym) == 4);
+const _: () = assert!(offset_of!(SDL_keysym, mod_) == 8);
+const _: () = assert!(offset_of!(SDL_keysym, unicode) == 12);
+
+const _: () = assert!(size_of::<SDL_KeyboardEvent>() == 20);
+const _: () = assert!(offset_of!(SDL_KeyboardEvent, keysym) == 4);
+const _: () = assert!(size_of::<SDL_MouseMotionEvent>() == 12);
+const _: () = assert!(size_of::<SDL_MouseButtonEvent>() == 8);
+
+// SDL_UserEvent, with its two pointers, is the largest event on 64-bit
+// platforms, and SDL_KeyboardEvent is on 32-bit ones.
+const EVENT_SIZE: usize = if 8 + 2 * PTR > 20 { 8 + 2 * PTR } else { 20 };
+const _: () = assert!(size_of::<SDL_Event>() == EVENT_SIZE);
+
+const _: () = assert!(offset_of!(SDL_Surface, format) == PTR);
+const _: () = assert!(offset_of!(SDL_Surface, w) == 2 * PTR);
+const _: () = assert!(offset_of!(SDL_Surface, h) == 2 * PTR + 4);
+const _: () = assert!(offset_of!(SDL_Surface, pitch) == 2 * PTR + 8);
+const _: () = assert!(offset_of!(SDL_Surface, pixels) == align_up(2 * PTR + 10, PTR));
+
+const _: () = assert!(offset_of!(SDL_PixelFormat, BitsPerPixel) == PTR);
+const _: () = assert!(offset_of!(SDL_PixelFormat, BytesPerPixel) == PTR + 1);
@@ -7,6 +7,107 @@
 
 include!(concat!(env!("OUT_DIR"), "/sdl_bindings.rs"));
 
+// The build script groups related flag constants into types, which are
+// generated as invocations of this macro.
+macro_rules! sdl_flags {
+    ($(#[$meta:meta])* $name:ident: $repr:ty { $($flag:ident = $value:expr,)* }) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+        pub struct $name(pub $repr);
+
+        impl $name {
+            $(pub const $flag: $name = $name($value);)*
+
+            pub const fn empty() -> $name {
+                $name(0)
+            }
+
+            pub const fn bits(self) -> $repr {
+                self.0
+            }
+
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            pub const fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            pub const fn intersects(self, other: $name) -> bool {
+                self.0 & other.0 != 0
+            }
+        }
+
+        impl core::ops::BitOr for $name {
+            type Output = $name;
+
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl core::ops::BitAnd for $name {
+            type Output = $name;
+
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl core::ops::BitXor for $name {
+            type Output = $name;
+
+            fn bitxor(self, other: $name) -> $name {
+                $name(self.0 ^ other.0)
+            }
+        }
+
+        impl core::ops::Not for $name {
+            type Output = $name;
+
+            fn not(self) -> $name {
+                $name(!self.0)
+            }
+        }
+
+        impl core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl core::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, other: $name) {
+                self.0 &= other.0;
+            }
+        }
+
+        impl core::ops::BitXorAssign for $name {
+            fn bitxor_assign(&mut self, other: $name) {
+                self.0 ^= other.0;
+            }
+        }
+
+        impl From<$repr> for $name {
+            fn from(bits: $repr) -> $name {
+                $name(bits)
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(flags: $name) -> $repr {
+                flags.0
+            }
+        }
+    };
+}
+
+include!(concat!(env!("OUT_DIR"), "/sdl_flags.rs"));
+
+mod layout;
+
 #[repr(u32)]
 pub enum SDL_WindowFlags {
     SDL_SWSURFACE = crate::SDL_SWSURFACE,
@@ -22,6 +123,23 @@ pub enum SDL_WindowFlags {
     SDL_NOFRAME = crate::SDL_NOFRAME,
 }
 
+// Browsers don't allow blocking the main thread, so on emscripten the main
+// loop needs to be handed off to the runtime. These live in emscripten.h
+// rather than the SDL headers, so we declare them by hand.
+#[cfg(target_os = "emscripten")]
+pub mod emscripten {
+    pub type em_callback_func = Option<unsafe extern "C" fn()>;
+
+    extern "C" {
+        pub fn emscripten_set_main_loop(
+            func: em_callback_func,
+            fps: libc::c_int,
+            simulate_infinite_loop: libc::c_int,
+        );
+        pub fn emscripten_cancel_main_loop();
+    }
+}
+
 #[cfg(feature = "mixer")]
 pub mod mixer {
     include!(concat!(env!("OUT_DIR"), "/sdl_mixer_bindings.rs"));
@@ -1 +1,2 @@
 #include <SDL/SDL.h>
+#include <SDL/SDL_syswm.h>
@@ -19,7 +19,7 @@ fn main() -> anyhow::Result<()> {
     window.draw_circle(50, 50, 10, Color::RED)?;
     window.draw_box(150, 150, 200, 200, Color::RED)?;
 
-    window.flip()?;
+    window.present()?;
 
     ::std::thread::sleep(Duration::new(3, 1_000_000_000u32 / 60));
 
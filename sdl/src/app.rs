@@ -0,0 +1,406 @@
+//! A runner which takes care of the boilerplate every SDL program needs.
+//!
+//! Implement [`App`] and hand it to [`run`], which initializes SDL, opens the
+//! window, runs the event loop at a steady framerate and shuts everything
+//! down again once the window is closed.
+//!
+//! For callbacks rather than a trait, [`run_with`] does the same, handing
+//! every event and frame to a single closure which decides whether to carry
+//! on with a [`ControlFlow`].
+//!
+//! Closing the window can be intercepted, such as to ask about saving
+//! first, with [`App::quit_requested`] or, for either runner,
+//! [`set_quit_hook`].
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::event::{AppState, Event, EventPump};
+use crate::sdl;
+use crate::timer;
+use crate::video::{Screen, WindowBuildError};
+use crate::{VideoSubsystem, SDL};
+
+/// An application driven by [`run`].
+///
+/// Every method other than [`render`](App::render) has a default which does
+/// nothing.
+pub trait App {
+    /// Called once, after the window has been created but before the first
+    /// frame.
+    fn init(&mut self, _screen: &mut Screen) -> sdl::Result<()> {
+        Ok(())
+    }
+
+    /// Called for every event, other than `Event::Quit`, which goes to
+    /// [`quit_requested`](App::quit_requested) instead.
+    fn handle_event(&mut self, _event: Event) -> sdl::Result<()> {
+        Ok(())
+    }
+
+    /// Advances the application by `dt`, the time since the last frame.
+    fn update(&mut self, _dt: Duration) -> sdl::Result<()> {
+        Ok(())
+    }
+
+    /// Draws the current frame. The screen is presented afterwards.
+    fn render(&mut self, screen: &mut Screen) -> sdl::Result<()>;
+
+    /// Called when the window loses input focus or is minimized. This is the
+    /// place to pause audio.
+    ///
+    /// Unless [`AppConfig::pause_in_background`] is turned off,
+    /// [`update`](App::update) isn't called until
+    /// [`on_resume`](App::on_resume), and the time in between doesn't count
+    /// towards its `dt`.
+    fn on_pause(&mut self) -> sdl::Result<()> {
+        Ok(())
+    }
+
+    /// Called when the window is visible and has input focus again after
+    /// [`on_pause`](App::on_pause).
+    fn on_resume(&mut self) -> sdl::Result<()> {
+        Ok(())
+    }
+
+    /// Checked once per frame; returning `true` ends the loop.
+    fn should_quit(&self) -> bool {
+        false
+    }
+
+    /// Called when the window is closed, or the program is otherwise asked
+    /// to quit, to decide whether to. The default quits.
+    ///
+    /// Returning [`QuitResponse::Cancel`] keeps the loop running, such as
+    /// to ask whether to save first. The app can end the loop itself later
+    /// with [`should_quit`](App::should_quit).
+    fn quit_requested(&mut self) -> QuitResponse {
+        QuitResponse::Quit
+    }
+}
+
+/// Whether to go through with a request to quit, see
+/// [`App::quit_requested`] and [`set_quit_hook`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum QuitResponse {
+    Quit,
+    /// Ignore the request and keep running.
+    Cancel,
+}
+
+type QuitHook = Box<dyn FnMut() -> QuitResponse + Send>;
+
+// The hook, and a count of the times it's been set or cleared. The hook is
+// taken out while it runs, so it can set or clear itself without
+// deadlocking, and the count says whether it did.
+struct QuitHookSlot {
+    hook: Option<QuitHook>,
+    changes: u64,
+}
+
+static QUIT_HOOK: Mutex<QuitHookSlot> = Mutex::new(QuitHookSlot {
+    hook: None,
+    changes: 0,
+});
+
+fn replace_quit_hook(hook: Option<QuitHook>) {
+    let mut slot = QUIT_HOOK.lock().unwrap_or_else(|err| err.into_inner());
+    slot.hook = hook;
+    slot.changes += 1;
+}
+
+/// Installs a hook which decides what happens to every `Event::Quit` in
+/// [`run`] and [`run_with`], replacing the previous hook, if any.
+///
+/// The hook is asked first. If it returns [`QuitResponse::Cancel`], the
+/// event is dropped: [`run`] doesn't ask [`App::quit_requested`], and
+/// [`run_with`] doesn't pass the event on to its callback.
+///
+/// The hook can call this or [`clear_quit_hook`] itself, such as to swap in
+/// a different hook while a "save before exit?" dialog is open.
+pub fn set_quit_hook<F>(hook: F)
+where
+    F: FnMut() -> QuitResponse + Send + 'static,
+{
+    replace_quit_hook(Some(Box::new(hook)));
+}
+
+/// Removes the hook installed with [`set_quit_hook`], if there is one.
+pub fn clear_quit_hook() {
+    replace_quit_hook(None);
+}
+
+// Asks the quit hook about a quit event. Without a hook, quitting goes ahead.
+fn run_quit_hook() -> QuitResponse {
+    let (hook, changes) = {
+        let mut slot = QUIT_HOOK.lock().unwrap_or_else(|err| err.into_inner());
+        (slot.hook.take(), slot.changes)
+    };
+    let mut hook = match hook {
+        Some(hook) => hook,
+        None => return QuitResponse::Quit,
+    };
+
+    let response = hook();
+
+    // Put the hook back, unless it replaced or cleared itself.
+    let mut slot = QUIT_HOOK.lock().unwrap_or_else(|err| err.into_inner());
+    if slot.changes == changes {
+        slot.hook = Some(hook);
+    }
+    response
+}
+
+/// Settings for the window and the loop created by [`run`].
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub resizable: bool,
+    /// The target framerate. 0 runs as fast as possible.
+    pub fps: u32,
+    /// See [`EventPump::coalesce_wheel`].
+    pub coalesce_wheel: bool,
+    /// Whether [`run`] stops calling [`App::update`] while the app is
+    /// paused. See [`App::on_pause`].
+    pub pause_in_background: bool,
+}
+
+impl AppConfig {
+    pub fn new(title: &str, width: u32, height: u32) -> AppConfig {
+        AppConfig {
+            title: title.to_owned(),
+            width,
+            height,
+            ..AppConfig::default()
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> AppConfig {
+        AppConfig {
+            title: String::new(),
+            width: 640,
+            height: 480,
+            fullscreen: false,
+            resizable: false,
+            fps: 60,
+            coalesce_wheel: false,
+            pause_in_background: true,
+        }
+    }
+}
+
+/// Runs `app` until it quits or the window is closed.
+///
+/// This is built on [`timer::main_loop`], so on emscripten it never returns.
+pub fn run<A>(config: AppConfig, app: A) -> Result<(), AppError>
+where
+    A: App + 'static,
+{
+    let (_sdl_context, _video, mut screen, mut events) = open(&config)?;
+    let mut app = app;
+    app.init(&mut screen)?;
+
+    // The main loop takes ownership of the app and the screen, which means
+    // they're dropped before the subsystems above once it's done. Errors have
+    // to be smuggled out of the loop rather than returned.
+    let result = Rc::new(RefCell::new(Ok(())));
+    let loop_result = Rc::clone(&result);
+    let mut state = LoopState {
+        last_frame: Instant::now(),
+        paused: false,
+        pause_in_background: config.pause_in_background,
+    };
+
+    timer::main_loop(config.fps, move || {
+        match frame(&mut app, &mut screen, &mut events, &mut state) {
+            Ok(keep_going) => keep_going,
+            Err(err) => {
+                *loop_result.borrow_mut() = Err(err);
+                false
+            }
+        }
+    })?;
+
+    result.replace(Ok(())).map_err(AppError::from)
+}
+
+/// What [`run_with`] should do after a callback returns.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ControlFlow {
+    Continue,
+    /// Ends the loop, and makes [`run_with`] return the given exit code.
+    Exit(i32),
+}
+
+/// What a [`run_with`] callback is being called for.
+pub enum LoopEvent<'a> {
+    /// An event, including `Event::Quit` unless [`set_quit_hook`] cancelled
+    /// it. The loop carries on after a quit event unless the callback asks
+    /// it not to.
+    Event(Event),
+    /// Time to update and draw the next frame. `dt` is the time since the
+    /// last frame, and the screen is presented once the callback returns.
+    Frame {
+        dt: Duration,
+        screen: &'a mut Screen,
+    },
+}
+
+/// Runs a loop which hands every event and frame to `callback`, until it
+/// returns [`ControlFlow::Exit`]. Returns the exit code given to it.
+///
+/// SDL is initialized and the window opened as for [`run`], and shut down
+/// again once the loop is done. This is built on [`timer::main_loop`], so on
+/// emscripten it never returns.
+pub fn run_with<F>(config: AppConfig, callback: F) -> Result<i32, AppError>
+where
+    F: FnMut(LoopEvent<'_>) -> ControlFlow + 'static,
+{
+    let (_sdl_context, _video, mut screen, mut events) = open(&config)?;
+    let mut callback = callback;
+
+    // As in `run`, the result has to be smuggled out of the loop.
+    let result = Rc::new(RefCell::new(Ok(0)));
+    let loop_result = Rc::clone(&result);
+    let mut last_frame = Instant::now();
+
+    timer::main_loop(config.fps, move || {
+        let flow = frame_with(&mut callback, &mut screen, &mut events, &mut last_frame);
+        match flow {
+            Ok(ControlFlow::Continue) => true,
+            Ok(ControlFlow::Exit(code)) => {
+                *loop_result.borrow_mut() = Ok(code);
+                false
+            }
+            Err(err) => {
+                *loop_result.borrow_mut() = Err(err);
+                false
+            }
+        }
+    })?;
+
+    result.replace(Ok(0)).map_err(AppError::from)
+}
+
+// Initializes SDL and opens the window. Everything returned has to be kept
+// around for as long as the loop runs.
+fn open(config: &AppConfig) -> Result<(SDL, VideoSubsystem, Screen, EventPump), AppError> {
+    let sdl_context = crate::init()?;
+    let video = sdl_context.video()?;
+
+    let mut builder = video.window(&config.title, config.width, config.height);
+    if config.fullscreen {
+        builder.fullscreen();
+    }
+    if config.resizable {
+        builder.resizable();
+    }
+
+    let screen = builder.build()?;
+    let mut events = video.event_pump();
+    events.coalesce_wheel(config.coalesce_wheel);
+
+    Ok((sdl_context, video, screen, events))
+}
+
+fn frame_with<F>(
+    callback: &mut F,
+    screen: &mut Screen,
+    events: &mut EventPump,
+    last_frame: &mut Instant,
+) -> sdl::Result<ControlFlow>
+where
+    F: FnMut(LoopEvent<'_>) -> ControlFlow,
+{
+    sdl::check_callbacks()?;
+
+    while let Some(event) = events.poll_event() {
+        if matches!(event, Event::Quit) && run_quit_hook() == QuitResponse::Cancel {
+            continue;
+        }
+        if let ControlFlow::Exit(code) = callback(LoopEvent::Event(event)) {
+            return Ok(ControlFlow::Exit(code));
+        }
+    }
+
+    let now = Instant::now();
+    let dt = now - *last_frame;
+    *last_frame = now;
+
+    let flow = callback(LoopEvent::Frame { dt, screen });
+    screen.present()?;
+
+    Ok(flow)
+}
+
+// What `run` keeps track of between frames.
+struct LoopState {
+    last_frame: Instant,
+    paused: bool,
+    pause_in_background: bool,
+}
+
+fn frame<A: App>(
+    app: &mut A,
+    screen: &mut Screen,
+    events: &mut EventPump,
+    state: &mut LoopState,
+) -> sdl::Result<bool> {
+    sdl::check_callbacks()?;
+
+    while let Some(event) = events.poll_event() {
+        let active_changed = match event {
+            Event::Quit => {
+                if run_quit_hook() == QuitResponse::Quit
+                    && app.quit_requested() == QuitResponse::Quit
+                {
+                    return Ok(false);
+                }
+                continue;
+            }
+            Event::Active(_) => true,
+            _ => false,
+        };
+        app.handle_event(event)?;
+
+        // A single event only reports what changed, so the whole state is
+        // checked to tell whether the app is fully in the foreground.
+        if active_changed {
+            let foreground = AppState::ACTIVE | AppState::INPUT_FOCUS;
+            let paused = !AppState::current().contains(foreground);
+            if paused && !state.paused {
+                app.on_pause()?;
+            } else if !paused && state.paused {
+                app.on_resume()?;
+                state.last_frame = Instant::now();
+            }
+            state.paused = paused;
+        }
+    }
+
+    let now = Instant::now();
+    if !(state.paused && state.pause_in_background) {
+        app.update(now - state.last_frame)?;
+    }
+    state.last_frame = now;
+
+    app.render(screen)?;
+    screen.present()?;
+
+    Ok(!app.should_quit())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AppError {
+    #[error("window error: {}", .0)]
+    WindowError(#[from] WindowBuildError),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
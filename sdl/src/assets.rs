@@ -0,0 +1,598 @@
+//! A cache for the images, sounds, music and fonts a game uses, loaded the
+//! first time they're asked for.
+//!
+//! Assets are identified by their path relative to the [`Assets`] root, and
+//! handed out as [`Rc`]s so the same asset is only ever loaded once. Sounds
+//! and music need the `mixer` feature, fonts need `ttf`, and images are
+//! loaded with SDL_image if the `image` feature is enabled, or as BMPs if not.
+
+use std::collections::HashMap;
+#[cfg(any(feature = "mixer", feature = "ttf"))]
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+#[cfg(feature = "mixer")]
+use crate::mixer::{Chunk, Music};
+use crate::sdl;
+#[cfg(feature = "ttf")]
+use crate::ttf::Font;
+use crate::video::{PixelFormatSpec, Surface};
+
+/// A font at a particular size, as listed in a [`Manifest`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontSpec {
+    pub path: String,
+    pub size: u16,
+}
+
+/// A list of assets to load up front with [`Assets::preload`].
+///
+/// With the `json` or `toml` feature enabled this can be loaded from a file,
+/// for example:
+///
+/// ```toml
+/// images = ["player.png", "tiles.png"]
+/// sounds = ["jump.wav"]
+/// fonts = [{ path = "font.ttf", size = 16 }]
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manifest {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub images: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sounds: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub music: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fonts: Vec<FontSpec>,
+}
+
+impl Manifest {
+    #[cfg(feature = "json")]
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Manifest, AssetError> {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(|err| AssetError::InvalidManifest(err.to_string()))
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Manifest, AssetError> {
+        let data = std::fs::read_to_string(path)?;
+        toml::from_str(&data).map_err(|err| AssetError::InvalidManifest(err.to_string()))
+    }
+}
+
+/// Loads assets on demand and keeps them around for as long as they're used.
+///
+/// Cached assets stay loaded until [`purge`](Assets::purge) drops the ones
+/// nothing else holds on to, or [`clear`](Assets::clear) drops them all.
+#[derive(Debug)]
+pub struct Assets {
+    root: PathBuf,
+    convert_images: bool,
+    cache_display_format: bool,
+    images: HashMap<String, Rc<Surface>>,
+    // Copies of `images` in the screen's format, when caching them.
+    converted: HashMap<String, Rc<Surface>>,
+    // The screen format `converted` was made for.
+    converted_for: Option<PixelFormatSpec>,
+    #[cfg(feature = "mixer")]
+    sounds: HashMap<String, Rc<Chunk>>,
+    #[cfg(feature = "mixer")]
+    music: HashMap<String, Rc<Music>>,
+    #[cfg(feature = "ttf")]
+    fonts: HashMap<FontSpec, Rc<Font>>,
+}
+
+impl Assets {
+    /// Creates an empty cache which loads assets from paths relative to
+    /// `root`.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Assets {
+        Assets {
+            root: root.into(),
+            convert_images: true,
+            cache_display_format: false,
+            images: HashMap::new(),
+            converted: HashMap::new(),
+            converted_for: None,
+            #[cfg(feature = "mixer")]
+            sounds: HashMap::new(),
+            #[cfg(feature = "mixer")]
+            music: HashMap::new(),
+            #[cfg(feature = "ttf")]
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// Whether images are converted to the screen's format as they're
+    /// loaded, with [`Surface::to_display_format`]. This is on by default,
+    /// and needs the video mode to be set before any images are loaded.
+    pub fn convert_images(&mut self, convert: bool) -> &mut Assets {
+        self.convert_images = convert;
+        self
+    }
+
+    /// Keeps images as they were loaded, and caches copies of them in the
+    /// screen's format separately. Whenever the screen's format changes,
+    /// such as after switching to fullscreen at a different depth, the
+    /// copies are thrown away and made again from the originals as they're
+    /// asked for, so they're never left in the old format.
+    ///
+    /// Both the original and the copy of each image are kept, so this uses
+    /// more memory. Images handed out before the change aren't affected.
+    /// This takes priority over [`convert_images`](Assets::convert_images).
+    pub fn cache_display_format(&mut self, enabled: bool) -> &mut Assets {
+        self.cache_display_format = enabled;
+        self.converted.clear();
+        self.converted_for = None;
+        self
+    }
+
+    pub fn image(&mut self, path: &str) -> Result<Rc<Surface>, AssetError> {
+        if !self.cache_display_format {
+            return self.load_image(path, self.convert_images);
+        }
+
+        let format = PixelFormatSpec::display();
+        if format != self.converted_for {
+            self.converted.clear();
+            self.converted_for = format;
+        }
+        if let Some(image) = self.converted.get(path) {
+            return Ok(Rc::clone(image));
+        }
+
+        let original = self.load_image(path, false)?;
+        let full_path = self.root.join(path);
+        get_or_load(&mut self.converted, path.to_owned(), || {
+            original
+                .to_display_format()
+                .map_err(|err| load_error(&full_path, err))
+        })
+    }
+
+    fn load_image(&mut self, path: &str, convert: bool) -> Result<Rc<Surface>, AssetError> {
+        let full_path = self.root.join(path);
+        get_or_load(&mut self.images, path.to_owned(), || {
+            #[cfg(feature = "image")]
+            let surface = crate::image::load(&full_path);
+            #[cfg(not(feature = "image"))]
+            let surface = Surface::load_bmp(&full_path);
+
+            let surface = surface.map_err(|err| load_error(&full_path, err))?;
+            finish_image(surface, convert, &full_path)
+        })
+    }
+
+    // Adds an image a `Loader` decoded, converting it as `image` would have.
+    fn insert_image(&mut self, path: &str, surface: Surface) -> Result<(), AssetError> {
+        let full_path = self.root.join(path);
+        let convert = self.convert_images && !self.cache_display_format;
+        get_or_load(&mut self.images, path.to_owned(), || {
+            finish_image(surface, convert, &full_path)
+        })?;
+        Ok(())
+    }
+
+    #[cfg(feature = "mixer")]
+    fn insert_sound(&mut self, path: &str, data: &[u8]) -> Result<(), AssetError> {
+        let full_path = self.root.join(path);
+        get_or_load(&mut self.sounds, path.to_owned(), || {
+            Chunk::load_from_memory(data).map_err(|err| load_error(&full_path, err))
+        })?;
+        Ok(())
+    }
+
+    #[cfg(feature = "mixer")]
+    pub fn sound(&mut self, path: &str) -> Result<Rc<Chunk>, AssetError> {
+        let full_path = self.root.join(path);
+        get_or_load(&mut self.sounds, path.to_owned(), || {
+            Chunk::load(&full_path).map_err(|err| load_error(&full_path, err))
+        })
+    }
+
+    #[cfg(feature = "mixer")]
+    pub fn music(&mut self, path: &str) -> Result<Rc<Music>, AssetError> {
+        let full_path = self.root.join(path);
+        get_or_load(&mut self.music, path.to_owned(), || {
+            Music::load(&full_path).map_err(|err| load_error(&full_path, err))
+        })
+    }
+
+    /// Returns the font at `path` in the given size. Each size is loaded
+    /// separately.
+    #[cfg(feature = "ttf")]
+    pub fn font(&mut self, path: &str, size: u16) -> Result<Rc<Font>, AssetError> {
+        let full_path = self.root.join(path);
+        let spec = FontSpec {
+            path: path.to_owned(),
+            size,
+        };
+        get_or_load(&mut self.fonts, spec, || {
+            Font::open(&full_path, size).map_err(|err| load_error(&full_path, err))
+        })
+    }
+
+    /// Loads everything in `manifest` which isn't loaded yet, stopping at
+    /// the first failure.
+    pub fn preload(&mut self, manifest: &Manifest) -> Result<(), AssetError> {
+        for path in &manifest.images {
+            self.image(path)?;
+        }
+
+        #[cfg(feature = "mixer")]
+        {
+            for path in &manifest.sounds {
+                self.sound(path)?;
+            }
+            for path in &manifest.music {
+                self.music(path)?;
+            }
+        }
+        #[cfg(not(feature = "mixer"))]
+        if !manifest.sounds.is_empty() || !manifest.music.is_empty() {
+            return Err(AssetError::Unsupported(
+                "sounds and music need the mixer feature",
+            ));
+        }
+
+        #[cfg(feature = "ttf")]
+        for font in &manifest.fonts {
+            self.font(&font.path, font.size)?;
+        }
+        #[cfg(not(feature = "ttf"))]
+        if !manifest.fonts.is_empty() {
+            return Err(AssetError::Unsupported("fonts need the ttf feature"));
+        }
+
+        Ok(())
+    }
+
+    /// Drops every asset which is only held by the cache, and returns how
+    /// many were dropped.
+    pub fn purge(&mut self) -> usize {
+        #[allow(unused_mut)]
+        let mut purged = purge_unused(&mut self.converted);
+        purged += purge_unused(&mut self.images);
+        #[cfg(feature = "mixer")]
+        {
+            purged += purge_unused(&mut self.sounds);
+            purged += purge_unused(&mut self.music);
+        }
+        #[cfg(feature = "ttf")]
+        {
+            purged += purge_unused(&mut self.fonts);
+        }
+        purged
+    }
+
+    /// Drops the cache's references to every asset. Assets which are still
+    /// in use elsewhere stay loaded until they're dropped there too.
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.converted.clear();
+        #[cfg(feature = "mixer")]
+        {
+            self.sounds.clear();
+            self.music.clear();
+        }
+        #[cfg(feature = "ttf")]
+        self.fonts.clear();
+    }
+}
+
+fn finish_image(surface: Surface, convert: bool, path: &Path) -> Result<Surface, AssetError> {
+    if convert {
+        surface
+            .to_display_format()
+            .map_err(|err| load_error(path, err))
+    } else {
+        Ok(surface)
+    }
+}
+
+fn get_or_load<K, T, F>(cache: &mut HashMap<K, Rc<T>>, key: K, load: F) -> Result<Rc<T>, AssetError>
+where
+    K: Eq + Hash,
+    F: FnOnce() -> Result<T, AssetError>,
+{
+    if let Some(asset) = cache.get(&key) {
+        return Ok(Rc::clone(asset));
+    }
+
+    let asset = Rc::new(load()?);
+    cache.insert(key, Rc::clone(&asset));
+    Ok(asset)
+}
+
+fn purge_unused<K, T>(cache: &mut HashMap<K, Rc<T>>) -> usize {
+    let before = cache.len();
+    cache.retain(|_, asset| Rc::strong_count(asset) > 1);
+    before - cache.len()
+}
+
+fn load_error(path: &Path, source: sdl::Error) -> AssetError {
+    AssetError::LoadFailed {
+        path: path.to_owned(),
+        source,
+    }
+}
+
+/// Loads assets into an [`Assets`] cache on background threads, so a load
+/// screen can keep drawing while it waits.
+///
+/// SDL 1.2 isn't thread safe, so the worker threads only read the files,
+/// and decode images into pixels with the `image` crate if the
+/// `image-crate` feature is enabled. Creating the surfaces and sounds, and
+/// converting them to the screen's format, is left to
+/// [`update`](Loader::update), which is called on the main thread. Music and
+/// fonts are read from their files as they're used, so they're opened by
+/// `update` directly.
+///
+/// Dropping the loader stops the workers once they're done with the file
+/// they're reading.
+#[derive(Debug)]
+pub struct Loader {
+    jobs: mpsc::Sender<Job>,
+    results: mpsc::Receiver<Loaded>,
+    // Assets which are opened on the main thread, in the order queued.
+    #[cfg(any(feature = "mixer", feature = "ttf"))]
+    deferred: VecDeque<Deferred>,
+    queued: usize,
+    finished: usize,
+}
+
+#[derive(Debug)]
+enum Job {
+    Image(String),
+    #[cfg(feature = "mixer")]
+    Sound(String),
+}
+
+#[derive(Debug)]
+struct Loaded {
+    path: String,
+    data: std::io::Result<Data>,
+}
+
+#[derive(Debug)]
+enum Data {
+    Image(Vec<u8>),
+    #[cfg(feature = "image-crate")]
+    Pixels(image_crate::RgbaImage),
+    #[cfg(feature = "mixer")]
+    Sound(Vec<u8>),
+}
+
+#[cfg(any(feature = "mixer", feature = "ttf"))]
+#[derive(Debug)]
+enum Deferred {
+    #[cfg(feature = "mixer")]
+    Music(String),
+    #[cfg(feature = "ttf")]
+    Font(FontSpec),
+}
+
+impl Loader {
+    /// Starts `threads` worker threads, which load assets from the same root
+    /// as `assets`.
+    pub fn new(assets: &Assets, threads: usize) -> Result<Loader, AssetError> {
+        let (jobs, job_receiver) = mpsc::channel();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for _ in 0..threads.max(1) {
+            let root = assets.root.clone();
+            let jobs = Arc::clone(&job_receiver);
+            let results = result_sender.clone();
+            thread::Builder::new()
+                .name("asset-loader".to_owned())
+                .spawn(move || work(&root, &jobs, &results))?;
+        }
+
+        Ok(Loader {
+            jobs,
+            results,
+            #[cfg(any(feature = "mixer", feature = "ttf"))]
+            deferred: VecDeque::new(),
+            queued: 0,
+            finished: 0,
+        })
+    }
+
+    pub fn queue_image(&mut self, path: &str) -> &mut Loader {
+        self.send(Job::Image(path.to_owned()))
+    }
+
+    #[cfg(feature = "mixer")]
+    pub fn queue_sound(&mut self, path: &str) -> &mut Loader {
+        self.send(Job::Sound(path.to_owned()))
+    }
+
+    #[cfg(feature = "mixer")]
+    pub fn queue_music(&mut self, path: &str) -> &mut Loader {
+        self.defer(Deferred::Music(path.to_owned()))
+    }
+
+    #[cfg(feature = "ttf")]
+    pub fn queue_font(&mut self, path: &str, size: u16) -> &mut Loader {
+        self.defer(Deferred::Font(FontSpec {
+            path: path.to_owned(),
+            size,
+        }))
+    }
+
+    /// Queues everything in `manifest`. Like [`Assets::preload`], this fails
+    /// if the manifest lists assets the enabled features can't load.
+    pub fn queue_manifest(&mut self, manifest: &Manifest) -> Result<(), AssetError> {
+        #[cfg(not(feature = "mixer"))]
+        if !manifest.sounds.is_empty() || !manifest.music.is_empty() {
+            return Err(AssetError::Unsupported(
+                "sounds and music need the mixer feature",
+            ));
+        }
+        #[cfg(not(feature = "ttf"))]
+        if !manifest.fonts.is_empty() {
+            return Err(AssetError::Unsupported("fonts need the ttf feature"));
+        }
+
+        for path in &manifest.images {
+            self.queue_image(path);
+        }
+        #[cfg(feature = "mixer")]
+        {
+            for path in &manifest.sounds {
+                self.queue_sound(path);
+            }
+            for path in &manifest.music {
+                self.queue_music(path);
+            }
+        }
+        #[cfg(feature = "ttf")]
+        for font in &manifest.fonts {
+            self.queue_font(&font.path, font.size);
+        }
+        Ok(())
+    }
+
+    /// Finishes loading whatever the workers have ready into `assets`, along
+    /// with one music track or font, without waiting for anything else.
+    /// Returns how many assets were finished.
+    ///
+    /// This stops at the first failure, which counts as finished, so the
+    /// rest are picked up by the next call.
+    pub fn update(&mut self, assets: &mut Assets) -> Result<usize, AssetError> {
+        let before = self.finished;
+
+        #[cfg(any(feature = "mixer", feature = "ttf"))]
+        if let Some(deferred) = self.deferred.pop_front() {
+            self.finished += 1;
+            match deferred {
+                #[cfg(feature = "mixer")]
+                Deferred::Music(path) => {
+                    assets.music(&path)?;
+                }
+                #[cfg(feature = "ttf")]
+                Deferred::Font(font) => {
+                    assets.font(&font.path, font.size)?;
+                }
+            }
+        }
+
+        while let Ok(loaded) = self.results.try_recv() {
+            self.finished += 1;
+            let data = loaded.data.map_err(|source| AssetError::ReadFailed {
+                path: assets.root.join(&loaded.path),
+                source,
+            })?;
+            match data {
+                Data::Image(data) => {
+                    #[cfg(feature = "image")]
+                    let surface = crate::image::load_from_memory(&data);
+                    #[cfg(not(feature = "image"))]
+                    let surface = Surface::load_bmp_from_memory(&data);
+
+                    let surface =
+                        surface.map_err(|err| load_error(&assets.root.join(&loaded.path), err))?;
+                    assets.insert_image(&loaded.path, surface)?;
+                }
+                #[cfg(feature = "image-crate")]
+                Data::Pixels(image) => {
+                    let surface = crate::interop::image::from_rgba_image(&image)
+                        .map_err(|err| load_error(&assets.root.join(&loaded.path), err))?;
+                    assets.insert_image(&loaded.path, surface)?;
+                }
+                #[cfg(feature = "mixer")]
+                Data::Sound(data) => assets.insert_sound(&loaded.path, &data)?,
+            }
+        }
+
+        Ok(self.finished - before)
+    }
+
+    /// How many assets have been finished, and how many were queued.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.finished, self.queued)
+    }
+
+    /// Whether everything queued has been finished.
+    pub fn is_done(&self) -> bool {
+        self.finished == self.queued
+    }
+
+    fn send(&mut self, job: Job) -> &mut Loader {
+        // The workers only stop once the loader is dropped, so this can't
+        // fail.
+        let _ = self.jobs.send(job);
+        self.queued += 1;
+        self
+    }
+
+    #[cfg(any(feature = "mixer", feature = "ttf"))]
+    fn defer(&mut self, deferred: Deferred) -> &mut Loader {
+        self.deferred.push_back(deferred);
+        self.queued += 1;
+        self
+    }
+}
+
+fn work(root: &Path, jobs: &Mutex<mpsc::Receiver<Job>>, results: &mpsc::Sender<Loaded>) {
+    loop {
+        // Only one worker waits for a job at a time, and the lock is let go
+        // before reading the file so the others can carry on.
+        let job = match jobs.lock() {
+            Ok(jobs) => match jobs.recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+
+        let loaded = match job {
+            Job::Image(path) => Loaded {
+                data: std::fs::read(root.join(&path)).map(decode_image),
+                path,
+            },
+            #[cfg(feature = "mixer")]
+            Job::Sound(path) => Loaded {
+                data: std::fs::read(root.join(&path)).map(Data::Sound),
+                path,
+            },
+        };
+        if results.send(loaded).is_err() {
+            return;
+        }
+    }
+}
+
+// Decodes the image if the `image` crate can, leaving anything else to SDL
+// on the main thread.
+fn decode_image(data: Vec<u8>) -> Data {
+    #[cfg(feature = "image-crate")]
+    if let Ok(image) = image_crate::load_from_memory(&data) {
+        return Data::Pixels(image.into_rgba8());
+    }
+    Data::Image(data)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AssetError {
+    #[error("failed to load {}: {}", .path.display(), .source)]
+    LoadFailed { path: PathBuf, source: sdl::Error },
+    #[error("invalid asset manifest: {}", .0)]
+    InvalidManifest(String),
+    #[error("unsupported asset: {}", .0)]
+    Unsupported(&'static str),
+    #[error("failed to read {}: {}", .path.display(), .source)]
+    ReadFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("IO error: {}", .0)]
+    IoError(#[from] std::io::Error),
+}
@@ -1,13 +1,25 @@
+use std::cell::UnsafeCell;
+use std::ffi::{c_int, c_void};
+use std::fmt;
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use sys::SDL_InitSubSystem;
 
 use crate::sys;
 use crate::sdl;
+use crate::SDL;
 
 #[derive(Debug)]
 pub struct Subsystem {
     _pinned: std::marker::PhantomPinned,
+    _not_send: sdl::NotSend,
 }
 
 impl Drop for Subsystem {
@@ -25,7 +37,526 @@ impl Subsystem {
         } else {
             Ok(Subsystem {
                 _pinned: PhantomPinned,
+                _not_send: PhantomData,
             })
         }
     }
 }
+
+impl SDL {
+    pub fn audio(&self) -> sdl::Result<Subsystem> {
+        Subsystem::new()
+    }
+}
+
+/// The format of audio samples, matching the `AUDIO_*` constants.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AudioFormat {
+    U8,
+    S8,
+    U16Lsb,
+    S16Lsb,
+    U16Msb,
+    S16Msb,
+}
+
+impl AudioFormat {
+    /// Unsigned 16-bit samples in the byte order of this machine.
+    pub const U16_SYS: AudioFormat = if cfg!(target_endian = "little") {
+        AudioFormat::U16Lsb
+    } else {
+        AudioFormat::U16Msb
+    };
+
+    /// Signed 16-bit samples in the byte order of this machine, which is
+    /// what SDL_mixer uses by default.
+    pub const S16_SYS: AudioFormat = if cfg!(target_endian = "little") {
+        AudioFormat::S16Lsb
+    } else {
+        AudioFormat::S16Msb
+    };
+
+    pub fn from_raw(raw: u16) -> Option<AudioFormat> {
+        let format = match raw as u32 {
+            sys::AUDIO_U8 => AudioFormat::U8,
+            sys::AUDIO_S8 => AudioFormat::S8,
+            sys::AUDIO_U16LSB => AudioFormat::U16Lsb,
+            sys::AUDIO_S16LSB => AudioFormat::S16Lsb,
+            sys::AUDIO_U16MSB => AudioFormat::U16Msb,
+            sys::AUDIO_S16MSB => AudioFormat::S16Msb,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    pub fn raw(self) -> u16 {
+        let raw = match self {
+            AudioFormat::U8 => sys::AUDIO_U8,
+            AudioFormat::S8 => sys::AUDIO_S8,
+            AudioFormat::U16Lsb => sys::AUDIO_U16LSB,
+            AudioFormat::S16Lsb => sys::AUDIO_S16LSB,
+            AudioFormat::U16Msb => sys::AUDIO_U16MSB,
+            AudioFormat::S16Msb => sys::AUDIO_S16MSB,
+        };
+        raw as u16
+    }
+
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            AudioFormat::U8 | AudioFormat::S8 => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// The frequency, sample format and number of channels audio is played at.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioSpec {
+    /// Samples per second, per channel.
+    pub frequency: i32,
+    pub format: AudioFormat,
+    /// 1 for mono, 2 for stereo.
+    pub channels: u8,
+}
+
+impl AudioSpec {
+    pub const fn new(frequency: i32, format: AudioFormat, channels: u8) -> AudioSpec {
+        AudioSpec {
+            frequency,
+            format,
+            channels,
+        }
+    }
+}
+
+/// What to do when the audio device can't be opened with the spec which was
+/// asked for. SDL picks the closest spec the hardware supports, and sounds
+/// played at the wrong frequency come out too fast or too slow.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum SpecPolicy {
+    /// Use whatever SDL picked. Check the obtained spec to find out what
+    /// that is.
+    #[default]
+    Accept,
+    /// Fail unless the spec is exactly the one asked for.
+    Exact,
+}
+
+/// A type of sample an [`AudioCallback`] can fill its buffer with. It's
+/// implemented for `u8`, `i8`, `u16` and `i16`, with 16-bit samples in the
+/// byte order of this machine.
+pub trait AudioSample: Copy + Send + sealed::Sealed + 'static {
+    const FORMAT: AudioFormat;
+    /// The value of a sample at rest.
+    const SILENCE: Self;
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for i8 {}
+    impl Sealed for u16 {}
+    impl Sealed for i16 {}
+}
+
+impl AudioSample for u8 {
+    const FORMAT: AudioFormat = AudioFormat::U8;
+    const SILENCE: u8 = 0x80;
+}
+
+impl AudioSample for i8 {
+    const FORMAT: AudioFormat = AudioFormat::S8;
+    const SILENCE: i8 = 0;
+}
+
+impl AudioSample for u16 {
+    const FORMAT: AudioFormat = AudioFormat::U16_SYS;
+    const SILENCE: u16 = 0x8000;
+}
+
+impl AudioSample for i16 {
+    const FORMAT: AudioFormat = AudioFormat::S16_SYS;
+    const SILENCE: i16 = 0;
+}
+
+/// Generates audio for an [`AudioDevice`].
+///
+/// The callback runs on SDL's audio thread, so it should be quick and
+/// mustn't block, or the sound skips. Use [`AudioDevice::lock`] to change
+/// its state from the main thread.
+pub trait AudioCallback: Send + 'static {
+    type Sample: AudioSample;
+
+    /// Fills `out` with the next samples to play. Samples of each channel
+    /// are interleaved, so for stereo it's left, right, left and so on.
+    fn callback(&mut self, out: &mut [Self::Sample]);
+}
+
+/// What to ask for when opening an [`AudioDevice`]. The sample format comes
+/// from the callback's [`Sample`](AudioCallback::Sample) type.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct AudioSpecDesired {
+    /// Samples per second, per channel.
+    pub frequency: i32,
+    /// 1 for mono, 2 for stereo.
+    pub channels: u8,
+    /// The size of the buffer the callback fills each time, in samples per
+    /// channel, which has to be a power of 2. Smaller buffers lower
+    /// latency, but can cause skips on slow machines.
+    pub samples: u16,
+}
+
+impl AudioSpecDesired {
+    /// A spec with a buffer of 1024 samples.
+    pub const fn new(frequency: i32, channels: u8) -> AudioSpecDesired {
+        AudioSpecDesired {
+            frequency,
+            channels,
+            samples: 1024,
+        }
+    }
+
+    pub const fn with_samples(self, samples: u16) -> AudioSpecDesired {
+        AudioSpecDesired { samples, ..self }
+    }
+}
+
+/// Whether an [`AudioDevice`] is playing, see [`AudioDevice::status`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AudioStatus {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+/// The audio device, opened to play whatever an [`AudioCallback`]
+/// generates, as returned by [`AudioDevice::open`]. Audio is closed again
+/// when this is dropped.
+///
+/// SDL 1.2 only has the one device, so this can't be open at the same time
+/// as the [`mixer`](crate::mixer), or another `AudioDevice`.
+#[derive(Debug)]
+pub struct AudioDevice<C: AudioCallback> {
+    spec: AudioSpec,
+    samples: u16,
+    // Owned by this, and shared with the audio thread until audio is
+    // closed.
+    callback: *mut C,
+    _not_send: sdl::NotSend,
+}
+
+impl<C: AudioCallback> AudioDevice<C> {
+    /// Opens the audio device, initializing the audio subsystem if it isn't
+    /// already. It starts out paused, so call
+    /// [`resume`](AudioDevice::resume) once the callback is ready to play.
+    ///
+    /// SDL converts the audio if the hardware doesn't support `desired`, so
+    /// the callback always gets exactly the spec it asked for.
+    pub fn open(desired: AudioSpecDesired, callback: C) -> sdl::Result<AudioDevice<C>> {
+        let callback = Box::into_raw(Box::new(callback));
+        let mut raw = sys::SDL_AudioSpec {
+            freq: desired.frequency as c_int,
+            format: C::Sample::FORMAT.raw(),
+            channels: desired.channels,
+            silence: 0,
+            samples: desired.samples,
+            padding: 0,
+            size: 0,
+            callback: Some(audio_callback_trampoline::<C>),
+            userdata: callback as *mut c_void,
+        };
+        if unsafe { sys::SDL_OpenAudio(&mut raw, ptr::null_mut()) } != 0 {
+            drop(unsafe { Box::from_raw(callback) });
+            return Err(sdl::get_error());
+        }
+
+        Ok(AudioDevice {
+            spec: AudioSpec::new(desired.frequency, C::Sample::FORMAT, desired.channels),
+            samples: raw.samples,
+            callback,
+            _not_send: PhantomData,
+        })
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    /// The size of the buffer the callback fills each time, in samples per
+    /// channel.
+    pub fn buffer_samples(&self) -> u16 {
+        self.samples
+    }
+
+    pub fn status(&self) -> AudioStatus {
+        match unsafe { sys::SDL_GetAudioStatus() } {
+            sys::SDL_AudioStatus::SDL_AUDIO_STOPPED => AudioStatus::Stopped,
+            sys::SDL_AudioStatus::SDL_AUDIO_PLAYING => AudioStatus::Playing,
+            sys::SDL_AudioStatus::SDL_AUDIO_PAUSED => AudioStatus::Paused,
+        }
+    }
+
+    /// Starts calling the callback to play audio.
+    pub fn resume(&self) {
+        unsafe { sys::SDL_PauseAudio(0) }
+    }
+
+    /// Stops calling the callback, playing silence instead.
+    pub fn pause(&self) {
+        unsafe { sys::SDL_PauseAudio(1) }
+    }
+
+    /// Locks out the audio thread, and gives access to the callback until
+    /// the returned guard is dropped. Keep it short, since the sound skips
+    /// if the callback can't run in time.
+    ///
+    /// Fails if the callback has panicked, see [`sdl::check_callbacks`].
+    pub fn lock(&mut self) -> sdl::Result<AudioDeviceLock<'_, C>> {
+        sdl::check_callbacks()?;
+        unsafe { sys::SDL_LockAudio() };
+        Ok(AudioDeviceLock { device: self })
+    }
+
+    /// Closes audio, and hands back the callback.
+    pub fn close_and_get_callback(self) -> C {
+        unsafe { sys::SDL_CloseAudio() };
+        let callback = unsafe { Box::from_raw(self.callback) };
+        mem::forget(self);
+        *callback
+    }
+}
+
+impl<C: AudioCallback> Drop for AudioDevice<C> {
+    fn drop(&mut self) {
+        // Closing waits for the audio thread to finish, so the callback can
+        // be freed after.
+        unsafe {
+            sys::SDL_CloseAudio();
+            drop(Box::from_raw(self.callback));
+        }
+    }
+}
+
+/// Access to the callback of an [`AudioDevice`], with the audio thread
+/// locked out, as returned by [`AudioDevice::lock`]. It derefs to the
+/// callback.
+#[derive(Debug)]
+pub struct AudioDeviceLock<'a, C: AudioCallback> {
+    device: &'a mut AudioDevice<C>,
+}
+
+impl<C: AudioCallback> Deref for AudioDeviceLock<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        unsafe { &*self.device.callback }
+    }
+}
+
+impl<C: AudioCallback> DerefMut for AudioDeviceLock<'_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        unsafe { &mut *self.device.callback }
+    }
+}
+
+impl<C: AudioCallback> Drop for AudioDeviceLock<'_, C> {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_UnlockAudio() }
+    }
+}
+
+unsafe extern "C" fn audio_callback_trampoline<C: AudioCallback>(
+    userdata: *mut c_void,
+    stream: *mut u8,
+    len: c_int,
+) {
+    let callback = &mut *(userdata as *mut C);
+    let len = len as usize / mem::size_of::<C::Sample>();
+    let out = slice::from_raw_parts_mut(stream as *mut C::Sample, len);
+    // A panic leaves the buffer half written, so it's silenced instead.
+    if sdl::catch_callback_panic(|| callback.callback(out)).is_none() {
+        out.fill(C::Sample::SILENCE);
+    }
+}
+
+/// Plays samples pushed to it from the game thread, rather than generated
+/// in a callback, like SDL 2's `SDL_QueueAudio`.
+///
+/// Samples go through a fixed size ring buffer, which the audio thread
+/// reads from without locking. Once it runs dry, silence is played until
+/// more samples are queued.
+#[derive(Debug)]
+pub struct AudioQueue<T: AudioSample> {
+    device: AudioDevice<QueueCallback<T>>,
+    ring: Arc<Ring<T>>,
+}
+
+impl<T: AudioSample> AudioQueue<T> {
+    /// Opens the audio device, paused, with room for `capacity` samples
+    /// queued up. Like the buffer, that counts each channel's samples
+    /// separately, so a second of 44100 Hz stereo takes 88200. It's rounded
+    /// up to a power of two.
+    pub fn open(desired: AudioSpecDesired, capacity: usize) -> sdl::Result<AudioQueue<T>> {
+        let ring = Arc::new(Ring::new(capacity));
+        let callback = QueueCallback { ring: ring.clone() };
+        Ok(AudioQueue {
+            device: AudioDevice::open(desired, callback)?,
+            ring,
+        })
+    }
+
+    /// Adds `samples` to the end of the queue, and returns how many were
+    /// added, which is fewer than given if the queue fills up.
+    pub fn queue(&self, samples: &[T]) -> usize {
+        self.ring.push(samples)
+    }
+
+    /// The number of samples waiting to be played.
+    pub fn queued_len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// The most samples which can be queued at once.
+    pub fn capacity(&self) -> usize {
+        self.ring.slots.len()
+    }
+
+    /// Drops every sample waiting to be played.
+    pub fn clear(&mut self) -> sdl::Result<()> {
+        // Only the audio thread moves the read position, so it's locked out
+        // while it's moved here instead.
+        let _lock = self.device.lock()?;
+        self.ring
+            .read
+            .store(self.ring.write.load(Ordering::Acquire), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.device.spec()
+    }
+
+    pub fn status(&self) -> AudioStatus {
+        self.device.status()
+    }
+
+    pub fn resume(&self) {
+        self.device.resume()
+    }
+
+    pub fn pause(&self) {
+        self.device.pause()
+    }
+}
+
+// A ring buffer with one writer, the game thread, and one reader, the audio
+// thread. The positions only ever count up, wrapping around, and are masked
+// to index the slots. The capacity is a power of two so that still picks the
+// right slot when they wrap past `usize::MAX`.
+struct Ring<T> {
+    slots: Box<[UnsafeCell<T>]>,
+    read: AtomicUsize,
+    write: AtomicUsize,
+}
+
+// The reader and writer never touch the same slots at once, since each only
+// moves its own position, and only past slots the other is done with.
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T: AudioSample> Ring<T> {
+    fn new(capacity: usize) -> Ring<T> {
+        Ring {
+            slots: (0..capacity.next_power_of_two())
+                .map(|_| UnsafeCell::new(T::SILENCE))
+                .collect(),
+            read: AtomicUsize::new(0),
+            write: AtomicUsize::new(0),
+        }
+    }
+
+    fn len(&self) -> usize {
+        let write = self.write.load(Ordering::Acquire);
+        write.wrapping_sub(self.read.load(Ordering::Acquire))
+    }
+
+    fn slot(&self, pos: usize) -> &UnsafeCell<T> {
+        &self.slots[pos & (self.slots.len() - 1)]
+    }
+
+    fn push(&self, samples: &[T]) -> usize {
+        let write = self.write.load(Ordering::Relaxed);
+        let read = self.read.load(Ordering::Acquire);
+        let count = samples
+            .len()
+            .min(self.slots.len() - write.wrapping_sub(read));
+        for (i, &sample) in samples[..count].iter().enumerate() {
+            unsafe { *self.slot(write.wrapping_add(i)).get() = sample };
+        }
+        self.write
+            .store(write.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    // Fills `out` with queued samples, and the rest with silence.
+    fn pop_into(&self, out: &mut [T]) {
+        let read = self.read.load(Ordering::Relaxed);
+        let write = self.write.load(Ordering::Acquire);
+        let count = out.len().min(write.wrapping_sub(read));
+        for (i, sample) in out[..count].iter_mut().enumerate() {
+            *sample = unsafe { *self.slot(read.wrapping_add(i)).get() };
+        }
+        out[count..].fill(T::SILENCE);
+        self.read.store(read.wrapping_add(count), Ordering::Release);
+    }
+}
+
+impl<T> fmt::Debug for Ring<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ring")
+            .field("capacity", &self.slots.len())
+            .field("read", &self.read)
+            .field("write", &self.write)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct QueueCallback<T> {
+    ring: Arc<Ring<T>>,
+}
+
+impl<T: AudioSample> AudioCallback for QueueCallback<T> {
+    type Sample = T;
+
+    fn callback(&mut self, out: &mut [T]) {
+        self.ring.pop_into(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_wraps_past_usize_max() {
+        let ring = Ring::<i16>::new(3);
+        assert_eq!(ring.slots.len(), 4);
+        ring.read.store(usize::MAX - 2, Ordering::Relaxed);
+        ring.write.store(usize::MAX - 2, Ordering::Relaxed);
+
+        assert_eq!(ring.push(&[1, 2, 3, 4, 5]), 4);
+        let mut out = [0; 2];
+        ring.pop_into(&mut out);
+        assert_eq!(out, [1, 2]);
+
+        assert_eq!(ring.push(&[6, 7, 8]), 2);
+        assert_eq!(ring.len(), 4);
+        let mut out = [-1; 6];
+        ring.pop_into(&mut out);
+        assert_eq!(out, [3, 4, 6, 7, 0, 0]);
+        assert_eq!(ring.len(), 0);
+    }
+}
@@ -0,0 +1,214 @@
+//! Working out how SDL will blit one surface to another, for finding out why
+//! drawing is slower than expected.
+//!
+//! SDL 1.2 picks a blitter based on where both surfaces live, whether their
+//! formats match, and the color key and alpha settings of the source. Some
+//! combinations are much slower than others, and nothing says which one was
+//! picked. [`BlitReport::new`] makes the same decisions SDL does and explains
+//! them.
+
+use std::fmt;
+
+use crate::sys;
+use crate::sys::SDL_SurfaceFlags as Flags;
+use crate::video::{PixelFormat, Surface};
+
+/// The way SDL blits a surface, from fastest to slowest, roughly.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BlitPath {
+    /// The video hardware does the blit.
+    Hardware,
+    /// Rows are copied as they are, since the formats match and there's no
+    /// color key or alpha.
+    Copy,
+    /// Each pixel is converted to the destination's format as it's copied.
+    Converted,
+    /// Runs of transparent pixels are skipped using the RLE encoded surface.
+    RleColorKey,
+    /// Each pixel is compared against the color key.
+    ColorKey,
+    /// Pixels are blended using RLE encoding to skip fully transparent and
+    /// copy fully opaque runs.
+    RleAlpha,
+    /// Every pixel is blended with the same per-surface alpha.
+    SurfaceAlpha,
+    /// Every pixel is blended with its own alpha, in software.
+    PixelAlpha,
+}
+
+impl BlitPath {
+    /// Whether the blit is done in software by blending pixels, which is the
+    /// usual reason for a slow frame rate.
+    pub fn is_blended(self) -> bool {
+        matches!(
+            self,
+            BlitPath::RleAlpha | BlitPath::SurfaceAlpha | BlitPath::PixelAlpha
+        )
+    }
+}
+
+impl fmt::Display for BlitPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BlitPath::Hardware => "hardware accelerated",
+            BlitPath::Copy => "straight copy",
+            BlitPath::Converted => "format conversion",
+            BlitPath::RleColorKey => "RLE color key",
+            BlitPath::ColorKey => "color key",
+            BlitPath::RleAlpha => "RLE alpha blend",
+            BlitPath::SurfaceAlpha => "per-surface alpha blend",
+            BlitPath::PixelAlpha => "per-pixel alpha blend",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What SDL will do to blit one surface to another, and what could make it
+/// faster. Its [`Display`](fmt::Display) output is meant to be logged.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BlitReport {
+    /// The blitter SDL will use.
+    pub path: BlitPath,
+    /// Whether pixels are converted between formats along the way.
+    pub converts_format: bool,
+    /// Whether the source is, or will be on its first blit, RLE encoded.
+    pub rle: bool,
+    /// Whether both surfaces are in video memory.
+    pub both_hardware: bool,
+    /// Suggestions for making the blit faster, if there are any.
+    pub hints: Vec<&'static str>,
+    /// The size, format and flags of the source.
+    pub source: String,
+    /// The size, format and flags of the destination.
+    pub destination: String,
+}
+
+impl BlitReport {
+    /// Works out how blitting `src` to `dst` will be done. The screen is the
+    /// usual `dst`.
+    pub fn new(src: &Surface, dst: &Surface) -> BlitReport {
+        let src_flags = surface_flags(src);
+        let dst_flags = surface_flags(dst);
+        let keyed = src_flags.contains(Flags::SRCCOLORKEY);
+        let alpha = src_flags.contains(Flags::SRCALPHA);
+        let pixel_alpha = alpha && src.format().has_alpha();
+        let rle = (keyed || alpha) && src_flags.intersects(Flags::RLEACCEL | Flags::RLEACCELOK);
+        let converts_format = !same_format(src.format(), dst.format());
+
+        let both_hardware =
+            src_flags.contains(Flags::HWSURFACE) && dst_flags.contains(Flags::HWSURFACE);
+        let hardware = both_hardware && {
+            let info = unsafe { sys::SDL_GetVideoInfo().as_ref() };
+            info.is_some_and(|info| {
+                info.blit_hw() != 0
+                    && (!keyed || info.blit_hw_CC() != 0)
+                    && (!alpha || info.blit_hw_A() != 0)
+            })
+        };
+
+        let path = if hardware {
+            BlitPath::Hardware
+        } else if pixel_alpha && rle {
+            BlitPath::RleAlpha
+        } else if pixel_alpha {
+            BlitPath::PixelAlpha
+        } else if alpha {
+            BlitPath::SurfaceAlpha
+        } else if keyed && rle {
+            BlitPath::RleColorKey
+        } else if keyed {
+            BlitPath::ColorKey
+        } else if converts_format {
+            BlitPath::Converted
+        } else {
+            BlitPath::Copy
+        };
+
+        let mut hints = Vec::new();
+        if converts_format && !hardware {
+            hints.push(
+                "the formats differ, so every pixel is converted; \
+                 convert the source once with Surface::to_display_format",
+            );
+        }
+        if path == BlitPath::PixelAlpha {
+            hints.push(
+                "per-pixel alpha is blended in software; if the source only \
+                 needs fully transparent pixels, use Surface::to_color_keyed",
+            );
+        }
+        if keyed && !rle && !hardware {
+            hints.push(
+                "the color key isn't RLE accelerated; Surface::set_color_key \
+                 turns it on",
+            );
+        }
+        if both_hardware && !hardware {
+            hints.push(
+                "both surfaces are in video memory but the driver can't \
+                 accelerate this blit, so it's done in software reading video \
+                 memory, which is slow; keep the source in system memory",
+            );
+        }
+
+        BlitReport {
+            path,
+            converts_format,
+            rle,
+            both_hardware,
+            hints,
+            source: describe(src, src_flags),
+            destination: describe(dst, dst_flags),
+        }
+    }
+}
+
+impl fmt::Display for BlitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "source:      {}", self.source)?;
+        writeln!(f, "destination: {}", self.destination)?;
+        write!(f, "blit path:   {}", self.path)?;
+        for hint in &self.hints {
+            write!(f, "\n  hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+fn surface_flags(surface: &Surface) -> Flags {
+    Flags(unsafe { (*surface.raw()).flags })
+}
+
+fn same_format(a: &PixelFormat, b: &PixelFormat) -> bool {
+    a.bits_per_pixel() == b.bits_per_pixel() && a.masks() == b.masks()
+}
+
+fn describe(surface: &Surface, flags: Flags) -> String {
+    let format = surface.format();
+    let (r, g, b, a) = format.masks();
+    let mut text = format!(
+        "{}x{} {}-bit (masks {:08x} {:08x} {:08x} {:08x})",
+        surface.width(),
+        surface.height(),
+        format.bits_per_pixel(),
+        r,
+        g,
+        b,
+        a
+    );
+
+    let names = [
+        (Flags::HWSURFACE, "hardware"),
+        (Flags::SRCCOLORKEY, "color key"),
+        (Flags::SRCALPHA, "alpha"),
+        (Flags::RLEACCELOK, "RLE requested"),
+        (Flags::RLEACCEL, "RLE"),
+    ];
+    for (flag, name) in names {
+        if flags.contains(flag) {
+            text.push_str(", ");
+            text.push_str(name);
+        }
+    }
+    text
+}
@@ -1,13 +1,19 @@
+use std::ffi::c_int;
+use std::ffi::CStr;
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
+use std::time::Duration;
 
 use sys::SDL_InitSubSystem;
 
 use crate::sys;
 use crate::sdl;
+use crate::SDL;
 
 #[derive(Debug)]
 pub struct Subsystem {
     _pinned: std::marker::PhantomPinned,
+    _not_send: sdl::NotSend,
 }
 
 impl Drop for Subsystem {
@@ -25,7 +31,204 @@ impl Subsystem {
         } else {
             Ok(Subsystem {
                 _pinned: PhantomPinned,
+                _not_send: PhantomData,
             })
         }
     }
 }
+
+impl SDL {
+    pub fn cdrom(&self) -> sdl::Result<Subsystem> {
+        Subsystem::new()
+    }
+}
+
+impl Subsystem {
+    /// The number of CD-ROM drives on the system.
+    pub fn num_drives(&self) -> sdl::Result<u32> {
+        let count = unsafe { sys::SDL_CDNumDrives() };
+        if count < 0 {
+            Err(sdl::get_error())
+        } else {
+            Ok(count as u32)
+        }
+    }
+
+    /// A system dependent name for a drive, such as `/dev/cdrom`.
+    pub fn drive_name(&self, drive: u32) -> Option<String> {
+        let name = unsafe { sys::SDL_CDName(drive as c_int) };
+        if name.is_null() {
+            None
+        } else {
+            Some(
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    /// Opens a drive for access. Drive 0 is the system's default drive.
+    pub fn open(&self, drive: u32) -> sdl::Result<Cd> {
+        let raw = unsafe { sys::SDL_CDOpen(drive as c_int) };
+        if raw.is_null() {
+            Err(sdl::get_error())
+        } else {
+            Ok(Cd {
+                raw,
+                _not_send: PhantomData,
+            })
+        }
+    }
+}
+
+/// The state of a CD-ROM drive.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CdStatus {
+    TrayEmpty,
+    Stopped,
+    Playing,
+    Paused,
+    Error,
+}
+
+impl CdStatus {
+    fn from_raw(raw: sys::SDL_CDStatus) -> CdStatus {
+        match raw {
+            sys::SDL_CDStatus::CD_TRAYEMPTY => CdStatus::TrayEmpty,
+            sys::SDL_CDStatus::CD_STOPPED => CdStatus::Stopped,
+            sys::SDL_CDStatus::CD_PLAYING => CdStatus::Playing,
+            sys::SDL_CDStatus::CD_PAUSED => CdStatus::Paused,
+            sys::SDL_CDStatus::CD_ERROR => CdStatus::Error,
+        }
+    }
+
+    /// Whether there's a disc in the drive, matching `CD_INDRIVE`.
+    pub fn has_disc(self) -> bool {
+        !matches!(self, CdStatus::TrayEmpty | CdStatus::Error)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackKind {
+    Audio,
+    Data,
+}
+
+/// An entry in the table of contents of a CD. Lengths and offsets are in
+/// frames, of which there are [`FRAMES_PER_SECOND`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track {
+    /// The track number.
+    pub id: u8,
+    pub kind: TrackKind,
+    pub length: u32,
+    /// The first frame of the track, counted from the start of the disc.
+    pub offset: u32,
+}
+
+impl Track {
+    pub fn duration(&self) -> Duration {
+        frames_to_duration(self.length)
+    }
+}
+
+/// The number of frames in a second of CD audio, `CD_FPS`.
+pub const FRAMES_PER_SECOND: u32 = sys::CD_FPS;
+
+/// Converts a number of frames into the time they take to play.
+pub fn frames_to_duration(frames: u32) -> Duration {
+    Duration::from_millis(frames as u64 * 1000 / FRAMES_PER_SECOND as u64)
+}
+
+/// A copy of everything SDL knows about the disc in a drive, as returned by
+/// [`Cd::snapshot`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CdSnapshot {
+    /// The private drive identifier.
+    pub id: i32,
+    pub status: CdStatus,
+    /// The index into `tracks` of the current track.
+    pub current_track: u32,
+    /// The current frame, counted from the start of the current track.
+    pub current_frame: u32,
+    /// The table of contents. Empty if there's no disc in the drive.
+    pub tracks: Vec<Track>,
+    /// The frame where the lead-out starts, which is the total length of the
+    /// disc. This is what CDDB style disc IDs are computed from, along with
+    /// the track offsets.
+    pub leadout: u32,
+}
+
+/// An open CD-ROM drive, as returned by [`Subsystem::open`].
+#[derive(Debug)]
+pub struct Cd {
+    raw: *mut sys::SDL_CD,
+    _not_send: sdl::NotSend,
+}
+
+impl Cd {
+    pub fn raw(&self) -> *mut sys::SDL_CD {
+        self.raw
+    }
+
+    /// Checks the current state of the drive. This also updates the table of
+    /// contents when a disc has been inserted.
+    pub fn status(&mut self) -> CdStatus {
+        CdStatus::from_raw(unsafe { sys::SDL_CDStatus(self.raw) })
+    }
+
+    /// Checks the current state of the drive and returns a copy of it.
+    pub fn snapshot(&mut self) -> sdl::Result<CdSnapshot> {
+        let status = self.status();
+        if status == CdStatus::Error {
+            return Err(sdl::get_error());
+        }
+
+        let raw = unsafe { &*self.raw };
+        let count = if status.has_disc() {
+            (raw.numtracks.max(0) as usize).min(sys::SDL_MAX_TRACKS as usize)
+        } else {
+            0
+        };
+        let tracks = raw.track[..count]
+            .iter()
+            .map(|track| Track {
+                id: track.id,
+                kind: if track.type_ as u32 == sys::SDL_DATA_TRACK {
+                    TrackKind::Data
+                } else {
+                    TrackKind::Audio
+                },
+                length: track.length,
+                offset: track.offset,
+            })
+            .collect();
+
+        // SDL stores the lead-out as an extra track after the last one.
+        let leadout = if count > 0 {
+            raw.track[count].offset
+        } else {
+            0
+        };
+
+        Ok(CdSnapshot {
+            id: raw.id,
+            status,
+            current_track: raw.cur_track.max(0) as u32,
+            current_frame: raw.cur_frame.max(0) as u32,
+            tracks,
+            leadout,
+        })
+    }
+}
+
+impl Drop for Cd {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_CDClose(self.raw) }
+    }
+}
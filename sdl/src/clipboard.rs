@@ -0,0 +1,516 @@
+//! Best effort access to the system clipboard, which SDL 1.2 has no API for.
+//!
+//! This talks to the window system directly, using the window handles from
+//! `SDL_GetWMInfo`, so it needs the video mode to be set first. It's only
+//! implemented for X11 and Windows; everywhere else both functions return
+//! [`ClipboardError::Unsupported`].
+//!
+//! On X11 the clipboard is owned by the program which set it, and the text
+//! is only handed out when another program asks for it. Those requests
+//! arrive as window manager events, which [`EventPump`](crate::event::EventPump)
+//! answers as they're polled, so text set with [`set_text`] can only be
+//! pasted elsewhere while events are being polled.
+
+use crate::sdl;
+
+/// Returns the text on the clipboard, or `None` if it's empty or holds
+/// something other than text.
+pub fn get_text() -> Result<Option<String>, ClipboardError> {
+    imp::get_text()
+}
+
+/// Puts `text` on the clipboard.
+pub fn set_text(text: &str) -> Result<(), ClipboardError> {
+    imp::set_text(text)
+}
+
+// Answers clipboard requests from other programs, see the module docs.
+pub(crate) fn handle_syswm_event(event: &crate::sys::SDL_SysWMEvent) {
+    imp::handle_syswm_event(event)
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+    target_os = "solaris"
+))]
+mod imp {
+    use std::cell::RefCell;
+    use std::ffi::{c_char, c_int, c_long, c_uchar, c_ulong, c_void, CStr};
+    use std::mem::MaybeUninit;
+    use std::ptr;
+    use std::slice;
+    use std::time::{Duration, Instant};
+
+    use super::ClipboardError;
+    use crate::sys;
+    use crate::sys::{Display, Window};
+    use crate::syswm::{self, WmInfo, X11Lock};
+
+    type Atom = c_ulong;
+
+    const SELECTION_CLEAR: c_int = 29;
+    const SELECTION_REQUEST: c_int = 30;
+    const SELECTION_NOTIFY: c_int = 31;
+    const PROP_MODE_REPLACE: c_int = 0;
+    const XA_ATOM: Atom = 4;
+    const NONE: c_ulong = 0;
+    const CURRENT_TIME: c_ulong = 0;
+    const ANY_PROPERTY_TYPE: Atom = 0;
+
+    // How long to wait for the clipboard's owner to send the text over.
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct XSelectionEvent {
+        type_: c_int,
+        serial: c_ulong,
+        send_event: c_int,
+        display: *mut Display,
+        requestor: Window,
+        selection: Atom,
+        target: Atom,
+        property: Atom,
+        time: c_ulong,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct XSelectionRequestEvent {
+        type_: c_int,
+        serial: c_ulong,
+        send_event: c_int,
+        display: *mut Display,
+        owner: Window,
+        requestor: Window,
+        selection: Atom,
+        target: Atom,
+        property: Atom,
+        time: c_ulong,
+    }
+
+    #[repr(C)]
+    union XEvent {
+        type_: c_int,
+        selection: XSelectionEvent,
+        selection_request: XSelectionRequestEvent,
+        pad: [c_long; 24],
+    }
+
+    // SDL_SysWMmsg as SDL_syswm.h declares it for X11.
+    #[repr(C)]
+    struct SysWMmsg {
+        version: sys::SDL_version,
+        subsystem: c_int,
+        event: XEvent,
+    }
+
+    #[link(name = "X11")]
+    extern "C" {
+        fn XInternAtom(display: *mut Display, name: *const c_char, only_if_exists: c_int) -> Atom;
+        fn XGetSelectionOwner(display: *mut Display, selection: Atom) -> Window;
+        fn XSetSelectionOwner(display: *mut Display, selection: Atom, owner: Window, time: c_ulong);
+        fn XConvertSelection(
+            display: *mut Display,
+            selection: Atom,
+            target: Atom,
+            property: Atom,
+            requestor: Window,
+            time: c_ulong,
+        );
+        fn XCheckTypedWindowEvent(
+            display: *mut Display,
+            window: Window,
+            event_type: c_int,
+            event: *mut XEvent,
+        ) -> c_int;
+        #[allow(clippy::too_many_arguments)]
+        fn XGetWindowProperty(
+            display: *mut Display,
+            window: Window,
+            property: Atom,
+            offset: c_long,
+            length: c_long,
+            delete: c_int,
+            req_type: Atom,
+            actual_type: *mut Atom,
+            actual_format: *mut c_int,
+            nitems: *mut c_ulong,
+            bytes_after: *mut c_ulong,
+            prop: *mut *mut c_uchar,
+        ) -> c_int;
+        #[allow(clippy::too_many_arguments)]
+        fn XChangeProperty(
+            display: *mut Display,
+            window: Window,
+            property: Atom,
+            type_: Atom,
+            format: c_int,
+            mode: c_int,
+            data: *const c_uchar,
+            nelements: c_int,
+        ) -> c_int;
+        fn XSendEvent(
+            display: *mut Display,
+            window: Window,
+            propagate: c_int,
+            event_mask: c_long,
+            event: *mut XEvent,
+        ) -> c_int;
+        fn XFlush(display: *mut Display) -> c_int;
+        fn XFree(data: *mut c_void) -> c_int;
+    }
+
+    thread_local! {
+        // The text this program has put on the clipboard, while it owns it.
+        static TEXT: RefCell<Option<String>> = const { RefCell::new(None) };
+    }
+
+    // The X11 display and window, with SDL's lock held on the display for as
+    // long as this is around.
+    struct X11 {
+        display: *mut Display,
+        window: Window,
+        _lock: X11Lock,
+    }
+
+    impl X11 {
+        fn lock() -> Result<X11, ClipboardError> {
+            match syswm::wm_info()? {
+                WmInfo::X11(info) => Ok(X11 {
+                    display: info.display,
+                    window: info.window,
+                    _lock: info.lock(),
+                }),
+                _ => Err(ClipboardError::Unsupported),
+            }
+        }
+
+        fn atom(&self, name: &CStr) -> Atom {
+            unsafe { XInternAtom(self.display, name.as_ptr(), 0) }
+        }
+    }
+
+    pub fn get_text() -> Result<Option<String>, ClipboardError> {
+        let x11 = X11::lock()?;
+        let clipboard = x11.atom(c"CLIPBOARD");
+        let owner = unsafe { XGetSelectionOwner(x11.display, clipboard) };
+        if owner == x11.window {
+            return Ok(TEXT.with(|text| text.borrow().clone()));
+        } else if owner == NONE {
+            return Ok(None);
+        }
+
+        let utf8 = x11.atom(c"UTF8_STRING");
+        let property = x11.atom(c"SDL_CLIPBOARD");
+        unsafe {
+            XConvertSelection(
+                x11.display,
+                clipboard,
+                utf8,
+                property,
+                x11.window,
+                CURRENT_TIME,
+            );
+            XFlush(x11.display);
+        }
+
+        // SDL only reads X events when it's asked to pump them, so the reply
+        // can be waited for here without SDL taking it first.
+        let start = Instant::now();
+        let reply = loop {
+            let mut event = MaybeUninit::<XEvent>::uninit();
+            let found = unsafe {
+                XCheckTypedWindowEvent(
+                    x11.display,
+                    x11.window,
+                    SELECTION_NOTIFY,
+                    event.as_mut_ptr(),
+                )
+            };
+            if found != 0 {
+                break unsafe { event.assume_init().selection };
+            }
+            if start.elapsed() > TIMEOUT {
+                return Err(ClipboardError::Timeout);
+            }
+            unsafe { sys::SDL_Delay(1) };
+        };
+        if reply.property == NONE {
+            return Ok(None);
+        }
+
+        let (mut actual_type, mut format, mut len, mut remaining) = (0, 0, 0, 0);
+        let mut data = ptr::null_mut();
+        let ret = unsafe {
+            XGetWindowProperty(
+                x11.display,
+                x11.window,
+                property,
+                0,
+                c_long::MAX / 4,
+                1,
+                ANY_PROPERTY_TYPE,
+                &mut actual_type,
+                &mut format,
+                &mut len,
+                &mut remaining,
+                &mut data,
+            )
+        };
+        if ret != 0 || data.is_null() {
+            return Ok(None);
+        }
+
+        // Only 8-bit formats hold text; anything else, like the INCR
+        // protocol for very large selections, isn't supported.
+        let text = (format == 8).then(|| {
+            let bytes = unsafe { slice::from_raw_parts(data, len as usize) };
+            String::from_utf8_lossy(bytes).into_owned()
+        });
+        unsafe { XFree(data as *mut c_void) };
+        Ok(text)
+    }
+
+    pub fn set_text(text: &str) -> Result<(), ClipboardError> {
+        let x11 = X11::lock()?;
+        let clipboard = x11.atom(c"CLIPBOARD");
+        unsafe { XSetSelectionOwner(x11.display, clipboard, x11.window, CURRENT_TIME) };
+        if unsafe { XGetSelectionOwner(x11.display, clipboard) } != x11.window {
+            return Err(ClipboardError::Rejected);
+        }
+
+        TEXT.with(|current| *current.borrow_mut() = Some(text.to_owned()));
+        // Requests for the text arrive as window manager events, which SDL
+        // drops unless they're turned on.
+        unsafe {
+            sys::SDL_EventState(
+                sys::SDL_EventType::SDL_SYSWMEVENT as u8,
+                sys::SDL_ENABLE as c_int,
+            )
+        };
+        Ok(())
+    }
+
+    pub fn handle_syswm_event(event: &sys::SDL_SysWMEvent) {
+        let msg = match unsafe { (event.msg as *const SysWMmsg).as_ref() } {
+            Some(msg) => msg,
+            None => return,
+        };
+
+        match unsafe { msg.event.type_ } {
+            SELECTION_CLEAR => TEXT.with(|text| *text.borrow_mut() = None),
+            SELECTION_REQUEST => {
+                let request = unsafe { msg.event.selection_request };
+                if let Ok(x11) = X11::lock() {
+                    answer_request(&x11, &request);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Sends our text to another program which asked for it.
+    fn answer_request(x11: &X11, request: &XSelectionRequestEvent) {
+        let targets = x11.atom(c"TARGETS");
+        let utf8 = x11.atom(c"UTF8_STRING");
+        let string = x11.atom(c"STRING");
+
+        let text = TEXT.with(|text| text.borrow().clone());
+        let property = match text {
+            Some(_) if request.target == targets => {
+                let supported = [targets, utf8, string];
+                unsafe {
+                    XChangeProperty(
+                        x11.display,
+                        request.requestor,
+                        request.property,
+                        XA_ATOM,
+                        32,
+                        PROP_MODE_REPLACE,
+                        supported.as_ptr() as *const c_uchar,
+                        supported.len() as c_int,
+                    )
+                };
+                request.property
+            }
+            Some(text) if request.target == utf8 || request.target == string => {
+                unsafe {
+                    XChangeProperty(
+                        x11.display,
+                        request.requestor,
+                        request.property,
+                        request.target,
+                        8,
+                        PROP_MODE_REPLACE,
+                        text.as_ptr(),
+                        text.len() as c_int,
+                    )
+                };
+                request.property
+            }
+            // Refused, either because there's nothing to give or it was
+            // asked for in a format we don't have.
+            _ => NONE,
+        };
+
+        let mut reply = XEvent {
+            selection: XSelectionEvent {
+                type_: SELECTION_NOTIFY,
+                serial: 0,
+                send_event: 1,
+                display: x11.display,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                property,
+                time: request.time,
+            },
+        };
+        unsafe {
+            XSendEvent(x11.display, request.requestor, 0, 0, &mut reply);
+            XFlush(x11.display);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::ffi::{c_int, c_uint, c_void};
+    use std::ptr;
+    use std::slice;
+
+    use super::ClipboardError;
+    use crate::sys;
+    use crate::syswm::{self, WmInfo};
+
+    const CF_UNICODETEXT: c_uint = 13;
+    const GMEM_MOVEABLE: c_uint = 0x0002;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn OpenClipboard(window: *mut c_void) -> c_int;
+        fn CloseClipboard() -> c_int;
+        fn EmptyClipboard() -> c_int;
+        fn GetClipboardData(format: c_uint) -> *mut c_void;
+        fn SetClipboardData(format: c_uint, data: *mut c_void) -> *mut c_void;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GlobalAlloc(flags: c_uint, bytes: usize) -> *mut c_void;
+        fn GlobalFree(memory: *mut c_void) -> *mut c_void;
+        fn GlobalLock(memory: *mut c_void) -> *mut c_void;
+        fn GlobalUnlock(memory: *mut c_void) -> c_int;
+    }
+
+    // The clipboard, opened for SDL's window until this is dropped.
+    struct Clipboard;
+
+    impl Clipboard {
+        fn open() -> Result<Clipboard, ClipboardError> {
+            let hwnd = match syswm::wm_info()? {
+                WmInfo::Windows(info) => info.hwnd,
+                _ => return Err(ClipboardError::Unsupported),
+            };
+            if unsafe { OpenClipboard(hwnd) } == 0 {
+                Err(ClipboardError::Rejected)
+            } else {
+                Ok(Clipboard)
+            }
+        }
+    }
+
+    impl Drop for Clipboard {
+        fn drop(&mut self) {
+            unsafe { CloseClipboard() };
+        }
+    }
+
+    pub fn get_text() -> Result<Option<String>, ClipboardError> {
+        let _clipboard = Clipboard::open()?;
+        let data = unsafe { GetClipboardData(CF_UNICODETEXT) };
+        if data.is_null() {
+            return Ok(None);
+        }
+
+        let text = unsafe { GlobalLock(data) } as *const u16;
+        if text.is_null() {
+            return Ok(None);
+        }
+        let len = (0..).take_while(|&i| unsafe { *text.add(i) } != 0).count();
+        let result = String::from_utf16_lossy(unsafe { slice::from_raw_parts(text, len) });
+        unsafe { GlobalUnlock(data) };
+        Ok(Some(result))
+    }
+
+    pub fn set_text(text: &str) -> Result<(), ClipboardError> {
+        let mut wide: Vec<u16> = text.encode_utf16().collect();
+        wide.push(0);
+
+        let _clipboard = Clipboard::open()?;
+        unsafe {
+            EmptyClipboard();
+            let data = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2);
+            if data.is_null() {
+                return Err(ClipboardError::Rejected);
+            }
+            let dst = GlobalLock(data) as *mut u16;
+            if dst.is_null() {
+                GlobalFree(data);
+                return Err(ClipboardError::Rejected);
+            }
+            ptr::copy_nonoverlapping(wide.as_ptr(), dst, wide.len());
+            GlobalUnlock(data);
+
+            // The clipboard owns the memory once it's been handed over.
+            if SetClipboardData(CF_UNICODETEXT, data).is_null() {
+                GlobalFree(data);
+                return Err(ClipboardError::Rejected);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn handle_syswm_event(_event: &sys::SDL_SysWMEvent) {}
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+    target_os = "solaris",
+    windows
+)))]
+mod imp {
+    use super::ClipboardError;
+    use crate::sys;
+
+    pub fn get_text() -> Result<Option<String>, ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+
+    pub fn set_text(_text: &str) -> Result<(), ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+
+    pub fn handle_syswm_event(_event: &sys::SDL_SysWMEvent) {}
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClipboardError {
+    #[error("the clipboard isn't supported on this platform")]
+    Unsupported,
+    #[error("the window system refused access to the clipboard")]
+    Rejected,
+    #[error("timed out waiting for the clipboard's owner")]
+    Timeout,
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
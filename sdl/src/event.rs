@@ -1,8 +1,37 @@
+use std::any::TypeId;
+#[cfg(feature = "async")]
+use std::cell::RefCell;
+use std::collections::HashSet;
+#[cfg(feature = "async")]
+use std::collections::VecDeque;
+use std::ffi::{c_int, c_uint, c_void};
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
+use std::mem::{self, MaybeUninit};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::rc::Rc;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use bitflags::bitflags;
+
+pub use crate::keyboard::{Key, KeyMod, Keysym};
+pub use crate::mouse::MouseButtonState;
 use crate::sdl;
 use crate::sys;
+use crate::timer::Ticks;
+use crate::VideoSubsystem;
 
+#[derive(Debug)]
 pub enum Event<UserEvent = ()> {
     Active(ActiveEvent),
     Keyboard(KeyboardEvent),
@@ -13,8 +42,22 @@ pub enum Event<UserEvent = ()> {
     JoyHat(JoyHatEvent),
     JoyBall(JoyBallEvent),
     Resize(ResizeEvent),
+    /// A mouse wheel movement, synthesized by [`EventPump`] when
+    /// [`coalesce_wheel`](EventPump::coalesce_wheel) is enabled. `delta` is
+    /// positive when scrolling up.
+    MouseWheel {
+        delta: i32,
+    },
+    /// A character typed on the keyboard, synthesized by [`EventPump`] when
+    /// [`text_input`](EventPump::text_input) is enabled. It comes right
+    /// after the `KeyDown` which typed it.
+    TextInput(char),
     Expose,
-    SysWM, // TODO: decide how/if we want to support this
+    /// A platform specific window manager message, only sent once
+    /// [`syswm::enable_events`](crate::syswm::enable_events) turns them on.
+    /// The message itself isn't kept, see [`syswm`](crate::syswm) for the
+    /// native handles to talk to the window system directly.
+    SysWM,
     Quit,
     User(UserEvent),
     Unknown,
@@ -42,60 +85,1159 @@ impl From<sys::SDL_ExposeEvent> for Event {
     }
 }
 
-pub enum ActiveEvent {
-    MouseEnter,
-    MouseLeave,
-    AppFocused,
-    AppUnfocused,
-    Minimized,
-    Restored,
-    Unknown,
+// SDL_Event::type_ is a Uint8, so the event types need to be narrowed before
+// they can be used as patterns.
+const ACTIVEEVENT: u8 = sys::SDL_EventType::SDL_ACTIVEEVENT as u8;
+const KEYDOWN: u8 = sys::SDL_EventType::SDL_KEYDOWN as u8;
+const KEYUP: u8 = sys::SDL_EventType::SDL_KEYUP as u8;
+const MOUSEMOTION: u8 = sys::SDL_EventType::SDL_MOUSEMOTION as u8;
+const MOUSEBUTTONDOWN: u8 = sys::SDL_EventType::SDL_MOUSEBUTTONDOWN as u8;
+const MOUSEBUTTONUP: u8 = sys::SDL_EventType::SDL_MOUSEBUTTONUP as u8;
+const JOYAXISMOTION: u8 = sys::SDL_EventType::SDL_JOYAXISMOTION as u8;
+const JOYBALLMOTION: u8 = sys::SDL_EventType::SDL_JOYBALLMOTION as u8;
+const JOYHATMOTION: u8 = sys::SDL_EventType::SDL_JOYHATMOTION as u8;
+const JOYBUTTONDOWN: u8 = sys::SDL_EventType::SDL_JOYBUTTONDOWN as u8;
+const JOYBUTTONUP: u8 = sys::SDL_EventType::SDL_JOYBUTTONUP as u8;
+const QUIT: u8 = sys::SDL_EventType::SDL_QUIT as u8;
+const SYSWMEVENT: u8 = sys::SDL_EventType::SDL_SYSWMEVENT as u8;
+const VIDEORESIZE: u8 = sys::SDL_EventType::SDL_VIDEORESIZE as u8;
+const VIDEOEXPOSE: u8 = sys::SDL_EventType::SDL_VIDEOEXPOSE as u8;
+const USEREVENT: u8 = sys::SDL_EventType::SDL_USEREVENT as u8;
+
+// The code of the user events pushed by `EventSender`, to tell them apart
+// from any other code pushing user events.
+const SENDER_CODE: c_int = 0x5275_5374;
+
+impl<T: 'static> Event<T> {
+    /// Decodes a raw event based on its type.
+    pub(crate) fn from_raw(raw: &sys::SDL_Event) -> Event<T> {
+        // The type_ field is shared by every member of the union, and the
+        // match makes sure we only read the member it says is active.
+        unsafe {
+            match raw.type_ {
+                ACTIVEEVENT => raw.active.into(),
+                KEYDOWN | KEYUP => KeyboardEvent::from_raw(&raw.key).into(),
+                MOUSEMOTION => raw.motion.into(),
+                MOUSEBUTTONDOWN | MOUSEBUTTONUP => raw.button.into(),
+                JOYAXISMOTION => raw.jaxis.into(),
+                JOYBALLMOTION => raw.jball.into(),
+                JOYHATMOTION => raw.jhat.into(),
+                JOYBUTTONDOWN | JOYBUTTONUP => raw.jbutton.into(),
+                QUIT => Event::Quit,
+                SYSWMEVENT => Event::SysWM,
+                VIDEORESIZE => raw.resize.into(),
+                VIDEOEXPOSE => Event::Expose,
+                USEREVENT if raw.user.code == SENDER_CODE => Event::from_sent(&raw.user),
+                _ => Event::Unknown,
+            }
+        }
+    }
+
+    // Takes the value out of an event pushed by an `EventSender`. It's only
+    // reported if it's a `T`, and dropped otherwise.
+    unsafe fn from_sent(raw: &sys::SDL_UserEvent) -> Event<T> {
+        let take: unsafe fn(*mut c_void, TypeId) -> bool = mem::transmute(raw.data2);
+        if take(raw.data1, TypeId::of::<T>()) {
+            Event::User(*Box::from_raw(raw.data1 as *mut T))
+        } else {
+            Event::Unknown
+        }
+    }
+
+    /// Decodes an event which is staying in the queue. Events from an
+    /// `EventSender` can only be taken out once, so they're `Unknown`.
+    pub(crate) fn from_peeked(raw: &sys::SDL_Event) -> Event<T> {
+        match unsafe { (raw.type_, raw.user.code) } {
+            (USEREVENT, SENDER_CODE) => Event::Unknown,
+            _ => Event::from_raw(raw),
+        }
+    }
+
+    /// Encodes the event as SDL would, or returns `None` for events SDL has
+    /// no equivalent of.
+    pub(crate) fn to_raw(&self) -> Option<sys::SDL_Event> {
+        let state = |pressed: bool| {
+            if pressed {
+                sys::SDL_PRESSED
+            } else {
+                sys::SDL_RELEASED
+            }
+        };
+
+        let raw = match self {
+            Event::Active(event) => sys::SDL_Event {
+                active: sys::SDL_ActiveEvent {
+                    type_: ACTIVEEVENT,
+                    gain: event.gain as u8,
+                    state: event.state.bits(),
+                },
+            },
+            Event::Keyboard(KeyboardEvent::KeyDown(keysym)) => sys::SDL_Event {
+                key: sys::SDL_KeyboardEvent {
+                    type_: KEYDOWN,
+                    which: 0,
+                    state: sys::SDL_PRESSED,
+                    keysym: keysym.raw(),
+                },
+            },
+            Event::Keyboard(KeyboardEvent::KeyUp(keysym)) => sys::SDL_Event {
+                key: sys::SDL_KeyboardEvent {
+                    type_: KEYUP,
+                    which: 0,
+                    state: sys::SDL_RELEASED,
+                    keysym: keysym.raw(),
+                },
+            },
+            Event::MouseMotion(event) => sys::SDL_Event {
+                motion: sys::SDL_MouseMotionEvent {
+                    type_: MOUSEMOTION,
+                    which: 0,
+                    state: event.buttons.bits(),
+                    x: event.x,
+                    y: event.y,
+                    xrel: event.xrel,
+                    yrel: event.yrel,
+                },
+            },
+            Event::MouseButton(event) => sys::SDL_Event {
+                button: sys::SDL_MouseButtonEvent {
+                    type_: if event.pressed {
+                        MOUSEBUTTONDOWN
+                    } else {
+                        MOUSEBUTTONUP
+                    },
+                    which: 0,
+                    button: event.button.raw(),
+                    state: state(event.pressed),
+                    x: event.x,
+                    y: event.y,
+                },
+            },
+            Event::JoyAxis(event) => sys::SDL_Event {
+                jaxis: sys::SDL_JoyAxisEvent {
+                    type_: JOYAXISMOTION,
+                    which: event.device,
+                    axis: event.axis,
+                    value: event.value,
+                },
+            },
+            Event::JoyButton(event) => sys::SDL_Event {
+                jbutton: sys::SDL_JoyButtonEvent {
+                    type_: if event.pressed {
+                        JOYBUTTONDOWN
+                    } else {
+                        JOYBUTTONUP
+                    },
+                    which: event.device,
+                    button: event.button,
+                    state: state(event.pressed),
+                },
+            },
+            Event::JoyHat(event) => sys::SDL_Event {
+                jhat: sys::SDL_JoyHatEvent {
+                    type_: JOYHATMOTION,
+                    which: event.device,
+                    hat: event.hat,
+                    value: event.value.raw(),
+                },
+            },
+            Event::JoyBall(event) => sys::SDL_Event {
+                jball: sys::SDL_JoyBallEvent {
+                    type_: JOYBALLMOTION,
+                    which: event.device,
+                    ball: event.ball,
+                    xrel: event.xrel,
+                    yrel: event.yrel,
+                },
+            },
+            Event::Resize(event) => sys::SDL_Event {
+                resize: sys::SDL_ResizeEvent {
+                    type_: VIDEORESIZE,
+                    w: event.w,
+                    h: event.h,
+                },
+            },
+            Event::Expose => sys::SDL_Event {
+                expose: sys::SDL_ExposeEvent { type_: VIDEOEXPOSE },
+            },
+            Event::Quit => sys::SDL_Event {
+                quit: sys::SDL_QuitEvent { type_: QUIT },
+            },
+            Event::Keyboard(KeyboardEvent::Unknown)
+            | Event::MouseWheel { .. }
+            | Event::TextInput(_)
+            | Event::SysWM
+            | Event::User(_)
+            | Event::Unknown => return None,
+        };
+        Some(raw)
+    }
+}
+
+/// Sends events to an [`EventPump`] through SDL's queue, as returned by
+/// [`EventPump::sender`].
+///
+/// Events can be sent from any thread, which makes this the way for other
+/// threads to wake up a loop waiting with
+/// [`wait_event`](EventPump::wait_event). Events still in the queue when
+/// SDL shuts down are leaked.
+#[derive(Debug)]
+pub struct EventSender<UserEvent> {
+    _user: PhantomData<fn(UserEvent)>,
+}
+
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> EventSender<T> {
+        EventSender { _user: PhantomData }
+    }
+}
+
+impl<T: Send + 'static> EventSender<T> {
+    /// Adds `event` to the end of SDL's queue. This fails if the queue is
+    /// full.
+    pub fn send(&self, event: T) -> sdl::Result<()> {
+        let data = Box::into_raw(Box::new(event));
+        let take: unsafe fn(*mut c_void, TypeId) -> bool = take_sent::<T>;
+        let mut raw = sys::SDL_Event {
+            user: sys::SDL_UserEvent {
+                type_: USEREVENT,
+                code: SENDER_CODE,
+                data1: data as *mut c_void,
+                data2: take as *mut c_void,
+            },
+        };
+
+        if unsafe { sys::SDL_PushEvent(&mut raw) } != 0 {
+            drop(unsafe { Box::from_raw(data) });
+            DROPPED_EVENTS.fetch_add(1, Ordering::Relaxed);
+            Err(sdl::get_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Stored in events pushed by `EventSender<T>`. Returns whether `data` is a
+// `T`, the type `wanted`, and drops it if not, since it can't be reported.
+unsafe fn take_sent<T: 'static>(data: *mut c_void, wanted: TypeId) -> bool {
+    if TypeId::of::<T>() == wanted {
+        true
+    } else {
+        drop(Box::from_raw(data as *mut T));
+        false
+    }
+}
+
+// The number of events which didn't fit in the queue when sent or added,
+// for `EventPump::queue_stats`. Senders can be on any thread.
+static DROPPED_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+// Set by the signal handler installed by `quit_on_signals`.
+static QUIT_SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn quit_signal_handler(_signal: c_int) {
+    QUIT_SIGNALLED.store(true, Ordering::Relaxed);
+}
+
+/// Turns SIGINT and SIGTERM into an [`Event::Quit`], so pressing Ctrl+C in
+/// the terminal shuts the program down through its normal quit path and its
+/// `Drop` impls run, instead of it being killed mid-frame.
+///
+/// SDL 1.2 does the same once the video subsystem is up, but only if nothing
+/// else has handled the signals first, and not at all before then. The
+/// handlers installed here take priority over SDL's, and stay installed
+/// until the program exits.
+pub fn quit_on_signals() -> io::Result<()> {
+    let handler = quit_signal_handler as extern "C" fn(c_int) as libc::sighandler_t;
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the next pending event, if there is one.
+pub(crate) fn poll_event<T: 'static>() -> Option<Event<T>> {
+    if QUIT_SIGNALLED.swap(false, Ordering::Relaxed) {
+        return Some(Event::Quit);
+    }
+    poll_raw()
+}
+
+/// Waits for the next event.
+pub(crate) fn wait_event<T: 'static>() -> sdl::Result<Event<T>> {
+    sdl::check_callbacks()?;
+    Ok(wait_for(poll_raw, || Event::Quit))
+}
+
+fn poll_raw<T: 'static>() -> Option<Event<T>> {
+    let mut raw = MaybeUninit::<sys::SDL_Event>::uninit();
+    if unsafe { sys::SDL_PollEvent(raw.as_mut_ptr()) } == 0 {
+        None
+    } else {
+        Some(decode(unsafe { raw.assume_init_ref() }))
+    }
+}
+
+// How long `wait_for` sleeps between polls, as SDL_WaitEvent does.
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+// Polls until `poll` returns something, or returns `quit()` once a signal
+// caught by `quit_on_signals` comes in. SDL_WaitEvent can't be used, since
+// it only returns for queued events, and with SDL's own signal handlers
+// replaced nothing queues one for a signal.
+fn wait_for<R>(mut poll: impl FnMut() -> Option<R>, quit: impl FnOnce() -> R) -> R {
+    loop {
+        if QUIT_SIGNALLED.swap(false, Ordering::Relaxed) {
+            return quit();
+        }
+        if let Some(result) = poll() {
+            return result;
+        }
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+// Peeks at or takes up to `max` events from the queue with SDL_PeepEvents,
+// after pumping it so it's up to date.
+fn peep(
+    max: usize,
+    action: sys::SDL_EventAction,
+    mask: EventMask,
+) -> sdl::Result<Vec<sys::SDL_Event>> {
+    sdl::check_callbacks()?;
+    let mut raw = Vec::with_capacity(max);
+    let count = unsafe {
+        sys::SDL_PumpEvents();
+        sys::SDL_PeepEvents(raw.as_mut_ptr(), max as c_int, action, mask.bits())
+    };
+    if count < 0 {
+        return Err(sdl::get_error());
+    }
+    unsafe { raw.set_len(count as usize) };
+    Ok(raw)
+}
+
+fn decode<T: 'static>(raw: &sys::SDL_Event) -> Event<T> {
+    #[cfg(feature = "clipboard")]
+    if unsafe { raw.type_ } == SYSWMEVENT {
+        crate::clipboard::handle_syswm_event(unsafe { &raw.syswm });
+    }
+    Event::from_raw(raw)
+}
+
+impl VideoSubsystem {
+    /// SDL 1.2 sets up its event queue along with the video subsystem, so
+    /// events are read through it.
+    pub fn event_pump(&self) -> EventPump {
+        EventPump::new()
+    }
+
+    /// Like [`event_pump`](VideoSubsystem::event_pump), for programs which
+    /// send their own events of type `T` through SDL's queue, with the
+    /// [`EventSender`] from [`EventPump::sender`]. They come out of the pump
+    /// as `Event::User`.
+    pub fn user_event_pump<T: Send + 'static>(&self) -> EventPump<T> {
+        EventPump::new()
+    }
+}
+
+/// Reads events from SDL's event queue.
+///
+/// `UserEvent` is the type of the events sent with an [`EventSender`] from
+/// [`sender`](EventPump::sender), see
+/// [`user_event_pump`](VideoSubsystem::user_event_pump).
+#[derive(Debug)]
+pub struct EventPump<UserEvent = ()> {
+    coalesce_wheel: bool,
+    filter_key_repeat: bool,
+    // The keys which are down, while filtering key repeats.
+    held_keys: HashSet<Key>,
+    text_input: bool,
+    // The character typed by the last key press, to report next.
+    pending_text: Option<char>,
+    click_settings: Option<ClickSettings>,
+    last_press: Option<Press>,
+    // Whether the queue is being emptied, so it's only checked for pressure
+    // once per batch of events rather than on every poll.
+    draining: bool,
+    queue_stats: QueueStats,
+    // The value of `DROPPED_EVENTS` as of the last check.
+    seen_dropped: u64,
+    queue_warning: Option<QueueWarning>,
+    _user: PhantomData<fn() -> UserEvent>,
+    _not_send: sdl::NotSend,
+}
+
+/// An iterator over the pending events, as returned by
+/// [`EventPump::poll_iter`].
+#[derive(Debug)]
+pub struct PollIter<'a, UserEvent = ()> {
+    pump: &'a mut EventPump<UserEvent>,
+}
+
+impl<T: Send + 'static> Iterator for PollIter<'_, T> {
+    type Item = Event<T>;
+
+    fn next(&mut self) -> Option<Event<T>> {
+        self.pump.poll_event()
+    }
+}
+
+/// An iterator which waits for events, as returned by
+/// [`EventPump::wait_iter`].
+#[derive(Debug)]
+pub struct WaitIter<'a, UserEvent = ()> {
+    pump: &'a mut EventPump<UserEvent>,
+}
+
+impl<T: Send + 'static> Iterator for WaitIter<'_, T> {
+    type Item = Event<T>;
+
+    fn next(&mut self) -> Option<Event<T>> {
+        self.pump.wait_event().ok()
+    }
+}
+
+/// How close together presses of a mouse button have to be to count as a
+/// double click, see [`EventPump::detect_clicks`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ClickSettings {
+    /// The longest time between presses.
+    pub interval: Duration,
+    /// How far in pixels the mouse can move, between presses or while the
+    /// button is held, before it's no longer a click.
+    pub distance: u16,
+}
+
+impl Default for ClickSettings {
+    fn default() -> ClickSettings {
+        ClickSettings {
+            interval: Duration::from_millis(500),
+            distance: 4,
+        }
+    }
+}
+
+/// How soon and how often a held key repeats its `KeyDown`, see
+/// [`Subsystem::set_key_repeat`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct KeyRepeat {
+    /// How long the key has to be held before it starts repeating.
+    pub delay: Duration,
+    /// The time between repeats.
+    pub interval: Duration,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> KeyRepeat {
+        KeyRepeat {
+            delay: Duration::from_millis(sys::SDL_DEFAULT_REPEAT_DELAY as u64),
+            interval: Duration::from_millis(sys::SDL_DEFAULT_REPEAT_INTERVAL as u64),
+        }
+    }
+}
+
+/// The most events SDL 1.2's queue can hold. Once it's full, SDL drops any
+/// more input which comes in until some events are taken out.
+pub const QUEUE_CAPACITY: usize = 127;
+
+/// How many events have been lost to a full queue, as seen by an
+/// [`EventPump`] since it was created. See
+/// [`EventPump::queue_stats`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct QueueStats {
+    /// How many times the queue was full when the pump went to read it. SDL
+    /// doesn't say how much input it dropped, only that it may have.
+    pub full: u64,
+    /// Events which couldn't be added because the queue was full, by an
+    /// [`EventSender`] or [`EventPump::add_events`].
+    pub dropped: u64,
+    /// The most events the pump has found waiting at once.
+    pub peak: usize,
+}
+
+/// The state of SDL's queue when a warning was raised, see
+/// [`EventPump::set_queue_warning`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct QueuePressure {
+    /// The number of events waiting.
+    pub queued: usize,
+    /// Whether the queue was full, so input may have been dropped.
+    pub full: bool,
+    /// Events which couldn't be added since the last check.
+    pub dropped: u64,
+}
+
+struct QueueWarning {
+    threshold: usize,
+    callback: Box<dyn FnMut(QueuePressure)>,
+}
+
+impl fmt::Debug for QueueWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueWarning")
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+// The most recent press of a mouse button, for counting clicks.
+#[derive(Debug)]
+struct Press {
+    button: Button,
+    time: Instant,
+    x: u16,
+    y: u16,
+    clicks: u8,
+    // Whether the mouse has moved too far since the press to be a click.
+    dragged: bool,
+}
+
+impl<T: Send + 'static> EventPump<T> {
+    fn new() -> EventPump<T> {
+        EventPump {
+            coalesce_wheel: false,
+            filter_key_repeat: false,
+            held_keys: HashSet::new(),
+            text_input: false,
+            pending_text: None,
+            click_settings: None,
+            last_press: None,
+            draining: false,
+            queue_stats: QueueStats::default(),
+            seen_dropped: DROPPED_EVENTS.load(Ordering::Relaxed),
+            queue_warning: None,
+            _user: PhantomData,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// SDL 1.2 reports each notch of the mouse wheel as a press and release
+    /// of `Button::WheelUp` or `Button::WheelDown`. When enabled, each pair is
+    /// turned into a single `Event::MouseWheel` instead.
+    pub fn coalesce_wheel(&mut self, enabled: bool) -> &mut EventPump<T> {
+        self.coalesce_wheel = enabled;
+        self
+    }
+
+    /// When key repeat is turned on with [`Subsystem::set_key_repeat`],
+    /// holding a key down sends a `KeyDown` for it over and over. When
+    /// enabled, only the first `KeyDown` until the key is released is
+    /// reported, so something like "press to jump" only fires once.
+    pub fn filter_key_repeat(&mut self, enabled: bool) -> &mut EventPump<T> {
+        self.filter_key_repeat = enabled;
+        self.held_keys.clear();
+        self
+    }
+
+    /// Reports the characters typed on the keyboard as `Event::TextInput`,
+    /// and turns unicode translation on or off to match. Control characters,
+    /// such as backspace and return, are only reported as key presses.
+    pub fn text_input(&mut self, enabled: bool) -> &mut EventPump<T> {
+        unsafe { sys::SDL_EnableUNICODE(enabled as c_int) };
+        self.text_input = enabled;
+        self.pending_text = None;
+        self
+    }
+
+    /// Counts repeated clicks of the same mouse button, filling in the
+    /// `clicks` of each `MouseButtonEvent`. A press soon after and close to
+    /// the last one counts as another click, so the second press of a double
+    /// click has 2 `clicks`. Releases have the same count as their press, or
+    /// 0 if the mouse was dragged too far while the button was down.
+    ///
+    /// Passing `None` turns it off again, which is the default.
+    pub fn detect_clicks(&mut self, settings: Option<ClickSettings>) -> &mut EventPump<T> {
+        self.click_settings = settings;
+        self.last_press = None;
+        self
+    }
+
+    /// Calls `callback` whenever the pump finds `threshold` or more events
+    /// waiting in SDL's queue, the queue full, or events sent to it dropped.
+    /// That usually means frames are taking too long to keep up with the
+    /// input.
+    ///
+    /// The queue is checked once each time it's emptied, so when polling,
+    /// the check happens at the first poll after the last one which returned
+    /// `None`.
+    pub fn set_queue_warning<F>(&mut self, threshold: usize, callback: F) -> &mut EventPump<T>
+    where
+        F: FnMut(QueuePressure) + 'static,
+    {
+        self.queue_warning = Some(QueueWarning {
+            threshold,
+            callback: Box::new(callback),
+        });
+        self
+    }
+
+    /// Removes the callback set with
+    /// [`set_queue_warning`](EventPump::set_queue_warning).
+    pub fn clear_queue_warning(&mut self) -> &mut EventPump<T> {
+        self.queue_warning = None;
+        self
+    }
+
+    /// Returns how many events have been lost to a full queue since the
+    /// pump was created.
+    pub fn queue_stats(&self) -> QueueStats {
+        self.queue_stats
+    }
+
+    /// Returns the next pending event, if there is one.
+    pub fn poll_event(&mut self) -> Option<Event<T>> {
+        if let Some(c) = self.pending_text.take() {
+            return Some(Event::TextInput(c));
+        }
+        if !self.draining {
+            self.check_queue();
+            self.draining = true;
+        }
+        loop {
+            let Some(event) = poll_event() else {
+                self.draining = false;
+                return None;
+            };
+            if let Some(event) = self.process(event) {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Waits until there's an event, and returns it.
+    pub fn wait_event(&mut self) -> sdl::Result<Event<T>> {
+        if let Some(c) = self.pending_text.take() {
+            return Ok(Event::TextInput(c));
+        }
+        if !self.draining {
+            self.check_queue();
+        }
+        loop {
+            if let Some(event) = self.process(wait_event()?) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Returns an iterator over the pending events, which ends once there
+    /// are none left. This is the usual way to handle events once a frame,
+    /// with `for event in pump.poll_iter()`.
+    pub fn poll_iter(&mut self) -> PollIter<'_, T> {
+        PollIter { pump: self }
+    }
+
+    /// Returns an iterator which waits for each event in turn, for programs
+    /// which only redraw in response to input. It only ends if SDL fails to
+    /// wait, and [`wait_event`](EventPump::wait_event) returns the error.
+    pub fn wait_iter(&mut self) -> WaitIter<'_, T> {
+        WaitIter { pump: self }
+    }
+
+    /// Returns which keys are held down, as of the events handled so far.
+    pub fn keyboard_state(&self) -> KeyboardState {
+        KeyboardState::current()
+    }
+
+    /// Returns a sender for events which come out of this pump as
+    /// `Event::User`. It can be sent to, and used from, other threads.
+    pub fn sender(&self) -> EventSender<T> {
+        EventSender { _user: PhantomData }
+    }
+
+    /// Returns up to `max` events from the front of the queue, leaving them
+    /// there.
+    ///
+    /// This and [`get_events`](EventPump::get_events) work on SDL's queue
+    /// directly, so none of the pump's filters or conversions apply. Events
+    /// from an [`EventSender`] can only be taken out of the queue once, so
+    /// they're reported here as `Event::Unknown`.
+    pub fn peek_events(&mut self, max: usize) -> sdl::Result<Vec<Event<T>>> {
+        let raw = peep(max, sys::SDL_EventAction::SDL_PEEKEVENT, EventMask::ALL)?;
+        Ok(raw.iter().map(Event::from_peeked).collect())
+    }
+
+    /// Takes up to `max` events matching `mask` out of the queue, leaving
+    /// any others where they are. Taking every `EventMask::MOUSE_MOTION`
+    /// event at once, for example, is a cheap way to skip a flood of them.
+    pub fn get_events(&mut self, max: usize, mask: EventMask) -> sdl::Result<Vec<Event<T>>> {
+        let raw = peep(max, sys::SDL_EventAction::SDL_GETEVENT, mask)?;
+        Ok(raw.iter().map(decode).collect())
+    }
+
+    /// Adds `events` to the end of the queue, and returns how many were
+    /// added, which is fewer than given if the queue fills up.
+    ///
+    /// Events SDL has no equivalent of, such as `Event::MouseWheel` and
+    /// `Event::User`, are skipped. Use an [`EventSender`] for user events.
+    pub fn add_events(&mut self, events: &[Event<T>]) -> sdl::Result<usize> {
+        sdl::check_callbacks()?;
+        let mut raw: Vec<sys::SDL_Event> = events.iter().filter_map(Event::to_raw).collect();
+        let added = unsafe {
+            sys::SDL_PeepEvents(
+                raw.as_mut_ptr(),
+                raw.len() as c_int,
+                sys::SDL_EventAction::SDL_ADDEVENT,
+                EventMask::ALL.bits(),
+            )
+        };
+        if added < 0 {
+            Err(sdl::get_error())
+        } else {
+            DROPPED_EVENTS.fetch_add((raw.len() - added as usize) as u64, Ordering::Relaxed);
+            Ok(added as usize)
+        }
+    }
+
+    // Pumps SDL's queue and counts what's waiting, to find out if it's been
+    // full, and reports it to the warning callback if there's one.
+    fn check_queue(&mut self) {
+        let dropped_total = DROPPED_EVENTS.load(Ordering::Relaxed);
+        let dropped = dropped_total.wrapping_sub(self.seen_dropped);
+        self.seen_dropped = dropped_total;
+
+        let mut raw = [MaybeUninit::<sys::SDL_Event>::uninit(); QUEUE_CAPACITY];
+        let queued = unsafe {
+            sys::SDL_PumpEvents();
+            sys::SDL_PeepEvents(
+                raw.as_mut_ptr().cast(),
+                QUEUE_CAPACITY as c_int,
+                sys::SDL_EventAction::SDL_PEEKEVENT,
+                EventMask::ALL.bits(),
+            )
+        }
+        .max(0) as usize;
+        let full = queued >= QUEUE_CAPACITY;
+
+        self.queue_stats.dropped += dropped;
+        self.queue_stats.full += full as u64;
+        self.queue_stats.peak = self.queue_stats.peak.max(queued);
+
+        if let Some(warning) = &mut self.queue_warning {
+            if full || dropped > 0 || queued >= warning.threshold {
+                (warning.callback)(QueuePressure {
+                    queued,
+                    full,
+                    dropped,
+                });
+            }
+        }
+    }
+
+    // Applies the filters and conversions which are turned on to an event
+    // from SDL. Returns `None` if it's been filtered out.
+    fn process(&mut self, mut event: Event<T>) -> Option<Event<T>> {
+        if self.filter_key_repeat && self.is_key_repeat(&event) {
+            return None;
+        }
+        if let Some(settings) = self.click_settings {
+            self.count_clicks(&mut event, settings);
+        }
+        if self.text_input {
+            if let Event::Keyboard(KeyboardEvent::KeyDown(Keysym {
+                unicode: Some(c), ..
+            })) = event
+            {
+                if !c.is_control() {
+                    self.pending_text = Some(c);
+                }
+            }
+        }
+        if !self.coalesce_wheel {
+            return Some(event);
+        }
+
+        let delta = match event {
+            Event::MouseButton(MouseButtonEvent {
+                button: Button::WheelUp,
+                ..
+            }) => 1,
+            Event::MouseButton(MouseButtonEvent {
+                button: Button::WheelDown,
+                ..
+            }) => -1,
+            event => return Some(event),
+        };
+
+        // The release always immediately follows the press, so only the
+        // press is reported.
+        match event {
+            Event::MouseButton(MouseButtonEvent { pressed: true, .. }) => {
+                Some(Event::MouseWheel { delta })
+            }
+            _ => None,
+        }
+    }
+
+    /// Like [`poll_event`](EventPump::poll_event), but also records when the
+    /// event was taken off the queue.
+    ///
+    /// SDL 1.2 doesn't record when events happen, so this is the best there
+    /// is. Events which queue up between polls all get about the same time,
+    /// so polling once a frame leaves them up to a frame late.
+    pub fn poll_timed_event(&mut self) -> Option<TimedEvent<T>> {
+        let event = self.poll_event()?;
+        Some(TimedEvent {
+            event,
+            ticks: Ticks::now(),
+            instant: Instant::now(),
+        })
+    }
+
+    fn count_clicks(&mut self, event: &mut Event<T>, settings: ClickSettings) {
+        let too_far = |press: &Press, x: u16, y: u16| {
+            press.x.abs_diff(x) > settings.distance || press.y.abs_diff(y) > settings.distance
+        };
+
+        match event {
+            Event::MouseMotion(motion) => {
+                if let Some(press) = &mut self.last_press {
+                    if too_far(press, motion.x, motion.y) {
+                        press.dragged = true;
+                    }
+                }
+            }
+            Event::MouseButton(button) if button.pressed => {
+                let now = Instant::now();
+                let clicks = match &self.last_press {
+                    Some(press)
+                        if press.button == button.button
+                            && !press.dragged
+                            && !too_far(press, button.x, button.y)
+                            && now.duration_since(press.time) <= settings.interval =>
+                    {
+                        press.clicks.saturating_add(1)
+                    }
+                    _ => 1,
+                };
+                button.clicks = clicks;
+                self.last_press = Some(Press {
+                    button: button.button,
+                    time: now,
+                    x: button.x,
+                    y: button.y,
+                    clicks,
+                    dragged: false,
+                });
+            }
+            Event::MouseButton(button) => {
+                button.clicks = match &self.last_press {
+                    Some(press) if press.button == button.button => {
+                        if press.dragged {
+                            0
+                        } else {
+                            press.clicks
+                        }
+                    }
+                    _ => 1,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    // Keeps track of which keys are down, and returns whether `event` is a
+    // press of a key which already was.
+    fn is_key_repeat(&mut self, event: &Event<T>) -> bool {
+        match event {
+            Event::Keyboard(KeyboardEvent::KeyDown(keysym)) => !self.held_keys.insert(keysym.key),
+            Event::Keyboard(KeyboardEvent::KeyUp(keysym)) => {
+                self.held_keys.remove(&keysym.key);
+                false
+            }
+            // Releases aren't reported while the window is out of focus, so
+            // every key starts over as released.
+            Event::Active(ActiveEvent { gain: false, state })
+                if state.contains(AppState::INPUT_FOCUS) =>
+            {
+                self.held_keys.clear();
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+/// An event along with when it was polled, as returned by
+/// [`EventPump::poll_timed_event`].
+#[derive(Debug)]
+pub struct TimedEvent<UserEvent = ()> {
+    pub event: Event<UserEvent>,
+    /// The time from `SDL_GetTicks`.
+    pub ticks: Ticks,
+    pub instant: Instant,
+}
+
+#[cfg(feature = "async")]
+impl EventPump {
+    /// Splits the pump into an async [`Stream`](futures_core::Stream) of
+    /// events and the [`StreamPump`] which feeds it.
+    ///
+    /// SDL has no way to wake up an executor when events arrive, so the main
+    /// thread has to call [`StreamPump::pump`] regularly, usually once per
+    /// frame. The stream ends once the pump is dropped.
+    pub fn into_stream(self) -> (StreamPump, EventStream) {
+        let shared = Rc::new(RefCell::new(StreamState {
+            queue: VecDeque::new(),
+            waker: None,
+            closed: false,
+        }));
+        let pump = StreamPump {
+            pump: self,
+            shared: Rc::clone(&shared),
+        };
+        (pump, EventStream { shared })
+    }
+}
+
+#[cfg(feature = "async")]
+struct StreamState {
+    queue: VecDeque<Event>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// Moves events from SDL's queue to an [`EventStream`].
+#[cfg(feature = "async")]
+pub struct StreamPump {
+    pump: EventPump,
+    shared: Rc<RefCell<StreamState>>,
+}
+
+#[cfg(feature = "async")]
+impl StreamPump {
+    /// Moves every pending event over to the stream, waking up whatever is
+    /// waiting on it. Returns the number of events moved.
+    pub fn pump(&mut self) -> usize {
+        let mut state = self.shared.borrow_mut();
+        let mut count = 0;
+        while let Some(event) = self.pump.poll_event() {
+            state.queue.push_back(event);
+            count += 1;
+        }
+
+        if count > 0 {
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+        count
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for StreamPump {
+    fn drop(&mut self) {
+        let mut state = self.shared.borrow_mut();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// An async stream of events, fed by a [`StreamPump`].
+#[cfg(feature = "async")]
+pub struct EventStream {
+    shared: Rc<RefCell<StreamState>>,
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut state = self.shared.borrow_mut();
+        if let Some(event) = state.queue.pop_front() {
+            Poll::Ready(Some(event))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+bitflags! {
+    /// The parts of the application's state an [`ActiveEvent`] reports on.
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct AppState: u8 {
+        /// The mouse is over the window.
+        const MOUSE_FOCUS = sys::SDL_APPMOUSEFOCUS;
+        /// The window has keyboard focus.
+        const INPUT_FOCUS = sys::SDL_APPINPUTFOCUS;
+        /// The window is visible, rather than minimized.
+        const ACTIVE = sys::SDL_APPACTIVE;
+    }
+}
+
+bitflags! {
+    /// Which types of event to take from the queue, see
+    /// [`EventPump::get_events`].
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    pub struct EventMask: u32 {
+        const ACTIVE = sys::SDL_EventMask::SDL_ACTIVEEVENTMASK as u32;
+        const KEY_DOWN = sys::SDL_EventMask::SDL_KEYDOWNMASK as u32;
+        const KEY_UP = sys::SDL_EventMask::SDL_KEYUPMASK as u32;
+        const KEYBOARD = sys::SDL_EventMask::SDL_KEYEVENTMASK as u32;
+        const MOUSE_MOTION = sys::SDL_EventMask::SDL_MOUSEMOTIONMASK as u32;
+        const MOUSE_BUTTON_DOWN = sys::SDL_EventMask::SDL_MOUSEBUTTONDOWNMASK as u32;
+        const MOUSE_BUTTON_UP = sys::SDL_EventMask::SDL_MOUSEBUTTONUPMASK as u32;
+        const MOUSE = sys::SDL_EventMask::SDL_MOUSEEVENTMASK as u32;
+        const JOY_AXIS_MOTION = sys::SDL_EventMask::SDL_JOYAXISMOTIONMASK as u32;
+        const JOY_BALL_MOTION = sys::SDL_EventMask::SDL_JOYBALLMOTIONMASK as u32;
+        const JOY_HAT_MOTION = sys::SDL_EventMask::SDL_JOYHATMOTIONMASK as u32;
+        const JOY_BUTTON_DOWN = sys::SDL_EventMask::SDL_JOYBUTTONDOWNMASK as u32;
+        const JOY_BUTTON_UP = sys::SDL_EventMask::SDL_JOYBUTTONUPMASK as u32;
+        const JOYSTICK = sys::SDL_EventMask::SDL_JOYEVENTMASK as u32;
+        const VIDEO_RESIZE = sys::SDL_EventMask::SDL_VIDEORESIZEMASK as u32;
+        const VIDEO_EXPOSE = sys::SDL_EventMask::SDL_VIDEOEXPOSEMASK as u32;
+        const QUIT = sys::SDL_EventMask::SDL_QUITMASK as u32;
+        const SYSWM = sys::SDL_EventMask::SDL_SYSWMEVENTMASK as u32;
+        /// Events sent with an [`EventSender`].
+        const USER = 1 << USEREVENT;
+        const ALL = sys::SDL_ALLEVENTS;
+    }
+}
+
+impl AppState {
+    /// The application's current state, as of the last events which were
+    /// pumped.
+    pub fn current() -> AppState {
+        AppState::from_bits_retain(unsafe { sys::SDL_GetAppState() })
+    }
+}
+
+/// A snapshot of which keys are held down, for games which check the keys
+/// each frame rather than reacting to presses and releases.
+///
+/// SDL updates its key state as events are pumped, so this reflects the
+/// events handled before it was taken.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct KeyboardState {
+    keys: Vec<u8>,
+    modifiers: KeyMod,
+}
+
+impl KeyboardState {
+    /// Copies SDL's current key state.
+    pub fn current() -> KeyboardState {
+        let mut len: c_int = 0;
+        let keys = unsafe {
+            let raw = sys::SDL_GetKeyState(&mut len);
+            if raw.is_null() {
+                Vec::new()
+            } else {
+                slice::from_raw_parts(raw, len.max(0) as usize).to_vec()
+            }
+        };
+
+        KeyboardState {
+            keys,
+            modifiers: mod_state(),
+        }
+    }
+
+    pub fn is_pressed(&self, key: Key) -> bool {
+        let index = key.raw() as usize;
+        self.keys.get(index).is_some_and(|&state| state != 0)
+    }
+
+    /// Returns an iterator over the keys which are held down. Keys SDL has
+    /// no name for are skipped.
+    pub fn pressed(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|&(_, &state)| state != 0)
+            .map(|(sym, _)| Key::from_raw(sym as u32))
+            .filter(|&key| key != Key::Unknown)
+    }
+
+    /// The modifiers held down when the snapshot was taken.
+    pub fn modifiers(&self) -> KeyMod {
+        self.modifiers
+    }
+}
+
+/// Returns which modifier keys are held down, and which locks are on, as of
+/// the events handled so far.
+pub fn mod_state() -> KeyMod {
+    // Like in `Keysym::from_raw`, this is usually a combination of flags,
+    // which isn't a valid value of the generated enum, so it's declared here
+    // returning a plain integer instead.
+    extern "C" {
+        #[link_name = "SDL_GetModState"]
+        fn get_mod_state() -> c_uint;
+    }
+
+    KeyMod::from_bits_retain(unsafe { get_mod_state() })
+}
+
+/// A change in focus or visibility.
+///
+/// A single event can cover several states at once, for example restoring a
+/// minimized window reports both `ACTIVE` and `INPUT_FOCUS`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActiveEvent {
+    /// Whether the states were gained or lost.
+    pub gain: bool,
+    pub state: AppState,
 }
 
 impl From<sys::SDL_ActiveEvent> for ActiveEvent {
     fn from(value: sys::SDL_ActiveEvent) -> Self {
-        match (value.state, value.gain) {
-            (sys::SDL_APPMOUSEFOCUS, 0) => ActiveEvent::MouseLeave,
-            (sys::SDL_APPMOUSEFOCUS, 1) => ActiveEvent::MouseEnter,
-            (sys::SDL_APPINPUTFOCUS, 0) => ActiveEvent::AppUnfocused,
-            (sys::SDL_APPINPUTFOCUS, 1) => ActiveEvent::AppFocused,
-            (sys::SDL_APPACTIVE, 0) => ActiveEvent::Minimized,
-            (sys::SDL_APPACTIVE, 1) => ActiveEvent::Restored,
-            (_, _) => ActiveEvent::Unknown,
+        ActiveEvent {
+            gain: value.gain != 0,
+            state: AppState::from_bits_retain(value.state),
         }
     }
 }
 
 event_from!(Active, ActiveEvent, sys::SDL_ActiveEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyboardEvent {
-    KeyUp(sys::SDL_keysym),
-    KeyDown(sys::SDL_keysym),
+    KeyUp(Keysym),
+    KeyDown(Keysym),
     Unknown,
 }
 
-impl From<sys::SDL_KeyboardEvent> for KeyboardEvent {
-    fn from(value: sys::SDL_KeyboardEvent) -> Self {
+impl KeyboardEvent {
+    fn from_raw(value: &sys::SDL_KeyboardEvent) -> Self {
         match value.state {
-            sys::SDL_RELEASED => KeyboardEvent::KeyUp(value.keysym),
-            sys::SDL_PRESSED => KeyboardEvent::KeyDown(value.keysym),
+            sys::SDL_RELEASED => KeyboardEvent::KeyUp(Keysym::from_raw(&value.keysym)),
+            sys::SDL_PRESSED => KeyboardEvent::KeyDown(Keysym::from_raw(&value.keysym)),
             _ => KeyboardEvent::Unknown,
         }
     }
 }
 
+impl From<sys::SDL_KeyboardEvent> for KeyboardEvent {
+    fn from(value: sys::SDL_KeyboardEvent) -> Self {
+        KeyboardEvent::from_raw(&value)
+    }
+}
+
 event_from!(Keyboard, KeyboardEvent, sys::SDL_KeyboardEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseMotionEvent {
     pub x: u16,
     pub y: u16,
     pub xrel: i16,
     pub yrel: i16,
-    // This event also contains a bitmask representing the current pressed
-    // buttons, but it's incomplete and only supports 8 buttons, so we don't
-    // support it. You should instead use the MouseButtonEvent.
-    //
-    // pub button_state: ButtonStateBitmask,
+    /// The buttons held down during the motion, such as for dragging. Only
+    /// the first 8 buttons are tracked, so use `MouseButtonEvent`s for any
+    /// others.
+    pub buttons: MouseButtonState,
 }
 
 impl From<sys::SDL_MouseMotionEvent> for MouseMotionEvent {
@@ -105,18 +1247,29 @@ impl From<sys::SDL_MouseMotionEvent> for MouseMotionEvent {
             y: value.y,
             xrel: value.xrel,
             yrel: value.yrel,
+            buttons: MouseButtonState::from_bits_retain(value.state),
         }
     }
 }
 
+impl MouseMotionEvent {
+    /// Whether `button` was held down during the motion.
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.buttons.is_pressed(button)
+    }
+}
+
 event_from!(MouseMotion, MouseMotionEvent, sys::SDL_MouseMotionEvent);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ButtonState {
     Pressed = sys::SDL_PRESSED,
     Released = sys::SDL_RELEASED,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Button {
     Left = sys::SDL_BUTTON_LEFT,
@@ -144,11 +1297,32 @@ impl From<u8> for Button {
     }
 }
 
+impl Button {
+    pub fn raw(self) -> u8 {
+        match self {
+            Button::Left => sys::SDL_BUTTON_LEFT,
+            Button::Middle => sys::SDL_BUTTON_MIDDLE,
+            Button::Right => sys::SDL_BUTTON_RIGHT,
+            Button::WheelUp => sys::SDL_BUTTON_WHEELUP,
+            Button::WheelDown => sys::SDL_BUTTON_WHEELDOWN,
+            Button::X1 => sys::SDL_BUTTON_X1,
+            Button::X2 => sys::SDL_BUTTON_X2,
+            Button::Other(button) => button,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseButtonEvent {
     pub button: Button,
     pub pressed: bool,
     pub x: u16,
     pub y: u16,
+    /// 1 for a single click, 2 for a double click and so on. SDL doesn't
+    /// count clicks itself, so this is always 1 unless
+    /// [`EventPump::detect_clicks`] is enabled.
+    pub clicks: u8,
 }
 
 // TODO: impl from
@@ -160,12 +1334,15 @@ impl From<sys::SDL_MouseButtonEvent> for MouseButtonEvent {
             pressed: value.state == sys::SDL_PRESSED,
             x: value.x,
             y: value.y,
+            clicks: 1,
         }
     }
 }
 
 event_from!(MouseButton, MouseButtonEvent, sys::SDL_MouseButtonEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JoyAxisEvent {
     pub device: u8,
     pub axis: u8,
@@ -184,6 +1361,8 @@ impl From<sys::SDL_JoyAxisEvent> for JoyAxisEvent {
 
 event_from!(JoyAxis, JoyAxisEvent, sys::SDL_JoyAxisEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JoyButtonEvent {
     // NOTE: shows up as both SDL_JOYBUTTONDOWN and SDL_JOYBUTTONUP
     pub device: u8,
@@ -203,10 +1382,12 @@ impl From<sys::SDL_JoyButtonEvent> for JoyButtonEvent {
 
 event_from!(JoyButton, JoyButtonEvent, sys::SDL_JoyButtonEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JoyHatEvent {
     pub device: u8,
     pub hat: u8,
-    pub value: u8, // TODO: this has defines which may be helpful
+    pub value: HatState,
 }
 
 impl From<sys::SDL_JoyHatEvent> for JoyHatEvent {
@@ -214,13 +1395,95 @@ impl From<sys::SDL_JoyHatEvent> for JoyHatEvent {
         JoyHatEvent {
             device: value.which,
             hat: value.hat,
-            value: value.value,
+            value: HatState::from_raw(value.value),
+        }
+    }
+}
+
+/// The position of a joystick hat, matching the `SDL_HAT_*` values.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HatState {
+    Centered,
+    Up,
+    RightUp,
+    Right,
+    RightDown,
+    Down,
+    LeftDown,
+    Left,
+    LeftUp,
+}
+
+impl HatState {
+    /// Converts a raw `SDL_HAT_*` value. Opposite directions held at once,
+    /// which some broken hardware reports, cancel each other out.
+    pub fn from_raw(raw: u8) -> HatState {
+        use sys::SDL_HatFlags as Flags;
+
+        let flags = Flags(raw);
+        let axis =
+            |negative, positive| flags.contains(positive) as i8 - flags.contains(negative) as i8;
+        HatState::from_direction(
+            axis(Flags::LEFT, Flags::RIGHT),
+            axis(Flags::UP, Flags::DOWN),
+        )
+    }
+
+    pub fn raw(self) -> u8 {
+        use sys::SDL_HatFlags as Flags;
+
+        let flags = match self {
+            HatState::Centered => Flags::CENTERED,
+            HatState::Up => Flags::UP,
+            HatState::RightUp => Flags::RIGHTUP,
+            HatState::Right => Flags::RIGHT,
+            HatState::RightDown => Flags::RIGHTDOWN,
+            HatState::Down => Flags::DOWN,
+            HatState::LeftDown => Flags::LEFTDOWN,
+            HatState::Left => Flags::LEFT,
+            HatState::LeftUp => Flags::LEFTUP,
+        };
+        flags.bits()
+    }
+
+    /// The position as `x` and `y` each from -1 to 1, with `y` increasing
+    /// downwards like screen coordinates, for moving in the direction held.
+    pub fn direction(self) -> (i8, i8) {
+        match self {
+            HatState::Centered => (0, 0),
+            HatState::Up => (0, -1),
+            HatState::RightUp => (1, -1),
+            HatState::Right => (1, 0),
+            HatState::RightDown => (1, 1),
+            HatState::Down => (0, 1),
+            HatState::LeftDown => (-1, 1),
+            HatState::Left => (-1, 0),
+            HatState::LeftUp => (-1, -1),
+        }
+    }
+
+    /// The inverse of [`direction`](HatState::direction). Components are
+    /// clamped to -1 to 1.
+    pub fn from_direction(x: i8, y: i8) -> HatState {
+        match (x.signum(), y.signum()) {
+            (0, -1) => HatState::Up,
+            (1, -1) => HatState::RightUp,
+            (1, 0) => HatState::Right,
+            (1, 1) => HatState::RightDown,
+            (0, 1) => HatState::Down,
+            (-1, 1) => HatState::LeftDown,
+            (-1, 0) => HatState::Left,
+            (-1, -1) => HatState::LeftUp,
+            _ => HatState::Centered,
         }
     }
 }
 
 event_from!(JoyHat, JoyHatEvent, sys::SDL_JoyHatEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JoyBallEvent {
     pub device: u8,
     pub ball: u8,
@@ -241,6 +1504,8 @@ impl From<sys::SDL_JoyBallEvent> for JoyBallEvent {
 
 event_from!(JoyBall, JoyBallEvent, sys::SDL_JoyBallEvent);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResizeEvent {
     pub w: i32,
     pub h: i32,
@@ -260,14 +1525,38 @@ event_from!(Resize, ResizeEvent, sys::SDL_ResizeEvent);
 #[derive(Debug)]
 pub struct Subsystem {
     _pinned: std::marker::PhantomPinned,
+    _not_send: sdl::NotSend,
 }
 
 impl Drop for Subsystem {
     fn drop(&mut self) {
+        self.clear_event_filter();
         unsafe { sys::SDL_QuitSubSystem(sys::SDL_INIT_EVENTTHREAD) }
     }
 }
 
+type EventFilter = Box<dyn FnMut(&Event) -> bool + Send>;
+
+// The filter installed by `Subsystem::set_event_filter`. SDL 1.2's filters
+// don't take any user data, so there can only be one, and it's global.
+static EVENT_FILTER: Mutex<Option<EventFilter>> = Mutex::new(None);
+
+extern "C" fn event_filter_trampoline(raw: *const sys::SDL_Event) -> c_int {
+    let event = match unsafe { raw.as_ref() } {
+        Some(raw) => Event::from_peeked(raw),
+        None => return 1,
+    };
+
+    let mut filter = EVENT_FILTER.lock().unwrap_or_else(|err| err.into_inner());
+    let keep = match filter.as_mut() {
+        // Events are kept if the filter panics, which is reported by
+        // `check_callbacks`.
+        Some(filter) => sdl::catch_callback_panic(|| filter(&event)).unwrap_or(true),
+        None => true,
+    };
+    keep as c_int
+}
+
 impl Subsystem {
     pub(crate) fn new() -> sdl::Result<Subsystem> {
         if unsafe { sys::SDL_InitSubSystem(sys::SDL_INIT_EVENTTHREAD) } != 0 {
@@ -275,7 +1564,111 @@ impl Subsystem {
         } else {
             Ok(Subsystem {
                 _pinned: PhantomPinned,
+                _not_send: PhantomData,
             })
         }
     }
+
+    /// Returns the next pending event, if there is one.
+    ///
+    /// SDL 1.2 only starts its event queue along with the video subsystem,
+    /// so nothing arrives until that's been initialized. For key repeat
+    /// filtering and the like, use an [`EventPump`] instead. Characters are
+    /// only filled in for key presses once
+    /// [`VideoSubsystem::enable_unicode`](crate::VideoSubsystem::enable_unicode)
+    /// turns translation on.
+    pub fn poll_event(&self) -> Option<Event> {
+        poll_event()
+    }
+
+    /// Waits until there's an event, and returns it.
+    pub fn wait_event(&self) -> sdl::Result<Event> {
+        wait_event()
+    }
+
+    /// Makes held keys repeat their `KeyDown`, or stop repeating with
+    /// `None`, which is the default.
+    pub fn set_key_repeat(&self, repeat: Option<KeyRepeat>) -> sdl::Result<()> {
+        let (delay, interval) = match repeat {
+            // A delay of 0 turns repeat off, so it's at least a millisecond.
+            Some(repeat) => (
+                repeat.delay.as_millis().clamp(1, c_int::MAX as u128) as c_int,
+                repeat.interval.as_millis().min(c_int::MAX as u128) as c_int,
+            ),
+            None => (0, 0),
+        };
+        if unsafe { sys::SDL_EnableKeyRepeat(delay, interval) } != 0 {
+            Err(sdl::get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The current key repeat settings, or `None` if keys don't repeat.
+    pub fn key_repeat(&self) -> Option<KeyRepeat> {
+        let (mut delay, mut interval) = (0, 0);
+        unsafe { sys::SDL_GetKeyRepeat(&mut delay, &mut interval) };
+        (delay > 0).then(|| KeyRepeat {
+            delay: Duration::from_millis(delay as u64),
+            interval: Duration::from_millis(interval.max(0) as u64),
+        })
+    }
+
+    /// Installs `filter` to decide which events are added to the queue,
+    /// replacing any earlier filter. Events it returns `false` for are
+    /// dropped before anything can read them, which is the cheapest way to
+    /// ignore floods of mouse motion, or to intercept `Event::Quit`.
+    ///
+    /// Any events which are already queued are discarded. The filter runs on
+    /// whichever thread pumps events, which is SDL's event thread if there is
+    /// one, so it has to be `Send`. It mustn't set or clear the filter
+    /// itself. It's removed when the subsystem is dropped.
+    pub fn set_event_filter<F>(&self, filter: F)
+    where
+        F: FnMut(&Event) -> bool + Send + 'static,
+    {
+        *EVENT_FILTER.lock().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(filter));
+        // SDL_SetEventFilter empties the queue through the new filter, so the
+        // lock can't be held here.
+        unsafe { sys::SDL_SetEventFilter(Some(event_filter_trampoline)) };
+    }
+
+    /// Removes the filter installed with
+    /// [`set_event_filter`](Subsystem::set_event_filter), if there is one.
+    pub fn clear_event_filter(&self) {
+        unsafe { sys::SDL_SetEventFilter(None) };
+        EVENT_FILTER
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+    }
+}
+
+impl sdl::SDL {
+    pub fn event(&self) -> sdl::Result<Subsystem> {
+        Subsystem::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{wait_for, QUIT_SIGNALLED};
+
+    #[test]
+    fn wait_sees_signal_while_waiting() {
+        let signaller = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            QUIT_SIGNALLED.store(true, Ordering::Relaxed);
+        });
+
+        // Nothing is ever polled, so only the signal can end the wait.
+        let quit = wait_for(|| None, || true);
+        signaller.join().unwrap();
+        assert!(quit);
+        assert!(!QUIT_SIGNALLED.load(Ordering::Relaxed));
+    }
 }
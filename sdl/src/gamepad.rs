@@ -0,0 +1,514 @@
+//! Gamepads with a standard layout, built on top of joysticks.
+//!
+//! SDL 1.2 only knows about joysticks, whose axes and buttons are numbered
+//! however the driver likes. A [`Mapping`] says which of them are the A
+//! button, the left stick and so on, so a [`Gamepad`] can be read the same
+//! way whichever controller is plugged in, like SDL 2's game controllers.
+//!
+//! Mappings are looked up by the joystick's name. There are built in
+//! mappings for common controllers, as named by Linux's drivers, and more
+//! can be added with [`add_mapping`]. Mappings can be written in the format
+//! of SDL 2's `gamecontrollerdb.txt`, see [`Mapping::parse`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::event::HatState;
+use crate::joystick::{self, Joystick};
+use crate::sdl;
+
+/// A button in the standard layout, named after the positions on an Xbox
+/// controller. `A` is the bottom face button, `Y` the top one.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Button {
+    A,
+    B,
+    X,
+    Y,
+    Back,
+    Guide,
+    Start,
+    LeftStick,
+    RightStick,
+    LeftShoulder,
+    RightShoulder,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// An axis in the standard layout.
+///
+/// The sticks range from -32768 to 32767, with negative values up and to
+/// the left. The triggers range from 0 when released to 32767.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Axis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    TriggerLeft,
+    TriggerRight,
+}
+
+impl Axis {
+    fn is_trigger(self) -> bool {
+        matches!(self, Axis::TriggerLeft | Axis::TriggerRight)
+    }
+}
+
+/// The control on the joystick a button or axis of the gamepad reads from.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Binding {
+    Button(u32),
+    Axis(u32),
+    /// An axis read the other way around, so its positive end is negative.
+    InvertedAxis(u32),
+    /// One half of an axis, from its center to one end.
+    HalfAxis {
+        axis: u32,
+        positive: bool,
+    },
+    /// One direction of a hat.
+    Hat {
+        hat: u32,
+        direction: HatState,
+    },
+}
+
+impl Binding {
+    // Parses a binding as gamecontrollerdb.txt writes them, such as `b0`,
+    // `a2`, `+a4`, `a1~` or `h0.1`.
+    fn parse(s: &str) -> Option<Binding> {
+        let index = |s: &str| s.parse::<u32>().ok();
+        if let Some(hat) = s.strip_prefix('h') {
+            let (hat, mask) = hat.split_once('.')?;
+            return Some(Binding::Hat {
+                hat: index(hat)?,
+                direction: HatState::from_raw(mask.parse().ok()?),
+            });
+        }
+
+        let (half, s) = if let Some(rest) = s.strip_prefix('+') {
+            (Some(true), rest)
+        } else if let Some(rest) = s.strip_prefix('-') {
+            (Some(false), rest)
+        } else {
+            (None, s)
+        };
+        let (s, inverted) = match s.strip_suffix('~') {
+            Some(rest) => (rest, true),
+            None => (s, false),
+        };
+        if let Some(button) = s.strip_prefix('b') {
+            return (half.is_none() && !inverted).then_some(Binding::Button(index(button)?));
+        }
+        let axis = index(s.strip_prefix('a')?)?;
+        Some(match (half, inverted) {
+            // The other half of an inverted axis is the one that's used.
+            (Some(positive), _) => Binding::HalfAxis {
+                axis,
+                positive: positive != inverted,
+            },
+            (None, false) => Binding::Axis(axis),
+            (None, true) => Binding::InvertedAxis(axis),
+        })
+    }
+}
+
+/// Which joystick controls make up each button and axis of a gamepad.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Mapping {
+    name: String,
+    platform: Option<String>,
+    buttons: HashMap<Button, Binding>,
+    axes: HashMap<Axis, Binding>,
+}
+
+impl Mapping {
+    /// An empty mapping for joysticks named `name`, to fill in with
+    /// [`button`](Mapping::button) and [`axis`](Mapping::axis).
+    pub fn new(name: &str) -> Mapping {
+        Mapping {
+            name: name.to_owned(),
+            platform: None,
+            buttons: HashMap::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    /// Parses a mapping in the format of SDL 2's `gamecontrollerdb.txt`,
+    /// such as `Pad,a:b0,b:b1,leftx:a0,lefty:a1,dpup:h0.1`. The GUID those
+    /// lines start with is optional, and ignored, since SDL 1.2 has no way
+    /// to get it. Mappings are matched by the name instead, so it has to be
+    /// the name SDL 1.2 gives the joystick.
+    ///
+    /// Fields for controls which aren't part of the layout here, such as
+    /// `misc1`, are skipped. The `platform` field is kept, see
+    /// [`platform`](Mapping::platform).
+    pub fn parse(s: &str) -> Result<Mapping, GamepadError> {
+        let invalid = || GamepadError::InvalidMapping(s.to_owned());
+        let mut fields = s.trim().trim_end_matches(',').split(',');
+        let mut name = fields.next().ok_or_else(invalid)?;
+        if name.len() == 32 && name.bytes().all(|b| b.is_ascii_hexdigit()) {
+            name = fields.next().ok_or_else(invalid)?;
+        }
+
+        let mut mapping = Mapping::new(name);
+        for field in fields {
+            let (control, binding) = field.split_once(':').ok_or_else(invalid)?;
+            if control == "platform" {
+                mapping.platform = Some(binding.to_owned());
+                continue;
+            }
+            let control = match parse_control(control) {
+                Some(control) => control,
+                None => continue,
+            };
+            let binding = Binding::parse(binding).ok_or_else(invalid)?;
+            match control {
+                Control::Button(button) => mapping.buttons.insert(button, binding),
+                Control::Axis(axis) => mapping.axes.insert(axis, binding),
+            };
+        }
+        Ok(mapping)
+    }
+
+    /// The name of the joysticks this mapping is for.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The OS the mapping is for, such as `Linux` or `Windows`, if it says.
+    /// The same controller is often numbered differently on each one.
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_deref()
+    }
+
+    pub fn button(mut self, button: Button, binding: Binding) -> Mapping {
+        self.buttons.insert(button, binding);
+        self
+    }
+
+    pub fn axis(mut self, axis: Axis, binding: Binding) -> Mapping {
+        self.axes.insert(axis, binding);
+        self
+    }
+
+    /// What `button` is bound to, if it's bound to anything.
+    pub fn button_binding(&self, button: Button) -> Option<Binding> {
+        self.buttons.get(&button).copied()
+    }
+
+    /// What `axis` is bound to, if it's bound to anything.
+    pub fn axis_binding(&self, axis: Axis) -> Option<Binding> {
+        self.axes.get(&axis).copied()
+    }
+}
+
+enum Control {
+    Button(Button),
+    Axis(Axis),
+}
+
+fn parse_control(name: &str) -> Option<Control> {
+    Some(match name {
+        "a" => Control::Button(Button::A),
+        "b" => Control::Button(Button::B),
+        "x" => Control::Button(Button::X),
+        "y" => Control::Button(Button::Y),
+        "back" => Control::Button(Button::Back),
+        "guide" => Control::Button(Button::Guide),
+        "start" => Control::Button(Button::Start),
+        "leftstick" => Control::Button(Button::LeftStick),
+        "rightstick" => Control::Button(Button::RightStick),
+        "leftshoulder" => Control::Button(Button::LeftShoulder),
+        "rightshoulder" => Control::Button(Button::RightShoulder),
+        "dpup" => Control::Button(Button::DPadUp),
+        "dpdown" => Control::Button(Button::DPadDown),
+        "dpleft" => Control::Button(Button::DPadLeft),
+        "dpright" => Control::Button(Button::DPadRight),
+        "leftx" => Control::Axis(Axis::LeftX),
+        "lefty" => Control::Axis(Axis::LeftY),
+        "rightx" => Control::Axis(Axis::RightX),
+        "righty" => Control::Axis(Axis::RightY),
+        "lefttrigger" => Control::Axis(Axis::TriggerLeft),
+        "righttrigger" => Control::Axis(Axis::TriggerRight),
+        _ => return None,
+    })
+}
+
+// The layout Linux's xpad driver gives Xbox controllers and their clones.
+const XPAD_LAYOUT: &str = "a:b0,b:b1,x:b2,y:b3,back:b6,guide:b8,start:b7,leftstick:b9,\
+    rightstick:b10,leftshoulder:b4,rightshoulder:b5,dpup:h0.1,dpdown:h0.4,dpleft:h0.8,\
+    dpright:h0.2,leftx:a0,lefty:a1,rightx:a3,righty:a4,lefttrigger:a2,righttrigger:a5";
+
+// The layout Linux's hid-sony driver gives the DualShock 4.
+const DS4_LAYOUT: &str = "a:b0,b:b1,x:b3,y:b2,back:b8,guide:b10,start:b9,leftstick:b11,\
+    rightstick:b12,leftshoulder:b4,rightshoulder:b5,dpup:h0.1,dpdown:h0.4,dpleft:h0.8,\
+    dpright:h0.2,leftx:a0,lefty:a1,rightx:a3,righty:a4,lefttrigger:a2,righttrigger:a5";
+
+const BUILT_IN: &[(&str, &str)] = &[
+    ("Microsoft X-Box 360 pad", XPAD_LAYOUT),
+    ("Xbox 360 Wireless Receiver", XPAD_LAYOUT),
+    ("Microsoft X-Box One pad", XPAD_LAYOUT),
+    ("Microsoft Xbox One S pad", XPAD_LAYOUT),
+    ("Logitech Gamepad F310", XPAD_LAYOUT),
+    ("Logitech Gamepad F710", XPAD_LAYOUT),
+    (
+        "Sony Interactive Entertainment Wireless Controller",
+        DS4_LAYOUT,
+    ),
+    (
+        "Sony Computer Entertainment Wireless Controller",
+        DS4_LAYOUT,
+    ),
+    ("Wireless Controller", DS4_LAYOUT),
+];
+
+// The mappings by joystick name, filled with the built in ones on first use.
+static MAPPINGS: Mutex<Option<HashMap<String, Mapping>>> = Mutex::new(None);
+
+fn with_mappings<R>(f: impl FnOnce(&mut HashMap<String, Mapping>) -> R) -> R {
+    let mut mappings = MAPPINGS.lock().unwrap_or_else(|err| err.into_inner());
+    let mappings = mappings.get_or_insert_with(|| {
+        BUILT_IN
+            .iter()
+            .map(|&(name, layout)| {
+                let mapping = Mapping::parse(&format!("{},{}", name, layout))
+                    .expect("built in gamepad mappings are valid");
+                (name.to_owned(), mapping)
+            })
+            .collect()
+    });
+    f(mappings)
+}
+
+/// Adds a mapping, replacing any there was for joysticks of the same name.
+/// It's used by gamepads opened from then on.
+pub fn add_mapping(mapping: Mapping) {
+    with_mappings(|mappings| mappings.insert(mapping.name.clone(), mapping));
+}
+
+/// Adds every mapping in `db`, in the format of SDL 2's
+/// `gamecontrollerdb.txt`, one per line. Blank lines, lines starting with
+/// `#` and mappings for other platforms than this one are skipped. Returns
+/// the number of mappings added.
+pub fn add_mappings_from_str(db: &str) -> Result<usize, GamepadError> {
+    let mut count = 0;
+    for line in db.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mapping = Mapping::parse(line)?;
+        if mapping
+            .platform()
+            .is_some_and(|platform| platform != PLATFORM)
+        {
+            continue;
+        }
+        add_mapping(mapping);
+        count += 1;
+    }
+    Ok(count)
+}
+
+// This OS, as SDL 2 names it in the `platform` field of mappings.
+const PLATFORM: &str = if cfg!(windows) {
+    "Windows"
+} else if cfg!(target_os = "macos") {
+    "Mac OS X"
+} else if cfg!(target_os = "linux") {
+    "Linux"
+} else if cfg!(target_os = "freebsd") {
+    "FreeBSD"
+} else if cfg!(target_os = "openbsd") {
+    "OpenBSD"
+} else if cfg!(target_os = "netbsd") {
+    "NetBSD"
+} else if cfg!(target_os = "emscripten") {
+    "Emscripten"
+} else {
+    "Unknown"
+};
+
+/// The mapping for joysticks named `name`, if there is one.
+pub fn mapping_for(name: &str) -> Option<Mapping> {
+    with_mappings(|mappings| mappings.get(name).cloned())
+}
+
+/// Whether there's a mapping for the joystick at `index`, so it can be
+/// opened as a [`Gamepad`].
+pub fn is_gamepad(index: u32) -> bool {
+    joystick::name(index).is_some_and(|name| mapping_for(&name).is_some())
+}
+
+/// A joystick read through a [`Mapping`].
+///
+/// The state is read from the joystick as of the last time events were
+/// pumped, or [`joystick::Subsystem::update`] was called.
+#[derive(Debug)]
+pub struct Gamepad {
+    joystick: Joystick,
+    mapping: Mapping,
+}
+
+impl Gamepad {
+    /// Opens the joystick at `index` as a gamepad, using the mapping for its
+    /// name.
+    pub fn open(subsystem: &joystick::Subsystem, index: u32) -> Result<Gamepad, GamepadError> {
+        let name = joystick::name(index).unwrap_or_default();
+        let mapping = mapping_for(&name).ok_or(GamepadError::NoMapping(name))?;
+        Ok(Gamepad::with_mapping(subsystem.open(index)?, mapping))
+    }
+
+    /// Reads an already open joystick through `mapping`, whatever its name.
+    pub fn with_mapping(joystick: Joystick, mapping: Mapping) -> Gamepad {
+        Gamepad { joystick, mapping }
+    }
+
+    pub fn joystick(&self) -> &Joystick {
+        &self.joystick
+    }
+
+    pub fn mapping(&self) -> &Mapping {
+        &self.mapping
+    }
+
+    /// Whether `button` is held down. Buttons the mapping doesn't have read
+    /// as released.
+    pub fn button(&self, button: Button) -> bool {
+        let binding = match self.mapping.button_binding(button) {
+            Some(binding) => binding,
+            None => return false,
+        };
+        match binding {
+            Binding::Button(button) => self.joystick.button(button),
+            // An axis counts as pressed once it's past halfway.
+            Binding::Axis(axis) => self.joystick.axis(axis) > i16::MAX / 2,
+            Binding::InvertedAxis(axis) => invert(self.joystick.axis(axis)) > i16::MAX / 2,
+            Binding::HalfAxis { axis, positive } => {
+                half_axis(self.joystick.axis(axis), positive) > i16::MAX / 2
+            }
+            Binding::Hat { hat, direction } => self.hat_pressed(hat, direction),
+        }
+    }
+
+    /// The position of `axis`. Axes the mapping doesn't have read as
+    /// centered, or released for the triggers.
+    pub fn axis(&self, axis: Axis) -> i16 {
+        let binding = match self.mapping.axis_binding(axis) {
+            Some(binding) => binding,
+            None => return 0,
+        };
+        // Sticks bound to buttons only go one way, to the positive end.
+        let pressed = |pressed: bool| if pressed { i16::MAX } else { 0 };
+        match binding {
+            Binding::Button(button) => pressed(self.joystick.button(button)),
+            Binding::Axis(raw) => full_axis(axis, self.joystick.axis(raw)),
+            Binding::InvertedAxis(raw) => full_axis(axis, invert(self.joystick.axis(raw))),
+            Binding::HalfAxis { axis, positive } => half_axis(self.joystick.axis(axis), positive),
+            Binding::Hat { hat, direction } => pressed(self.hat_pressed(hat, direction)),
+        }
+    }
+
+    fn hat_pressed(&self, hat: u32, direction: HatState) -> bool {
+        self.joystick.hat(hat).raw() & direction.raw() != 0
+    }
+}
+
+// A value read from a whole axis, as `axis` reports it.
+fn full_axis(axis: Axis, value: i16) -> i16 {
+    if axis.is_trigger() {
+        // Triggers which span a whole axis rest at its negative end.
+        ((value as i32 + 32768) / 2) as i16
+    } else {
+        value
+    }
+}
+
+// Flips an axis value end to end, so -32768 becomes 32767 and 0 becomes -1.
+fn invert(value: i16) -> i16 {
+    !value
+}
+
+// How far along one half of an axis a value is, from 0 to 32767.
+fn half_axis(value: i16, positive: bool) -> i16 {
+    if positive {
+        value.max(0)
+    } else {
+        (-(value as i32)).clamp(0, i16::MAX as i32) as i16
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GamepadError {
+    #[error("no gamepad mapping for joystick {:?}", .0)]
+    NoMapping(String),
+    #[error("invalid gamepad mapping {:?}", .0)]
+    InvalidMapping(String),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bindings() {
+        assert_eq!(Binding::parse("b3"), Some(Binding::Button(3)));
+        assert_eq!(Binding::parse("a1~"), Some(Binding::InvertedAxis(1)));
+        assert_eq!(
+            Binding::parse("+a2~"),
+            Some(Binding::HalfAxis {
+                axis: 2,
+                positive: false,
+            })
+        );
+        assert_eq!(
+            Binding::parse("h0.4"),
+            Some(Binding::Hat {
+                hat: 0,
+                direction: HatState::from_raw(4),
+            })
+        );
+        assert_eq!(Binding::parse("+b1"), None);
+    }
+
+    #[test]
+    fn parses_mapping_with_guid() {
+        let mapping = Mapping::parse(
+            "030000005e0400008e02000014010000,Test Pad,a:b0,lefty:a1~,misc1:b15,dpdown:h0.4,",
+        )
+        .unwrap();
+        assert_eq!(mapping.name(), "Test Pad");
+        assert_eq!(mapping.platform(), None);
+        assert_eq!(mapping.button_binding(Button::A), Some(Binding::Button(0)));
+        assert_eq!(
+            mapping.axis_binding(Axis::LeftY),
+            Some(Binding::InvertedAxis(1))
+        );
+        assert_eq!(
+            mapping.button_binding(Button::DPadDown),
+            Some(Binding::Hat {
+                hat: 0,
+                direction: HatState::from_raw(4),
+            })
+        );
+    }
+
+    #[test]
+    fn skips_mappings_for_other_platforms() {
+        let other = if PLATFORM == "Windows" {
+            "Linux"
+        } else {
+            "Windows"
+        };
+        let line = format!("Other OS Pad,a:b1,platform:{},", other);
+        assert_eq!(Mapping::parse(&line).unwrap().platform(), Some(other));
+        assert_eq!(add_mappings_from_str(&line).unwrap(), 0);
+        assert_eq!(mapping_for("Other OS Pad"), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Frame-based animations over the sprites of a [`SpriteSheet`].
+
+use std::time::Duration;
+
+use crate::gfx::atlas::{AtlasError, SpriteSheet};
+use crate::video::Surface;
+
+/// What an [`Animation`] does once it reaches its last frame.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoopMode {
+    /// Stop on the last frame.
+    Once,
+    /// Start over from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+/// A single frame of an [`Animation`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frame {
+    /// The index of the sprite in the sprite sheet.
+    pub sprite: usize,
+    pub duration: Duration,
+}
+
+/// Keeps track of which frame of an animation is showing.
+///
+/// Call [`update`](Animation::update) once per frame with the elapsed time,
+/// then draw [`current_frame`](Animation::current_frame).
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<Frame>,
+    mode: LoopMode,
+    position: usize,
+    frame_elapsed: Duration,
+    forward: bool,
+    finished: bool,
+}
+
+impl Animation {
+    /// Creates an animation which shows each sprite for `frame_duration`.
+    pub fn new<I>(sprites: I, frame_duration: Duration, mode: LoopMode) -> Animation
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let frames = sprites
+            .into_iter()
+            .map(|sprite| Frame {
+                sprite,
+                duration: frame_duration,
+            })
+            .collect();
+        Animation::with_frames(frames, mode)
+    }
+
+    /// Creates an animation where every frame has its own duration.
+    pub fn with_frames(frames: Vec<Frame>, mode: LoopMode) -> Animation {
+        Animation {
+            frames,
+            mode,
+            position: 0,
+            frame_elapsed: Duration::ZERO,
+            forward: true,
+            finished: false,
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn mode(&self) -> LoopMode {
+        self.mode
+    }
+
+    /// Rewinds the animation to its first frame.
+    pub fn reset(&mut self) {
+        self.position = 0;
+        self.frame_elapsed = Duration::ZERO;
+        self.forward = true;
+        self.finished = false;
+    }
+
+    /// Whether a [`LoopMode::Once`] animation has reached its end. Looping
+    /// animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the sprite index of the frame which is showing, or `None` if
+    /// the animation has no frames.
+    pub fn current_frame(&self) -> Option<usize> {
+        self.frames.get(self.position).map(|frame| frame.sprite)
+    }
+
+    /// Advances the animation by `dt`.
+    pub fn update(&mut self, dt: Duration) {
+        if self.frames.is_empty() || self.finished {
+            return;
+        }
+
+        // Looping animations end up where they started after a whole cycle,
+        // so those can be skipped. This also keeps long pauses from stepping
+        // through thousands of frames.
+        let mut remaining = self.frame_elapsed;
+        if self.mode == LoopMode::Once {
+            remaining += dt;
+        } else {
+            let cycle = self.cycle().as_nanos();
+            if cycle == 0 {
+                return;
+            }
+            remaining += Duration::from_nanos((dt.as_nanos() % cycle) as u64);
+        }
+
+        while remaining >= self.frames[self.position].duration {
+            remaining -= self.frames[self.position].duration;
+            self.advance();
+            if self.finished {
+                remaining = Duration::ZERO;
+                break;
+            }
+        }
+        self.frame_elapsed = remaining;
+    }
+
+    /// Blits the current frame from `sheet` to `dst`. Does nothing if the
+    /// animation has no frames.
+    pub fn draw(
+        &self,
+        sheet: &SpriteSheet,
+        dst: &mut Surface,
+        x: i16,
+        y: i16,
+    ) -> Result<(), AtlasError> {
+        match self.current_frame() {
+            Some(sprite) => sheet.blit_sprite(sprite, dst, x, y),
+            None => Ok(()),
+        }
+    }
+
+    // The time it takes to get back to the same frame, heading in the same
+    // direction.
+    fn cycle(&self) -> Duration {
+        let total: Duration = self.frames.iter().map(|frame| frame.duration).sum();
+        match (self.mode, self.frames.as_slice()) {
+            (LoopMode::PingPong, [first, .., last]) => total * 2 - first.duration - last.duration,
+            _ => total,
+        }
+    }
+
+    fn advance(&mut self) {
+        let last = self.frames.len() - 1;
+        match self.mode {
+            LoopMode::Once if self.position == last => self.finished = true,
+            LoopMode::Once => self.position += 1,
+            LoopMode::Loop if self.position == last => self.position = 0,
+            LoopMode::Loop => self.position += 1,
+            LoopMode::PingPong if last == 0 => {}
+            LoopMode::PingPong => {
+                if self.forward && self.position == last {
+                    self.forward = false;
+                } else if !self.forward && self.position == 0 {
+                    self.forward = true;
+                }
+
+                if self.forward {
+                    self.position += 1;
+                } else {
+                    self.position -= 1;
+                }
+            }
+        }
+    }
+}
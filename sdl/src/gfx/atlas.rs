@@ -0,0 +1,215 @@
+//! Sprite sheets, which pack many small images into a single surface.
+
+use std::collections::{BTreeMap, HashMap};
+#[cfg(any(feature = "json", feature = "toml"))]
+use std::path::Path;
+
+use crate::sdl;
+use crate::video::Surface;
+use crate::{Point, Rect};
+
+/// Identifies a sprite in a [`SpriteSheet`], either by index or by name.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Sprite<'a> {
+    Index(usize),
+    Name(&'a str),
+}
+
+impl From<usize> for Sprite<'_> {
+    fn from(index: usize) -> Self {
+        Sprite::Index(index)
+    }
+}
+
+impl<'a> From<&'a str> for Sprite<'a> {
+    fn from(name: &'a str) -> Self {
+        Sprite::Name(name)
+    }
+}
+
+/// A grid of equally sized cells, read left to right, top to bottom.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid {
+    pub width: u16,
+    pub height: u16,
+    /// The space around the whole grid.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub margin: u16,
+    /// The space between neighbouring cells.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spacing: u16,
+}
+
+impl Grid {
+    pub const fn new(width: u16, height: u16) -> Grid {
+        Grid {
+            width,
+            height,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    /// Returns the cells which fit entirely within a surface of the given
+    /// size.
+    pub fn cells(&self, surface_width: u32, surface_height: u32) -> Vec<Rect> {
+        if self.width == 0 || self.height == 0 {
+            return Vec::new();
+        }
+
+        let (margin, spacing) = (self.margin as u32, self.spacing as u32);
+        let count = |len: u32, cell: u16| {
+            let usable = len.saturating_sub(2 * margin) + spacing;
+            usable / (cell as u32 + spacing)
+        };
+
+        let mut cells = Vec::new();
+        for row in 0..count(surface_height, self.height) {
+            for col in 0..count(surface_width, self.width) {
+                let x = margin + col * (self.width as u32 + spacing);
+                let y = margin + row * (self.height as u32 + spacing);
+                cells.push(Rect::new(x as i16, y as i16, self.width, self.height));
+            }
+        }
+        cells
+    }
+}
+
+/// The layout of a sprite sheet, as it would be stored alongside the image.
+///
+/// With the `json` or `toml` feature enabled this can be loaded from a file,
+/// for example:
+///
+/// ```toml
+/// grid = { width = 16, height = 16 }
+///
+/// [regions]
+/// logo = { x = 0, y = 64, w = 64, h = 32 }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtlasMetadata {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub grid: Option<Grid>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub regions: BTreeMap<String, Rect>,
+}
+
+impl AtlasMetadata {
+    #[cfg(feature = "json")]
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<AtlasMetadata, AtlasError> {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(|err| AtlasError::InvalidMetadata(err.to_string()))
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<AtlasMetadata, AtlasError> {
+        let data = std::fs::read_to_string(path)?;
+        toml::from_str(&data).map_err(|err| AtlasError::InvalidMetadata(err.to_string()))
+    }
+}
+
+/// A surface split up into sprites.
+///
+/// Sprites are numbered in the order they're added, starting with the cells
+/// of the grid, if there is one. Named regions get the next indices in order
+/// of their names.
+#[derive(Debug)]
+pub struct SpriteSheet {
+    surface: Surface,
+    regions: Vec<Rect>,
+    names: HashMap<String, usize>,
+}
+
+impl SpriteSheet {
+    /// Creates a sprite sheet without any sprites.
+    pub fn new(surface: Surface) -> SpriteSheet {
+        SpriteSheet {
+            surface,
+            regions: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Creates a sprite sheet where every cell of `grid` is a sprite.
+    pub fn grid(surface: Surface, grid: Grid) -> SpriteSheet {
+        let mut sheet = SpriteSheet::new(surface);
+        sheet.regions = grid.cells(sheet.surface.width(), sheet.surface.height());
+        sheet
+    }
+
+    pub fn with_metadata(surface: Surface, metadata: AtlasMetadata) -> SpriteSheet {
+        let mut sheet = match metadata.grid {
+            Some(grid) => SpriteSheet::grid(surface, grid),
+            None => SpriteSheet::new(surface),
+        };
+        for (name, rect) in metadata.regions {
+            sheet.add_region(name, rect);
+        }
+        sheet
+    }
+
+    /// Adds a named sprite and returns its index. A name which is already
+    /// taken is moved to the new sprite.
+    pub fn add_region<S: Into<String>>(&mut self, name: S, rect: Rect) -> usize {
+        let index = self.regions.len();
+        self.regions.push(rect);
+        self.names.insert(name.into(), index);
+        index
+    }
+
+    pub fn surface(&self) -> &Surface {
+        &self.surface
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns where a sprite is on the sheet.
+    pub fn region<'a, S: Into<Sprite<'a>>>(&self, sprite: S) -> Option<Rect> {
+        let index = match sprite.into() {
+            Sprite::Index(index) => index,
+            Sprite::Name(name) => self.index_of(name)?,
+        };
+        self.regions.get(index).copied()
+    }
+
+    /// Blits a sprite to `dst` with its top left corner at `(x, y)`.
+    pub fn blit_sprite<'a, S: Into<Sprite<'a>>>(
+        &self,
+        sprite: S,
+        dst: &mut Surface,
+        x: i16,
+        y: i16,
+    ) -> Result<(), AtlasError> {
+        let sprite = sprite.into();
+        let region = self
+            .region(sprite)
+            .ok_or_else(|| AtlasError::UnknownSprite(format!("{:?}", sprite)))?;
+        self.surface
+            .blit(Some(region), dst, Point::new(x, y))
+            .map_err(AtlasError::from)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AtlasError {
+    #[error("unknown sprite: {}", .0)]
+    UnknownSprite(String),
+    #[error("invalid atlas metadata: {}", .0)]
+    InvalidMetadata(String),
+    #[error("IO error: {}", .0)]
+    IoError(#[from] std::io::Error),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
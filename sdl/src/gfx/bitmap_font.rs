@@ -0,0 +1,220 @@
+//! Text rendering from pre-drawn glyphs on a sprite sheet.
+//!
+//! This is a lot cheaper than rendering with SDL_ttf, which makes it a good
+//! fit for things like score counters which change every frame.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::gfx::atlas::{AtlasError, Grid, SpriteSheet};
+use crate::video::Surface;
+use crate::Rect;
+
+/// Where a character is on the sheet and how it's placed.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Glyph {
+    /// The index of the glyph's sprite in the sheet.
+    pub sprite: usize,
+    /// The offset from the pen position to the top left of the sprite.
+    pub x_offset: i16,
+    pub y_offset: i16,
+    /// How far the pen moves after drawing the glyph.
+    pub advance: u16,
+}
+
+/// A font made up of glyphs on a [`SpriteSheet`].
+///
+/// Kerning isn't supported; every glyph moves the pen by its own advance.
+#[derive(Debug)]
+pub struct BitmapFont {
+    sheet: SpriteSheet,
+    glyphs: HashMap<char, Glyph>,
+    line_height: u16,
+    fallback: Option<char>,
+}
+
+impl BitmapFont {
+    /// Creates a font from glyphs laid out in a grid, in the order given by
+    /// `chars`. Every glyph advances by the width of a cell.
+    pub fn grid(surface: Surface, grid: Grid, chars: &str) -> BitmapFont {
+        let sheet = SpriteSheet::grid(surface, grid);
+        let glyphs = chars
+            .chars()
+            .enumerate()
+            .take(sheet.len())
+            .map(|(sprite, c)| {
+                let glyph = Glyph {
+                    sprite,
+                    x_offset: 0,
+                    y_offset: 0,
+                    advance: grid.width,
+                };
+                (c, glyph)
+            })
+            .collect();
+
+        BitmapFont {
+            sheet,
+            glyphs,
+            line_height: grid.height,
+            fallback: None,
+        }
+    }
+
+    /// Creates a font from a grid holding the printable ASCII characters,
+    /// from space to `~`.
+    pub fn ascii_grid(surface: Surface, grid: Grid) -> BitmapFont {
+        let chars: String = (' '..='~').collect();
+        BitmapFont::grid(surface, grid, &chars)
+    }
+
+    /// Creates a font from a descriptor in the text format of AngelCode's
+    /// BMFont. Only single page fonts are supported, and `surface` is that
+    /// page. Kerning pairs are ignored.
+    pub fn from_bmfont(surface: Surface, descriptor: &str) -> Result<BitmapFont, AtlasError> {
+        let mut sheet = SpriteSheet::new(surface);
+        let mut glyphs = HashMap::new();
+        let mut line_height = 0;
+
+        for line in descriptor.lines() {
+            let mut tokens = line.split_whitespace();
+            let tag = match tokens.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let attrs = BmAttrs::parse(tokens);
+
+            match tag {
+                "common" => {
+                    line_height = attrs.get("lineHeight")?;
+                    if attrs.get::<u32>("pages")? != 1 {
+                        return Err(bmfont_error("only single page fonts are supported"));
+                    }
+                }
+                "char" => {
+                    let id: u32 = attrs.get("id")?;
+                    let c = char::from_u32(id).ok_or_else(|| bmfont_error("invalid char id"))?;
+                    let rect = Rect::new(
+                        attrs.get("x")?,
+                        attrs.get("y")?,
+                        attrs.get("width")?,
+                        attrs.get("height")?,
+                    );
+                    let glyph = Glyph {
+                        sprite: sheet.add_region(c.to_string(), rect),
+                        x_offset: attrs.get("xoffset")?,
+                        y_offset: attrs.get("yoffset")?,
+                        advance: attrs.get("xadvance")?,
+                    };
+                    glyphs.insert(c, glyph);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(BitmapFont {
+            sheet,
+            glyphs,
+            line_height,
+            fallback: None,
+        })
+    }
+
+    /// Loads a BMFont text descriptor from a file. See
+    /// [`from_bmfont`](BitmapFont::from_bmfont).
+    pub fn load_bmfont<P: AsRef<Path>>(
+        surface: Surface,
+        path: P,
+    ) -> Result<BitmapFont, AtlasError> {
+        let descriptor = std::fs::read_to_string(path)?;
+        BitmapFont::from_bmfont(surface, &descriptor)
+    }
+
+    /// Sets a character to draw in place of any the font doesn't have. By
+    /// default they're skipped.
+    pub fn set_fallback(&mut self, fallback: Option<char>) {
+        self.fallback = fallback;
+    }
+
+    pub fn line_height(&self) -> u16 {
+        self.line_height
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.fallback.and_then(|c| self.glyphs.get(&c)))
+    }
+
+    /// Returns the width and height `text` takes up when drawn.
+    pub fn text_size(&self, text: &str) -> (u32, u32) {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let line_width: u32 = line
+                .chars()
+                .filter_map(|c| self.glyph(c))
+                .map(|glyph| glyph.advance as u32)
+                .sum();
+            width = width.max(line_width);
+            lines += 1;
+        }
+        (width, lines * self.line_height as u32)
+    }
+
+    /// Draws `text` to `dst` with the top left corner of the first line at
+    /// `(x, y)`. Newlines start a new line.
+    pub fn draw_text(
+        &self,
+        text: &str,
+        dst: &mut Surface,
+        x: i16,
+        y: i16,
+    ) -> Result<(), AtlasError> {
+        let (mut pen_x, mut pen_y) = (x, y);
+        for c in text.chars() {
+            if c == '\n' {
+                pen_x = x;
+                pen_y = pen_y.saturating_add(self.line_height as i16);
+                continue;
+            }
+
+            if let Some(glyph) = self.glyph(c) {
+                self.sheet.blit_sprite(
+                    glyph.sprite,
+                    dst,
+                    pen_x.saturating_add(glyph.x_offset),
+                    pen_y.saturating_add(glyph.y_offset),
+                )?;
+                pen_x = pen_x.saturating_add(glyph.advance as i16);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The key=value pairs following the tag on each line of a BMFont descriptor.
+// Values may be quoted, but the ones we need never contain spaces.
+struct BmAttrs<'a>(HashMap<&'a str, &'a str>);
+
+impl<'a> BmAttrs<'a> {
+    fn parse<I: Iterator<Item = &'a str>>(tokens: I) -> BmAttrs<'a> {
+        BmAttrs(
+            tokens
+                .filter_map(|token| token.split_once('='))
+                .map(|(key, value)| (key, value.trim_matches('"')))
+                .collect(),
+        )
+    }
+
+    fn get<T: std::str::FromStr>(&self, key: &str) -> Result<T, AtlasError> {
+        self.0
+            .get(key)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| bmfont_error(&format!("missing or invalid {}", key)))
+    }
+}
+
+fn bmfont_error(msg: &str) -> AtlasError {
+    AtlasError::InvalidMetadata(format!("BMFont: {}", msg))
+}
@@ -0,0 +1,162 @@
+//! Fills which write the pixels of a surface directly: noise, and ordered
+//! dithering for gradients and blends in the style of old hardware with few
+//! colors.
+//!
+//! These all lock the surface and respect its clip rectangle, and the same
+//! arguments always produce the same pixels.
+
+use crate::sdl;
+use crate::video::Surface;
+use crate::Rect;
+
+// A 4x4 Bayer matrix. Pixels whose entry is below a fraction of 16 get the
+// next color up, which spreads each mix of two colors evenly.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// The direction a gradient runs in, see [`fill_dithered_gradient`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Gradient {
+    /// From the left edge to the right.
+    Horizontal,
+    /// From the top edge to the bottom.
+    Vertical,
+}
+
+/// Fills `rect` with `base`, brightening or darkening each pixel by up to
+/// `intensity`, for a grainy texture.
+///
+/// The noise depends only on `seed` and the position of each pixel on the
+/// surface, so filling overlapping rectangles with the same seed gives
+/// matching noise.
+pub fn draw_noise_box(
+    surface: &mut Surface,
+    rect: Rect,
+    base: sdl::Color,
+    intensity: u8,
+    seed: u64,
+) -> sdl::Result<()> {
+    let range = intensity as u32 * 2 + 1;
+    fill_with(surface, rect, |x, y| {
+        let offset = (noise(seed, x, y) % range) as i16 - intensity as i16;
+        let shift = |c: u8| (c as i16 + offset).clamp(0, 255) as u8;
+        sdl::Color::rgba(shift(base.r), shift(base.g), shift(base.b), base.a)
+    })
+}
+
+/// Fills `rect` with a mix of `a` and `b` in a fixed pattern, so that
+/// `amount` of the pixels are `b`. An `amount` of 0.5 gives a checkerboard.
+pub fn fill_dithered(
+    surface: &mut Surface,
+    rect: Rect,
+    a: sdl::Color,
+    b: sdl::Color,
+    amount: f32,
+) -> sdl::Result<()> {
+    fill_with(
+        surface,
+        rect,
+        |x, y| {
+            if amount > threshold(x, y) {
+                b
+            } else {
+                a
+            }
+        },
+    )
+}
+
+/// Fills `rect` with a gradient from `from` to `to` of only `levels` shades,
+/// including both ends, dithering between neighbouring shades to smooth it
+/// out. Fewer levels give a more retro look.
+pub fn fill_dithered_gradient(
+    surface: &mut Surface,
+    rect: Rect,
+    from: sdl::Color,
+    to: sdl::Color,
+    gradient: Gradient,
+    levels: u8,
+) -> sdl::Result<()> {
+    let steps = levels.max(2) as f32 - 1.0;
+    let len = match gradient {
+        Gradient::Horizontal => rect.w,
+        Gradient::Vertical => rect.h,
+    };
+    let len = (len as f32 - 1.0).max(1.0);
+
+    fill_with(surface, rect, |x, y| {
+        let along = match gradient {
+            Gradient::Horizontal => x - rect.x as i32,
+            Gradient::Vertical => y - rect.y as i32,
+        };
+        let shade = along as f32 / len * steps;
+        let mut level = shade.floor();
+        if shade - level > threshold(x, y) {
+            level += 1.0;
+        }
+        lerp(from, to, level / steps)
+    })
+}
+
+// Writes `color(x, y)` to every pixel of `rect` inside the clip rectangle,
+// with `x` and `y` relative to the surface.
+fn fill_with<F>(surface: &mut Surface, rect: Rect, color: F) -> sdl::Result<()>
+where
+    F: Fn(i32, i32) -> sdl::Color,
+{
+    let Some(area) = rect.intersection(surface.clip_rect()) else {
+        return Ok(());
+    };
+    let bpp = surface.format().bytes_per_pixel() as usize;
+
+    let mut lock = surface.lock()?;
+    let pitch = lock.pitch();
+    // Mapping colors needs the format while the pixels are borrowed, so
+    // they're mapped into a row first and then copied in.
+    let mut row = vec![0; area.w as usize * bpp];
+    for y in area.y as i32..area.y as i32 + area.h as i32 {
+        for (i, pixel) in row.chunks_exact_mut(bpp).enumerate() {
+            let value = color(area.x as i32 + i as i32, y).to_pixel(lock.format());
+            write_pixel(pixel, value);
+        }
+
+        let start = y as usize * pitch + area.x as usize * bpp;
+        lock.pixels_mut()[start..start + row.len()].copy_from_slice(&row);
+    }
+    Ok(())
+}
+
+// Stores a pixel value the way SDL reads it, as a native integer of the
+// pixel's size, with 24-bit pixels as the low three bytes.
+fn write_pixel(pixel: &mut [u8], value: u32) {
+    match pixel.len() {
+        1 => pixel[0] = value as u8,
+        2 => pixel.copy_from_slice(&(value as u16).to_ne_bytes()),
+        3 if cfg!(target_endian = "little") => pixel.copy_from_slice(&value.to_le_bytes()[..3]),
+        3 => pixel.copy_from_slice(&value.to_be_bytes()[1..]),
+        _ => pixel.copy_from_slice(&value.to_ne_bytes()),
+    }
+}
+
+// The fraction a pixel's mix has to be above to take the second color.
+fn threshold(x: i32, y: i32) -> f32 {
+    (BAYER[(y & 3) as usize][(x & 3) as usize] as f32 + 0.5) / 16.0
+}
+
+fn lerp(from: sdl::Color, to: sdl::Color, t: f32) -> sdl::Color {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    sdl::Color::rgba(
+        mix(from.r, to.r),
+        mix(from.g, to.g),
+        mix(from.b, to.b),
+        mix(from.a, to.a),
+    )
+}
+
+// A hash of the seed and position, with the finalizer from splitmix64.
+fn noise(seed: u64, x: i32, y: i32) -> u32 {
+    let mut z = seed ^ ((x as u32 as u64) << 32 | y as u32 as u64);
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)) as u32
+}
@@ -0,0 +1,142 @@
+//! An on-screen frame rate counter for debugging.
+
+use std::time::Duration;
+
+use crate::gfx::primitives::DrawRenderer;
+use crate::sdl;
+use crate::timer::FrameStats;
+use crate::video::Surface;
+
+// The size of a character in SDL_gfx's built in font.
+const CHAR_SIZE: i16 = 8;
+const PADDING: i16 = 2;
+
+/// A corner of the screen.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Keeps track of recent frame times, and draws the current and average
+/// frame rate and the 99th percentile frame time in a corner of the screen.
+///
+/// Call [`record`](FpsOverlay::record) once per frame with the time the
+/// frame took, then [`draw`](FpsOverlay::draw) after everything else.
+#[derive(Clone, Debug)]
+pub struct FpsOverlay {
+    stats: FrameStats,
+    corner: Corner,
+    color: sdl::Color,
+    background: sdl::Color,
+    visible: bool,
+}
+
+impl Default for FpsOverlay {
+    fn default() -> FpsOverlay {
+        FpsOverlay::new()
+    }
+}
+
+impl FpsOverlay {
+    /// Creates a visible overlay in the top left corner, averaging over the
+    /// last 60 frames.
+    pub fn new() -> FpsOverlay {
+        FpsOverlay {
+            stats: FrameStats::new(60),
+            corner: Corner::TopLeft,
+            color: sdl::Color::WHITE,
+            background: sdl::Color::BLACK.with_alpha(160),
+            visible: true,
+        }
+    }
+
+    pub fn set_corner(&mut self, corner: Corner) -> &mut FpsOverlay {
+        self.corner = corner;
+        self
+    }
+
+    pub fn set_colors(&mut self, color: sdl::Color, background: sdl::Color) -> &mut FpsOverlay {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Sets the number of frames the average is taken over.
+    pub fn set_window(&mut self, frames: usize) -> &mut FpsOverlay {
+        self.stats.set_window(frames);
+        self
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Shows the overlay if it's hidden, and hides it otherwise.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Records the time the last frame took. Frame times are recorded while
+    /// the overlay is hidden too, so the average is right when it's shown.
+    pub fn record(&mut self, frame_time: Duration) {
+        self.stats.record(frame_time);
+    }
+
+    /// The recorded frame times, for percentiles and stutters.
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// The frame rate based on the last frame alone.
+    pub fn fps(&self) -> f32 {
+        self.stats.last().map_or(0.0, rate)
+    }
+
+    /// The frame rate averaged over the recorded frames.
+    pub fn average_fps(&self) -> f32 {
+        self.stats.mean().map_or(0.0, rate)
+    }
+
+    /// Draws the overlay to `screen`, unless it's hidden.
+    pub fn draw(&self, screen: &Surface) -> sdl::Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let p99 = self.stats.p99().unwrap_or_default();
+        let text = format!(
+            "{:.1} FPS (avg {:.1}, p99 {:.1} ms)",
+            self.fps(),
+            self.average_fps(),
+            p99.as_secs_f32() * 1000.0
+        );
+        let width = text.len() as i16 * CHAR_SIZE + 2 * PADDING;
+        let height = CHAR_SIZE + 2 * PADDING;
+        let right = screen.width().min(i16::MAX as u32) as i16;
+        let bottom = screen.height().min(i16::MAX as u32) as i16;
+        let (x, y) = match self.corner {
+            Corner::TopLeft => (0, 0),
+            Corner::TopRight => (right - width, 0),
+            Corner::BottomLeft => (0, bottom - height),
+            Corner::BottomRight => (right - width, bottom - height),
+        };
+
+        screen.draw_box(x, y, x + width - 1, y + height - 1, self.background)?;
+        screen.draw_string(x + PADDING, y + PADDING, &text, self.color)
+    }
+}
+
+fn rate(frame_time: Duration) -> f32 {
+    if frame_time.is_zero() {
+        0.0
+    } else {
+        1.0 / frame_time.as_secs_f32()
+    }
+}
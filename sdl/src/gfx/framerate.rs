@@ -1,12 +1,15 @@
 use crate::sdl;
 use crate::sys::gfx::framerate;
+use crate::timer::FrameStats;
 
 use libc::{c_void, size_t};
 use std::mem;
+use std::time::Duration;
 
 /// Structure holding the state and timing information of the framerate controller.
 pub struct FPSManager {
     raw: *mut framerate::FPSmanager,
+    stats: FrameStats,
 }
 
 impl FPSManager {
@@ -16,7 +19,10 @@ impl FPSManager {
             let size = mem::size_of::<framerate::FPSmanager>() as size_t;
             let raw = libc::malloc(size) as *mut framerate::FPSmanager;
             framerate::SDL_initFramerate(raw);
-            FPSManager { raw: raw }
+            FPSManager {
+                raw: raw,
+                stats: FrameStats::default(),
+            }
         }
     }
 
@@ -30,20 +36,45 @@ impl FPSManager {
     }
 
     /// Return the current target framerate.
-    pub fn get_framerate(&self) -> i32 {
+    pub fn get_framerate(&self) -> u32 {
         // will not get an error
-        unsafe { framerate::SDL_getFramerate(self.raw) }
+        unsafe { framerate::SDL_getFramerate(self.raw) as u32 }
     }
 
-    /// Return the current framecount.
-    pub fn get_frame_count(&self) -> i32 {
-        // will not get an error
-        unsafe { framerate::SDL_getFramecount(self.raw) }
+    /// Return the number of frames since the count was last reset. SDL_gfx
+    /// resets it itself whenever a frame runs late.
+    pub fn get_frame_count(&self) -> u32 {
+        // will not get an error, and SDL_gfx stores it unsigned
+        unsafe { framerate::SDL_getFramecount(self.raw) as u32 }
+    }
+
+    /// Reset the frame count to 0, keeping the target framerate.
+    ///
+    /// SDL_gfx has no call for this, but setting the framerate resets the
+    /// count, so the current rate is set again.
+    pub fn reset_frame_count(&mut self) -> sdl::Result<()> {
+        let rate = self.get_framerate();
+        self.set_framerate(rate)
     }
 
     /// Delay execution to maintain a constant framerate and calculate fps.
+    /// Returns the time in milliseconds since the last call, which is also
+    /// recorded in [`stats`](FPSManager::stats).
     pub fn delay(&mut self) -> u32 {
-        unsafe { framerate::SDL_framerateDelay(self.raw) }
+        let ms = unsafe { framerate::SDL_framerateDelay(self.raw) };
+        self.stats.record(Duration::from_millis(ms as u64));
+        ms
+    }
+
+    /// The frame times recorded by [`delay`](FPSManager::delay).
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Like [`stats`](FPSManager::stats), for changing its settings or
+    /// resetting it.
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
     }
 }
 
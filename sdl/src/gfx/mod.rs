@@ -1,4 +1,12 @@
+pub mod animation;
+pub mod atlas;
+pub mod bitmap_font;
+pub mod fill;
+mod fps_overlay;
 pub mod framerate;
 pub mod imagefilter;
+pub mod painter;
 pub mod primitives;
 pub mod rotozoom;
+
+pub use self::fps_overlay::{Corner, FpsOverlay};
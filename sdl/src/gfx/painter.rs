@@ -0,0 +1,476 @@
+//! Drawing with a current color, line width and transform, so widgets can be
+//! drawn in their own coordinates.
+
+use crate::gfx::primitives::DrawRenderer;
+use crate::sdl;
+use crate::video;
+use crate::Point;
+
+/// Draws to a [`DrawRenderer`] with a current color, line width, and a
+/// translation and scale applied to every coordinate.
+///
+/// The painter is itself a `DrawRenderer`, so every primitive goes through
+/// the transform, and the shorthand methods like [`line`](Painter::line) use
+/// the current color and width as well. Painters are cheap to copy, so a
+/// widget can be handed a copy translated to its own position.
+///
+/// Positions, radii and line widths are scaled, but text is always drawn at
+/// the size of the font, and angles aren't affected.
+pub struct Painter<'a, R: DrawRenderer + ?Sized> {
+    target: &'a R,
+    color: sdl::Color,
+    width: u8,
+    offset: (f32, f32),
+    scale: f32,
+}
+
+impl<R: DrawRenderer + ?Sized> Clone for Painter<'_, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R: DrawRenderer + ?Sized> Copy for Painter<'_, R> {}
+
+impl<'a, R: DrawRenderer + ?Sized> Painter<'a, R> {
+    /// A painter drawing 1 pixel wide white lines on `target`, with no
+    /// transform.
+    pub fn new(target: &'a R) -> Painter<'a, R> {
+        Painter {
+            target,
+            color: sdl::Color::WHITE,
+            width: 1,
+            offset: (0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+
+    pub fn target(&self) -> &'a R {
+        self.target
+    }
+
+    pub fn color(&self) -> sdl::Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: sdl::Color) -> &mut Painter<'a, R> {
+        self.color = color;
+        self
+    }
+
+    /// The width of lines, before scaling.
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Sets the width of lines, before scaling. Widths over 1 are drawn
+    /// with thick lines.
+    pub fn set_width(&mut self, width: u8) -> &mut Painter<'a, R> {
+        self.width = width.max(1);
+        self
+    }
+
+    /// Moves the origin to `(x, y)` in the current coordinates.
+    pub fn translate(&mut self, x: i16, y: i16) -> &mut Painter<'a, R> {
+        self.offset.0 += x as f32 * self.scale;
+        self.offset.1 += y as f32 * self.scale;
+        self
+    }
+
+    /// Scales everything drawn from now on by `factor`, around the current
+    /// origin.
+    pub fn scale(&mut self, factor: f32) -> &mut Painter<'a, R> {
+        self.scale *= factor;
+        self
+    }
+
+    /// The current scale, relative to the target.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale
+    }
+
+    /// Goes back to drawing in the target's coordinates.
+    pub fn reset_transform(&mut self) -> &mut Painter<'a, R> {
+        self.offset = (0.0, 0.0);
+        self.scale = 1.0;
+        self
+    }
+
+    /// Converts a point in the current coordinates to the target's.
+    pub fn to_target(&self, point: Point) -> Point {
+        let (x, y) = self.at(point.x, point.y);
+        Point::new(x, y)
+    }
+
+    /// Draws a line with the current color and width.
+    pub fn line(&self, x1: i16, y1: i16, x2: i16, y2: i16) -> sdl::Result<()> {
+        if self.width > 1 {
+            self.draw_thick_line(x1, y1, x2, y2, self.width, self.color)
+        } else {
+            self.draw_line(x1, y1, x2, y2, self.color)
+        }
+    }
+
+    /// Draws the outline of a rectangle with the current color and width.
+    pub fn rect(&self, x1: i16, y1: i16, x2: i16, y2: i16) -> sdl::Result<()> {
+        if self.width > 1 {
+            let corners = [
+                Point::new(x1, y1),
+                Point::new(x2, y1),
+                Point::new(x2, y2),
+                Point::new(x1, y2),
+            ];
+            self.draw_thick_polygon(&corners, self.width, self.color)
+        } else {
+            self.draw_rectangle(x1, y1, x2, y2, self.color)
+        }
+    }
+
+    /// Fills a rectangle with the current color.
+    pub fn fill_rect(&self, x1: i16, y1: i16, x2: i16, y2: i16) -> sdl::Result<()> {
+        self.draw_box(x1, y1, x2, y2, self.color)
+    }
+
+    /// Draws the outline of a circle with the current color. Thick outlines
+    /// aren't supported by SDL_gfx, so the width is ignored.
+    pub fn circle(&self, x: i16, y: i16, rad: i16) -> sdl::Result<()> {
+        self.draw_circle(x, y, rad, self.color)
+    }
+
+    /// Fills a circle with the current color.
+    pub fn fill_circle(&self, x: i16, y: i16, rad: i16) -> sdl::Result<()> {
+        self.draw_filled_circle(x, y, rad, self.color)
+    }
+
+    /// Draws connected lines through `points` with the current color and
+    /// width.
+    pub fn polyline(&self, points: &[Point]) -> sdl::Result<()> {
+        self.draw_polyline(points, self.width, self.color)
+    }
+
+    /// Draws the outline of a polygon with the current color and width.
+    pub fn polygon(&self, points: &[Point]) -> sdl::Result<()> {
+        self.draw_thick_polygon(points, self.width, self.color)
+    }
+
+    /// Fills a polygon, which may be concave, with the current color.
+    pub fn fill_polygon(&self, points: &[Point]) -> sdl::Result<()> {
+        self.draw_filled_polygon_concave(points, self.color)
+    }
+
+    /// Draws text with the current color, with its top left corner at
+    /// `(x, y)`.
+    pub fn text(&self, x: i16, y: i16, s: &str) -> sdl::Result<()> {
+        self.draw_string(x, y, s, self.color)
+    }
+
+    fn at(&self, x: i16, y: i16) -> (i16, i16) {
+        (
+            to_i16(self.offset.0 + x as f32 * self.scale),
+            to_i16(self.offset.1 + y as f32 * self.scale),
+        )
+    }
+
+    fn at_all(&self, vx: &[i16], vy: &[i16]) -> (Vec<i16>, Vec<i16>) {
+        vx.iter().zip(vy).map(|(&x, &y)| self.at(x, y)).unzip()
+    }
+
+    // Scales a radius or other length.
+    fn len(&self, len: i16) -> i16 {
+        to_i16(len as f32 * self.scale)
+    }
+
+    fn line_width(&self, width: u8) -> u8 {
+        (width as f32 * self.scale)
+            .round()
+            .clamp(1.0, u8::MAX as f32) as u8
+    }
+}
+
+// Float to int casts saturate, which is what's wanted for coordinates off
+// the edge of the 16-bit range.
+fn to_i16(value: f32) -> i16 {
+    value.round() as i16
+}
+
+impl<R: DrawRenderer + ?Sized> DrawRenderer for Painter<'_, R> {
+    fn draw_pixel(&self, x: i16, y: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_pixel(x, y, color)
+    }
+
+    fn draw_hline(&self, x1: i16, x2: i16, y: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x1, y) = self.at(x1, y);
+        let (x2, _) = self.at(x2, 0);
+        self.target.draw_hline(x1, x2, y, color)
+    }
+
+    fn draw_vline(&self, x: i16, y1: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y1) = self.at(x, y1);
+        let (_, y2) = self.at(0, y2);
+        self.target.draw_vline(x, y1, y2, color)
+    }
+
+    fn draw_rectangle(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target.draw_rectangle(x1, y1, x2, y2, color)
+    }
+
+    fn draw_box(&self, x1: i16, y1: i16, x2: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target.draw_box(x1, y1, x2, y2, color)
+    }
+
+    fn draw_line(&self, x1: i16, y1: i16, x2: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target.draw_line(x1, y1, x2, y2, color)
+    }
+
+    fn draw_aa_line(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target.draw_aa_line(x1, y1, x2, y2, color)
+    }
+
+    fn draw_rounded_rectangle(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        rad: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target
+            .draw_rounded_rectangle(x1, y1, x2, y2, self.len(rad), color)
+    }
+
+    fn draw_rounded_box(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        rad: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target
+            .draw_rounded_box(x1, y1, x2, y2, self.len(rad), color)
+    }
+
+    fn draw_thick_line(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        width: u8,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        self.target
+            .draw_thick_line(x1, y1, x2, y2, self.line_width(width), color)
+    }
+
+    fn draw_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_circle(x, y, self.len(rad), color)
+    }
+
+    fn draw_aa_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_aa_circle(x, y, self.len(rad), color)
+    }
+
+    fn draw_filled_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_filled_circle(x, y, self.len(rad), color)
+    }
+
+    fn draw_arc(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: i16,
+        end: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_arc(x, y, self.len(rad), start, end, color)
+    }
+
+    fn draw_pie(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: i16,
+        end: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_pie(x, y, self.len(rad), start, end, color)
+    }
+
+    fn draw_filled_pie(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: i16,
+        end: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target
+            .draw_filled_pie(x, y, self.len(rad), start, end, color)
+    }
+
+    fn draw_ellipse(&self, x: i16, y: i16, rx: i16, ry: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target
+            .draw_ellipse(x, y, self.len(rx), self.len(ry), color)
+    }
+
+    fn draw_aa_ellipse(
+        &self,
+        x: i16,
+        y: i16,
+        rx: i16,
+        ry: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target
+            .draw_aa_ellipse(x, y, self.len(rx), self.len(ry), color)
+    }
+
+    fn draw_filled_ellipse(
+        &self,
+        x: i16,
+        y: i16,
+        rx: i16,
+        ry: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target
+            .draw_filled_ellipse(x, y, self.len(rx), self.len(ry), color)
+    }
+
+    fn draw_trigon(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        x3: i16,
+        y3: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        let (x3, y3) = self.at(x3, y3);
+        self.target.draw_trigon(x1, y1, x2, y2, x3, y3, color)
+    }
+
+    fn draw_aa_trigon(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        x3: i16,
+        y3: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        let (x3, y3) = self.at(x3, y3);
+        self.target.draw_aa_trigon(x1, y1, x2, y2, x3, y3, color)
+    }
+
+    fn draw_filled_trigon(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        x3: i16,
+        y3: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = self.at(x1, y1);
+        let (x2, y2) = self.at(x2, y2);
+        let (x3, y3) = self.at(x3, y3);
+        self.target
+            .draw_filled_trigon(x1, y1, x2, y2, x3, y3, color)
+    }
+
+    fn draw_polygon(&self, vx: &[i16], vy: &[i16], color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = self.at_all(vx, vy);
+        self.target.draw_polygon(&vx, &vy, color)
+    }
+
+    fn draw_aa_polygon(&self, vx: &[i16], vy: &[i16], color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = self.at_all(vx, vy);
+        self.target.draw_aa_polygon(&vx, &vy, color)
+    }
+
+    fn draw_filled_polygon(&self, vx: &[i16], vy: &[i16], color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = self.at_all(vx, vy);
+        self.target.draw_filled_polygon(&vx, &vy, color)
+    }
+
+    fn draw_textured_polygon(
+        &self,
+        vx: &[i16],
+        vy: &[i16],
+        texture: &video::Surface,
+        texture_dx: i16,
+        texture_dy: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (vx, vy) = self.at_all(vx, vy);
+        self.target
+            .draw_textured_polygon(&vx, &vy, texture, texture_dx, texture_dy, color)
+    }
+
+    fn draw_bezier(&self, vx: &[i16], vy: &[i16], s: i32, color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = self.at_all(vx, vy);
+        self.target.draw_bezier(&vx, &vy, s, color)
+    }
+
+    fn draw_character(&self, x: i16, y: i16, c: char, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_character(x, y, c, color)
+    }
+
+    fn draw_string(&self, x: i16, y: i16, s: &str, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = self.at(x, y);
+        self.target.draw_string(x, y, s, color)
+    }
+}
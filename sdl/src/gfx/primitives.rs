@@ -5,6 +5,7 @@ use crate::get_error;
 use crate::sdl;
 use crate::sys::gfx::primitives;
 use crate::video;
+use crate::Point;
 
 /// A surface which can have primitives drawn to it.
 pub trait DrawRenderer {
@@ -218,10 +219,9 @@ pub trait DrawRenderer {
     /// * `x` - X coordinate of the center of the arc.
     /// * `y` - Y coordinate of the center of the arc.
     /// * `rad` - Radius in pixels of the arc.
-    /// * `start` - Starting radius in degrees of the arc. 0 degrees is down,
-    ///   increasing counterclockwise.
-    /// * `end` - Ending radius in degrees of the arc. 0 degrees is down,
-    ///   increasing counterclockwise.
+    /// * `start` - Starting radius in degrees of the arc. 0 degrees is right,
+    ///   increasing clockwise; see [`Angle`].
+    /// * `end` - Ending radius in degrees of the arc.
     /// * `color` - The color value of the arc to draw.
     ///
     fn draw_arc(
@@ -300,7 +300,8 @@ pub trait DrawRenderer {
     /// * `x` - X coordinate of the center of the pie.
     /// * `y` - Y coordinate of the center of the pie.
     /// * `rad` - Radius in pixels of the pie.
-    /// * `start` - Starting radius in degrees of the pie.
+    /// * `start` - Starting radius in degrees of the pie. 0 degrees is right,
+    ///   increasing clockwise; see [`Angle`].
     /// * `end` - Ending radius in degrees of the pie.
     /// * `color` - The color value of the pie to draw.
     ///
@@ -321,7 +322,8 @@ pub trait DrawRenderer {
     /// * `x` - X coordinate of the center of the filled pie.
     /// * `y` - Y coordinate of the center of the filled pie.
     /// * `rad` - Radius in pixels of the filled pie.
-    /// * `start` - Starting radius in degrees of the filled pie.
+    /// * `start` - Starting radius in degrees of the filled pie. 0 degrees is
+    ///   right, increasing clockwise; see [`Angle`].
     /// * `end` - Ending radius in degrees of the filled pie.
     /// * `color` - The color value of the filled pie to draw
     ///
@@ -495,7 +497,8 @@ pub trait DrawRenderer {
     ///   character.
     /// * `y` - Y (vertical) coordinate of the upper left corner of the
     ///   character.
-    /// * `c` - The character to draw.
+    /// * `c` - The character to draw. Characters the font doesn't have are
+    ///   drawn as `?`; see [`font_glyph`].
     /// * `color` - The color value of the character to draw.
     ///
     fn draw_character(&self, x: i16, y: i16, c: char, color: sdl::Color) -> sdl::Result<()>;
@@ -508,15 +511,407 @@ pub trait DrawRenderer {
     ///
     /// * `x` - X (horizontal) coordinate of the upper left corner of the string.
     /// * `y` - Y (vertical) coordinate of the upper left corner of the string.
-    /// * `s` - The string to draw.
+    /// * `s` - The string to draw. Characters the font doesn't have are drawn
+    ///   as `?`; see [`font_glyph`].
     /// color	The color value of the string to draw.
     ///
     fn draw_string(&self, x: i16, y: i16, s: &str, color: sdl::Color) -> sdl::Result<()>;
+
+    /// Draw a character of the currently set font, or fail if the font
+    /// doesn't have it rather than drawing a placeholder.
+    fn draw_char_checked(
+        &self,
+        x: i16,
+        y: i16,
+        c: char,
+        color: sdl::Color,
+    ) -> Result<(), DrawTextError> {
+        if font_glyph(c).is_none() {
+            return Err(DrawTextError::UnsupportedChar(c));
+        }
+        Ok(self.draw_character(x, y, c, color)?)
+    }
+
+    /// Draw connected thick lines through `points`, with rounded joints so
+    /// there are no gaps where segments meet at an angle.
+    ///
+    /// Joints are drawn over the ends of the segments, so translucent
+    /// colors come out darker there.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The points to connect, in order. Nothing is drawn for
+    ///   fewer than 2.
+    /// * `width` - Width of the lines in pixels. Must be >0.
+    /// * `color` - The color value of the lines to draw.
+    ///
+    fn draw_polyline(&self, points: &[Point], width: u8, color: sdl::Color) -> sdl::Result<()> {
+        for segment in points.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            self.draw_thick_line(a.x, a.y, b.x, b.y, width, color)?;
+        }
+        if points.len() > 2 {
+            draw_joints(self, &points[1..points.len() - 1], width, color)?;
+        }
+        Ok(())
+    }
+
+    /// Draw an arc from `start` clockwise to `end`, like
+    /// [`draw_arc`](DrawRenderer::draw_arc) but with the angles normalized.
+    /// If `end` is a whole turn or more after `start`, the whole circle is
+    /// drawn.
+    fn draw_arc_angles(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: Angle,
+        end: Angle,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        match gfx_angles(start, end) {
+            Some((start, end)) => self.draw_arc(x, y, rad, start, end, color),
+            None => self.draw_circle(x, y, rad, color),
+        }
+    }
+
+    /// Draw a pie from `start` clockwise to `end`, like
+    /// [`draw_pie`](DrawRenderer::draw_pie) but with the angles normalized.
+    /// If `end` is a whole turn or more after `start`, the whole circle is
+    /// drawn.
+    fn draw_pie_angles(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: Angle,
+        end: Angle,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        match gfx_angles(start, end) {
+            Some((start, end)) => self.draw_pie(x, y, rad, start, end, color),
+            None => self.draw_circle(x, y, rad, color),
+        }
+    }
+
+    /// Draw a filled pie from `start` clockwise to `end`, like
+    /// [`draw_filled_pie`](DrawRenderer::draw_filled_pie) but with the angles
+    /// normalized. If `end` is a whole turn or more after `start`, the whole
+    /// circle is filled.
+    fn draw_filled_pie_angles(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: Angle,
+        end: Angle,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        match gfx_angles(start, end) {
+            Some((start, end)) => self.draw_filled_pie(x, y, rad, start, end, color),
+            None => self.draw_filled_circle(x, y, rad, color),
+        }
+    }
+
+    /// Draw the outline of a polygon with thick lines, with rounded joints at
+    /// every vertex. See [`draw_polyline`](DrawRenderer::draw_polyline).
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The vertices of the polygon. The last one is connected
+    ///   back to the first. Nothing is drawn for fewer than 2.
+    /// * `width` - Width of the lines in pixels. Must be >0.
+    /// * `color` - The color value of the polygon to draw.
+    ///
+    fn draw_thick_polygon(
+        &self,
+        points: &[Point],
+        width: u8,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (first, last) = match points {
+            [first, .., last] => (*first, *last),
+            _ => return Ok(()),
+        };
+        for segment in points.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            self.draw_thick_line(a.x, a.y, b.x, b.y, width, color)?;
+        }
+        if points.len() > 2 {
+            self.draw_thick_line(last.x, last.y, first.x, first.y, width, color)?;
+        }
+        draw_joints(self, points, width, color)
+    }
+
+    /// Draw a filled polygon which may be concave, by splitting it into
+    /// triangles with [`triangulate`] and filling each one.
+    ///
+    /// SDL_gfx's own [`draw_filled_polygon`](DrawRenderer::draw_filled_polygon)
+    /// fills by scanline, which gets concave shapes with touching or crossing
+    /// edges wrong. Neighbouring triangles share an edge, so translucent
+    /// colors come out darker along them.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The vertices of the polygon, in either order. Nothing is
+    ///   drawn for fewer than 3.
+    /// * `color` - The color value of the polygon to draw.
+    ///
+    fn draw_filled_polygon_concave(&self, points: &[Point], color: sdl::Color) -> sdl::Result<()> {
+        for [a, b, c] in triangulate(points) {
+            self.draw_filled_trigon(a.x, a.y, b.x, b.y, c.x, c.y, color)?;
+        }
+        Ok(())
+    }
+}
+
+/// An angle for the arc and pie primitives.
+///
+/// SDL_gfx measures angles in whole degrees from 3 o'clock, and since y
+/// points down on the screen, they increase clockwise. That's a quarter turn
+/// off from a compass bearing, and the opposite direction from maths with y
+/// pointing up, so the constructors spell out which convention an angle is
+/// in. (The SDL_gfx docs say arcs start at 6 o'clock and go
+/// counterclockwise, but that isn't what it draws.)
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Angle(f32);
+
+impl Angle {
+    pub const RIGHT: Angle = Angle(0.0);
+    pub const DOWN: Angle = Angle(90.0);
+    pub const LEFT: Angle = Angle(180.0);
+    pub const UP: Angle = Angle(270.0);
+
+    /// Degrees clockwise from 3 o'clock, which is what SDL_gfx uses.
+    pub const fn degrees(degrees: f32) -> Angle {
+        Angle(degrees)
+    }
+
+    /// Radians clockwise from 3 o'clock.
+    pub fn radians(radians: f32) -> Angle {
+        Angle(radians.to_degrees())
+    }
+
+    /// Degrees clockwise from 12 o'clock, like a compass bearing.
+    pub fn bearing(degrees: f32) -> Angle {
+        Angle(degrees - 90.0)
+    }
+
+    /// Degrees counterclockwise from 3 o'clock, as in maths with y pointing
+    /// up.
+    pub fn counterclockwise(degrees: f32) -> Angle {
+        Angle(-degrees)
+    }
+
+    /// Degrees clockwise from 3 o'clock, as given.
+    pub fn to_degrees(self) -> f32 {
+        self.0
+    }
+
+    /// Degrees clockwise from 3 o'clock, wrapped into `0.0..360.0`.
+    pub fn normalized(self) -> f32 {
+        self.0.rem_euclid(360.0)
+    }
+
+    // Rounded to the whole degrees SDL_gfx takes. Rounding can push an angle
+    // just under 360 up to it, so that wraps too.
+    fn to_gfx(self) -> i16 {
+        self.normalized().round() as i16 % 360
+    }
+}
+
+// Converts the angles for SDL_gfx, or returns `None` if they cover the whole
+// circle, which SDL_gfx can't be asked for since the angles are equal once
+// wrapped.
+fn gfx_angles(start: Angle, end: Angle) -> Option<(i16, i16)> {
+    if end.0 - start.0 >= 360.0 {
+        None
+    } else {
+        Some((start.to_gfx(), end.to_gfx()))
+    }
+}
+
+// SDL_gfx draws thick lines with square ends, which leaves a notch on the
+// outside of every bend. A circle as wide as the line fills it in. Thin lines
+// don't need it.
+fn draw_joints<R: DrawRenderer + ?Sized>(
+    renderer: &R,
+    points: &[Point],
+    width: u8,
+    color: sdl::Color,
+) -> sdl::Result<()> {
+    if width <= 2 {
+        return Ok(());
+    }
+    let rad = (width / 2) as i16;
+    for point in points {
+        renderer.draw_filled_circle(point.x, point.y, rad, color)?;
+    }
+    Ok(())
+}
+
+/// Splits a simple polygon, concave or not, into triangles by ear clipping.
+///
+/// The vertices can be in either order. Collinear vertices are dropped, and
+/// nothing is returned for fewer than 3 points or a polygon with no area.
+/// Self-intersecting polygons aren't simple, so while they're still split
+/// up, the result isn't guaranteed to cover them exactly.
+pub fn triangulate(points: &[Point]) -> Vec<[Point; 3]> {
+    // Twice the signed area, positive for clockwise vertices on the screen.
+    let area: i64 = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64
+        })
+        .sum();
+    if points.len() < 3 || area == 0 {
+        return Vec::new();
+    }
+    let winding = area.signum();
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            (points[a], points[b], points[c])
+        };
+
+        // Collinear vertices don't add anything, and would leave
+        // zero-area triangles behind.
+        if let Some(i) = (0..n).find(|&i| {
+            let (a, b, c) = corner(i);
+            cross(a, b, c) == 0
+        }) {
+            remaining.remove(i);
+            continue;
+        }
+
+        // An ear is a convex corner with no other vertex inside it. A simple
+        // polygon always has one; if none is found the polygon crosses
+        // itself, and the first corner is clipped to make progress.
+        let ear = (0..n)
+            .find(|&i| {
+                let (a, b, c) = corner(i);
+                cross(a, b, c).signum() == winding
+                    && !remaining.iter().any(|&j| {
+                        let p = points[j];
+                        p != a && p != b && p != c && in_triangle(p, a, b, c)
+                    })
+            })
+            .unwrap_or(0);
+        let (a, b, c) = corner(ear);
+        triangles.push([a, b, c]);
+        remaining.remove(ear);
+    }
+
+    let (a, b, c) = (
+        points[remaining[0]],
+        points[remaining[1]],
+        points[remaining[2]],
+    );
+    if cross(a, b, c) != 0 {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+// The z component of (b - a) x (c - b), positive when a, b and c turn
+// clockwise on the screen.
+fn cross(a: Point, b: Point, c: Point) -> i64 {
+    let (abx, aby) = (b.x as i64 - a.x as i64, b.y as i64 - a.y as i64);
+    let (bcx, bcy) = (c.x as i64 - b.x as i64, c.y as i64 - b.y as i64);
+    abx * bcy - aby * bcx
+}
+
+// Whether `p` is inside or on the edge of the triangle `a`, `b`, `c`, which
+// can be in either order.
+fn in_triangle(p: Point, a: Point, b: Point, c: Point) -> bool {
+    let sides = [cross(a, b, p), cross(b, c, p), cross(c, a, p)];
+    sides.iter().all(|&side| side >= 0) || sides.iter().all(|&side| side <= 0)
+}
+
+/// Returns the points along the Bézier curve with `points` as its control
+/// points, the same curve [`draw_bezier`](DrawRenderer::draw_bezier) draws
+/// for the same number of `steps`.
+///
+/// Like SDL_gfx, the curve is split into `steps` segments per control point,
+/// so this returns `points.len() * steps + 1` points, from the first control
+/// point to the last.
+pub fn bezier_points(points: &[Point], steps: u32) -> Vec<Point> {
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+
+    let segments = points.len() * steps.max(1) as usize;
+    let mut scratch = Vec::with_capacity(points.len());
+    (0..=segments)
+        .map(|i| {
+            let (x, y) = evaluate_bezier(points, i as f64 / segments as f64, &mut scratch);
+            Point::new(x.round() as i16, y.round() as i16)
+        })
+        .collect()
+}
+
+// Evaluates the curve at `t` between 0 and 1 with de Casteljau's algorithm,
+// which is the same polynomial as SDL_gfx's, but stable for many points.
+fn evaluate_bezier(points: &[Point], t: f64, scratch: &mut Vec<(f64, f64)>) -> (f64, f64) {
+    scratch.clear();
+    scratch.extend(points.iter().map(|p| (p.x as f64, p.y as f64)));
+    for len in (1..points.len()).rev() {
+        for i in 0..len {
+            let (a, b) = (scratch[i], scratch[i + 1]);
+            scratch[i] = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        }
+    }
+    scratch[0]
+}
+
+// The upper half of code page 437, which the built-in font follows.
+const CP437_UPPER: &str = "\
+    ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+// Drawn in place of characters the font doesn't have.
+const PLACEHOLDER: u8 = b'?';
+
+/// Returns the index of `c` in SDL_gfx's built-in 8x8 font, which covers
+/// printable ASCII and the rest of code page 437, or `None` if the font
+/// doesn't have it. Fonts set with `gfxPrimitivesSetFont` are assumed to use
+/// the same layout.
+pub fn font_glyph(c: char) -> Option<u8> {
+    if c.is_ascii_graphic() || c == ' ' {
+        return Some(c as u8);
+    }
+    CP437_UPPER
+        .chars()
+        .position(|glyph| glyph == c)
+        .map(|i| 0x80 + i as u8)
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum DrawTextError {
+    #[error("the font has no glyph for {:?}", .0)]
+    UnsupportedChar(char),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
+
+// These call the `*RGBA` variants of the SDL_gfx functions, which take each
+// channel separately, rather than the `*Color` ones, which take them packed
+// into a `0xRRGGBBAA` u32. That saves packing the color on every call and
+// leaves no byte order to get wrong.
 impl DrawRenderer for video::Surface {
     fn draw_pixel(&self, x: i16, y: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::pixelColor(self.raw(), x, y, color.into()) };
+        let ret =
+            unsafe { primitives::pixelRGBA(self.raw(), x, y, color.r, color.g, color.b, color.a) };
         if ret == 0 {
             Ok(())
         } else {
@@ -524,7 +919,9 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_hline(&self, x1: i16, x2: i16, y: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::hlineColor(self.raw(), x1, x2, y, color.into()) };
+        let ret = unsafe {
+            primitives::hlineRGBA(self.raw(), x1, x2, y, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -532,7 +929,9 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_vline(&self, x: i16, y1: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::vlineColor(self.raw(), x, y1, y2, color.into()) };
+        let ret = unsafe {
+            primitives::vlineRGBA(self.raw(), x, y1, y2, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -547,7 +946,19 @@ impl DrawRenderer for video::Surface {
         y2: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret = unsafe { primitives::rectangleColor(self.raw(), x1, y1, x2, y2, color.into()) };
+        let ret = unsafe {
+            primitives::rectangleRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -564,7 +975,18 @@ impl DrawRenderer for video::Surface {
         color: sdl::Color,
     ) -> sdl::Result<()> {
         let ret = unsafe {
-            primitives::roundedRectangleColor(self.raw(), x1, y1, x2, y2, rad, color.into())
+            primitives::roundedRectangleRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                rad,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
         };
         if ret == 0 {
             Ok(())
@@ -573,7 +995,19 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_box(&self, x1: i16, y1: i16, x2: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::boxColor(self.raw(), x1, y1, x2, y2, color.into()) };
+        let ret = unsafe {
+            primitives::boxRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -589,8 +1023,20 @@ impl DrawRenderer for video::Surface {
         rad: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret =
-            unsafe { primitives::roundedBoxColor(self.raw(), x1, y1, x2, y2, rad, color.into()) };
+        let ret = unsafe {
+            primitives::roundedBoxRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                rad,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -598,7 +1044,19 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_line(&self, x1: i16, y1: i16, x2: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::lineColor(self.raw(), x1, y1, x2, y2, color.into()) };
+        let ret = unsafe {
+            primitives::lineRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -613,7 +1071,19 @@ impl DrawRenderer for video::Surface {
         y2: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret = unsafe { primitives::aalineColor(self.raw(), x1, y1, x2, y2, color.into()) };
+        let ret = unsafe {
+            primitives::aalineRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -629,8 +1099,20 @@ impl DrawRenderer for video::Surface {
         width: u8,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret =
-            unsafe { primitives::thickLineColor(self.raw(), x1, y1, x2, y2, width, color.into()) };
+        let ret = unsafe {
+            primitives::thickLineRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                width,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -638,7 +1120,9 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::circleColor(self.raw(), x, y, rad, color.into()) };
+        let ret = unsafe {
+            primitives::circleRGBA(self.raw(), x, y, rad, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -646,7 +1130,9 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_aa_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::aacircleColor(self.raw(), x, y, rad, color.into()) };
+        let ret = unsafe {
+            primitives::aacircleRGBA(self.raw(), x, y, rad, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -654,7 +1140,9 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_filled_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::filledCircleColor(self.raw(), x, y, rad, color.into()) };
+        let ret = unsafe {
+            primitives::filledCircleRGBA(self.raw(), x, y, rad, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -670,7 +1158,20 @@ impl DrawRenderer for video::Surface {
         end: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret = unsafe { primitives::arcColor(self.raw(), x, y, rad, start, end, color.into()) };
+        let ret = unsafe {
+            primitives::arcRGBA(
+                self.raw(),
+                x,
+                y,
+                rad,
+                start,
+                end,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -678,7 +1179,9 @@ impl DrawRenderer for video::Surface {
         }
     }
     fn draw_ellipse(&self, x: i16, y: i16, rx: i16, ry: i16, color: sdl::Color) -> sdl::Result<()> {
-        let ret = unsafe { primitives::ellipseColor(self.raw(), x, y, rx, ry, color.into()) };
+        let ret = unsafe {
+            primitives::ellipseRGBA(self.raw(), x, y, rx, ry, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -693,7 +1196,9 @@ impl DrawRenderer for video::Surface {
         ry: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret = unsafe { primitives::aaellipseColor(self.raw(), x, y, rx, ry, color.into()) };
+        let ret = unsafe {
+            primitives::aaellipseRGBA(self.raw(), x, y, rx, ry, color.r, color.g, color.b, color.a)
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -708,7 +1213,19 @@ impl DrawRenderer for video::Surface {
         ry: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret = unsafe { primitives::filledEllipseColor(self.raw(), x, y, rx, ry, color.into()) };
+        let ret = unsafe {
+            primitives::filledEllipseRGBA(
+                self.raw(),
+                x,
+                y,
+                rx,
+                ry,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -724,7 +1241,20 @@ impl DrawRenderer for video::Surface {
         end: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret = unsafe { primitives::pieColor(self.raw(), x, y, rad, start, end, color.into()) };
+        let ret = unsafe {
+            primitives::pieRGBA(
+                self.raw(),
+                x,
+                y,
+                rad,
+                start,
+                end,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -740,8 +1270,20 @@ impl DrawRenderer for video::Surface {
         end: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret =
-            unsafe { primitives::filledPieColor(self.raw(), x, y, rad, start, end, color.into()) };
+        let ret = unsafe {
+            primitives::filledPieRGBA(
+                self.raw(),
+                x,
+                y,
+                rad,
+                start,
+                end,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -758,8 +1300,21 @@ impl DrawRenderer for video::Surface {
         y3: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret =
-            unsafe { primitives::trigonColor(self.raw(), x1, y1, x2, y2, x3, y3, color.into()) };
+        let ret = unsafe {
+            primitives::trigonRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -776,8 +1331,21 @@ impl DrawRenderer for video::Surface {
         y3: i16,
         color: sdl::Color,
     ) -> sdl::Result<()> {
-        let ret =
-            unsafe { primitives::aatrigonColor(self.raw(), x1, y1, x2, y2, x3, y3, color.into()) };
+        let ret = unsafe {
+            primitives::aatrigonRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -795,7 +1363,19 @@ impl DrawRenderer for video::Surface {
         color: sdl::Color,
     ) -> sdl::Result<()> {
         let ret = unsafe {
-            primitives::filledTrigonColor(self.raw(), x1, y1, x2, y2, x3, y3, color.into())
+            primitives::filledTrigonRGBA(
+                self.raw(),
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
         };
         if ret == 0 {
             Ok(())
@@ -808,7 +1388,16 @@ impl DrawRenderer for video::Surface {
         assert_eq!(vx.len(), vy.len());
         let n = vx.len() as c_int;
         let ret = unsafe {
-            primitives::polygonColor(self.raw(), vx.as_ptr(), vy.as_ptr(), n, color.into())
+            primitives::polygonRGBA(
+                self.raw(),
+                vx.as_ptr(),
+                vy.as_ptr(),
+                n,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
         };
         if ret == 0 {
             Ok(())
@@ -821,7 +1410,16 @@ impl DrawRenderer for video::Surface {
         assert_eq!(vx.len(), vy.len());
         let n = vx.len() as c_int;
         let ret = unsafe {
-            primitives::aapolygonColor(self.raw(), vx.as_ptr(), vy.as_ptr(), n, color.into())
+            primitives::aapolygonRGBA(
+                self.raw(),
+                vx.as_ptr(),
+                vy.as_ptr(),
+                n,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
         };
         if ret == 0 {
             Ok(())
@@ -834,7 +1432,16 @@ impl DrawRenderer for video::Surface {
         assert_eq!(vx.len(), vy.len());
         let n = vx.len() as c_int;
         let ret = unsafe {
-            primitives::filledPolygonColor(self.raw(), vx.as_ptr(), vy.as_ptr(), n, color.into())
+            primitives::filledPolygonRGBA(
+                self.raw(),
+                vx.as_ptr(),
+                vy.as_ptr(),
+                n,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
         };
         if ret == 0 {
             Ok(())
@@ -859,13 +1466,16 @@ impl DrawRenderer for video::Surface {
         assert_eq!(vx.len(), vy.len());
         let n = vx.len() as c_int;
         let ret = unsafe {
-            primitives::bezierColor(
+            primitives::bezierRGBA(
                 self.raw(),
                 vx.as_ptr(),
                 vy.as_ptr(),
                 n,
                 s as c_int,
-                color.into(),
+                color.r,
+                color.g,
+                color.b,
+                color.a,
             )
         };
         if ret == 0 {
@@ -876,8 +1486,19 @@ impl DrawRenderer for video::Surface {
     }
 
     fn draw_character(&self, x: i16, y: i16, c: char, color: sdl::Color) -> sdl::Result<()> {
-        let ret =
-            unsafe { primitives::characterColor(self.raw(), x, y, c as c_char, color.into()) };
+        let glyph = font_glyph(c).unwrap_or(PLACEHOLDER);
+        let ret = unsafe {
+            primitives::characterRGBA(
+                self.raw(),
+                x,
+                y,
+                glyph as c_char,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
+        };
         if ret == 0 {
             Ok(())
         } else {
@@ -886,10 +1507,23 @@ impl DrawRenderer for video::Surface {
     }
 
     fn draw_string(&self, x: i16, y: i16, s: &str, color: sdl::Color) -> sdl::Result<()> {
+        let glyphs: Vec<u8> = s
+            .chars()
+            .map(|c| font_glyph(c).unwrap_or(PLACEHOLDER))
+            .collect();
         let ret = unsafe {
-            let cstring = CString::new(s).unwrap();
+            let cstring = CString::new(glyphs).expect("fonts have no glyph for nul");
             let buf = cstring.as_bytes().as_ptr();
-            primitives::stringColor(self.raw(), x, y, buf as *mut c_char, color.into())
+            primitives::stringRGBA(
+                self.raw(),
+                x,
+                y,
+                buf as *mut c_char,
+                color.r,
+                color.g,
+                color.b,
+                color.a,
+            )
         };
         if ret == 0 {
             Ok(())
@@ -898,3 +1532,332 @@ impl DrawRenderer for video::Surface {
         }
     }
 }
+
+// Views draw to the underlying surface, which already has its clip rectangle
+// set to the view, so only the coordinates need translating.
+impl DrawRenderer for video::SurfaceView<'_> {
+    fn draw_pixel(&self, x: i16, y: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_pixel(x, y, color)
+    }
+
+    fn draw_hline(&self, x1: i16, x2: i16, y: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x1, y) = at(self, x1, y);
+        let x2 = x2.saturating_add(self.origin().x);
+        self.surface().draw_hline(x1, x2, y, color)
+    }
+
+    fn draw_vline(&self, x: i16, y1: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y1) = at(self, x, y1);
+        let y2 = y2.saturating_add(self.origin().y);
+        self.surface().draw_vline(x, y1, y2, color)
+    }
+
+    fn draw_rectangle(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface().draw_rectangle(x1, y1, x2, y2, color)
+    }
+
+    fn draw_box(&self, x1: i16, y1: i16, x2: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface().draw_box(x1, y1, x2, y2, color)
+    }
+
+    fn draw_line(&self, x1: i16, y1: i16, x2: i16, y2: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface().draw_line(x1, y1, x2, y2, color)
+    }
+
+    fn draw_aa_line(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface().draw_aa_line(x1, y1, x2, y2, color)
+    }
+
+    fn draw_rounded_rectangle(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        rad: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface()
+            .draw_rounded_rectangle(x1, y1, x2, y2, rad, color)
+    }
+
+    fn draw_rounded_box(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        rad: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface().draw_rounded_box(x1, y1, x2, y2, rad, color)
+    }
+
+    fn draw_thick_line(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        width: u8,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        self.surface().draw_thick_line(x1, y1, x2, y2, width, color)
+    }
+
+    fn draw_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_circle(x, y, rad, color)
+    }
+
+    fn draw_aa_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_aa_circle(x, y, rad, color)
+    }
+
+    fn draw_filled_circle(&self, x: i16, y: i16, rad: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_filled_circle(x, y, rad, color)
+    }
+
+    fn draw_arc(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: i16,
+        end: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_arc(x, y, rad, start, end, color)
+    }
+
+    fn draw_pie(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: i16,
+        end: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_pie(x, y, rad, start, end, color)
+    }
+
+    fn draw_filled_pie(
+        &self,
+        x: i16,
+        y: i16,
+        rad: i16,
+        start: i16,
+        end: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_filled_pie(x, y, rad, start, end, color)
+    }
+
+    fn draw_ellipse(&self, x: i16, y: i16, rx: i16, ry: i16, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_ellipse(x, y, rx, ry, color)
+    }
+
+    fn draw_aa_ellipse(
+        &self,
+        x: i16,
+        y: i16,
+        rx: i16,
+        ry: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_aa_ellipse(x, y, rx, ry, color)
+    }
+
+    fn draw_filled_ellipse(
+        &self,
+        x: i16,
+        y: i16,
+        rx: i16,
+        ry: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_filled_ellipse(x, y, rx, ry, color)
+    }
+
+    fn draw_trigon(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        x3: i16,
+        y3: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        let (x3, y3) = at(self, x3, y3);
+        self.surface().draw_trigon(x1, y1, x2, y2, x3, y3, color)
+    }
+
+    fn draw_aa_trigon(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        x3: i16,
+        y3: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        let (x3, y3) = at(self, x3, y3);
+        self.surface().draw_aa_trigon(x1, y1, x2, y2, x3, y3, color)
+    }
+
+    fn draw_filled_trigon(
+        &self,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        x3: i16,
+        y3: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (x1, y1) = at(self, x1, y1);
+        let (x2, y2) = at(self, x2, y2);
+        let (x3, y3) = at(self, x3, y3);
+        self.surface()
+            .draw_filled_trigon(x1, y1, x2, y2, x3, y3, color)
+    }
+
+    fn draw_polygon(&self, vx: &[i16], vy: &[i16], color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = at_all(self, vx, vy);
+        self.surface().draw_polygon(&vx, &vy, color)
+    }
+
+    fn draw_aa_polygon(&self, vx: &[i16], vy: &[i16], color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = at_all(self, vx, vy);
+        self.surface().draw_aa_polygon(&vx, &vy, color)
+    }
+
+    fn draw_filled_polygon(&self, vx: &[i16], vy: &[i16], color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = at_all(self, vx, vy);
+        self.surface().draw_filled_polygon(&vx, &vy, color)
+    }
+
+    fn draw_textured_polygon(
+        &self,
+        vx: &[i16],
+        vy: &[i16],
+        texture: &video::Surface,
+        texture_dx: i16,
+        texture_dy: i16,
+        color: sdl::Color,
+    ) -> sdl::Result<()> {
+        let (vx, vy) = at_all(self, vx, vy);
+        self.surface()
+            .draw_textured_polygon(&vx, &vy, texture, texture_dx, texture_dy, color)
+    }
+
+    fn draw_bezier(&self, vx: &[i16], vy: &[i16], s: i32, color: sdl::Color) -> sdl::Result<()> {
+        let (vx, vy) = at_all(self, vx, vy);
+        self.surface().draw_bezier(&vx, &vy, s, color)
+    }
+
+    fn draw_character(&self, x: i16, y: i16, c: char, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_character(x, y, c, color)
+    }
+
+    fn draw_string(&self, x: i16, y: i16, s: &str, color: sdl::Color) -> sdl::Result<()> {
+        let (x, y) = at(self, x, y);
+        self.surface().draw_string(x, y, s, color)
+    }
+}
+
+fn at(view: &video::SurfaceView, x: i16, y: i16) -> (i16, i16) {
+    let point = view.to_surface(Point::new(x, y));
+    (point.x, point.y)
+}
+
+fn at_all(view: &video::SurfaceView, vx: &[i16], vy: &[i16]) -> (Vec<i16>, Vec<i16>) {
+    let origin = view.origin();
+    (
+        vx.iter().map(|x| x.saturating_add(origin.x)).collect(),
+        vy.iter().map(|y| y.saturating_add(origin.y)).collect(),
+    )
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::testing::{self, Image};
+
+    fn render_pie(start: Angle, end: Angle) -> Image {
+        let mut surface = testing::render(21, 21, |surface| {
+            surface.draw_filled_pie_angles(10, 10, 10, start, end, sdl::Color::WHITE)
+        })
+        .unwrap();
+        Image::from_surface(&mut surface).unwrap()
+    }
+
+    fn is_drawn(image: &Image, x: u32, y: u32) -> bool {
+        image.pixels[((y * image.width + x) * 4 + 3) as usize] != 0
+    }
+
+    // SDL_gfx's docs say 0 degrees is down and angles go counterclockwise,
+    // which would put this quarter in the bottom left. It's drawn in the
+    // bottom right, from 3 o'clock clockwise to 6 o'clock.
+    #[test]
+    fn angles_start_right_and_go_clockwise() {
+        let image = render_pie(Angle::RIGHT, Angle::DOWN);
+        assert!(is_drawn(&image, 15, 15));
+        assert!(!is_drawn(&image, 5, 15));
+        assert!(!is_drawn(&image, 5, 5));
+        assert!(!is_drawn(&image, 15, 5));
+    }
+
+    #[test]
+    fn whole_turn_fills_the_circle() {
+        let image = render_pie(Angle::UP, Angle::degrees(270.0 + 360.0));
+        for (x, y) in [(15, 15), (5, 15), (5, 5), (15, 5)] {
+            assert!(is_drawn(&image, x, y));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use libc::c_int;
+use std::collections::VecDeque;
 pub use std::f64::consts::PI;
 
 use sdl_sys::gfx::rotozoom;
@@ -155,6 +156,140 @@ impl RotozoomSurface for Surface {
     }
 }
 
+/// Keeps the most recent results of rotozooming a surface, so a sprite drawn
+/// at the same angle and zoom every frame is only rotozoomed once.
+///
+/// SDL_gfx always allocates a new surface for the result, so this is how to
+/// avoid doing that every frame. Use one cache per source surface, and
+/// [`clear`](RotozoomCache::clear) it if the source changes.
+#[derive(Debug)]
+pub struct RotozoomCache {
+    capacity: usize,
+    angle_step: f64,
+    // The least recently used entry is at the front.
+    entries: VecDeque<(RotozoomKey, Surface)>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct RotozoomKey {
+    rotate: bool,
+    // The bits of the f64s, since f64 isn't Eq. This treats 0.0 and -0.0 as
+    // different, which only costs an extra entry.
+    angle: u64,
+    zoomx: u64,
+    zoomy: u64,
+    smooth: bool,
+}
+
+impl RotozoomCache {
+    /// Creates a cache which keeps up to `capacity` results, dropping the
+    /// least recently used one when it's full. A capacity of 0 is treated
+    /// as 1.
+    pub fn new(capacity: usize) -> RotozoomCache {
+        RotozoomCache {
+            capacity: capacity.max(1),
+            angle_step: 0.0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Rounds angles to the nearest multiple of `step` degrees, so something
+    /// turning slowly reuses results instead of missing the cache every
+    /// frame. A step of 0, the default, uses angles as they are.
+    pub fn set_angle_step(&mut self, step: f64) {
+        self.angle_step = step.abs();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns `src` rotozoomed as by [`RotozoomSurface::rotozoom`].
+    pub fn rotozoom(
+        &mut self,
+        src: &Surface,
+        angle: f64,
+        zoom: f64,
+        smooth: bool,
+    ) -> crate::Result<&Surface> {
+        self.rotozoom_xy(src, angle, zoom, zoom, smooth)
+    }
+
+    /// Returns `src` rotozoomed as by [`RotozoomSurface::rotozoom_xy`].
+    pub fn rotozoom_xy(
+        &mut self,
+        src: &Surface,
+        angle: f64,
+        zoomx: f64,
+        zoomy: f64,
+        smooth: bool,
+    ) -> crate::Result<&Surface> {
+        let angle = self.snap(angle);
+        let key = RotozoomKey {
+            rotate: true,
+            angle: angle.to_bits(),
+            zoomx: zoomx.to_bits(),
+            zoomy: zoomy.to_bits(),
+            smooth,
+        };
+        self.get_or_insert(key, || src.rotozoom_xy(angle, zoomx, zoomy, smooth))
+    }
+
+    /// Returns `src` zoomed as by [`RotozoomSurface::zoom`].
+    pub fn zoom(
+        &mut self,
+        src: &Surface,
+        zoomx: f64,
+        zoomy: f64,
+        smooth: bool,
+    ) -> crate::Result<&Surface> {
+        let key = RotozoomKey {
+            rotate: false,
+            angle: 0,
+            zoomx: zoomx.to_bits(),
+            zoomy: zoomy.to_bits(),
+            smooth,
+        };
+        self.get_or_insert(key, || src.zoom(zoomx, zoomy, smooth))
+    }
+
+    fn snap(&self, angle: f64) -> f64 {
+        if self.angle_step > 0.0 {
+            (angle / self.angle_step).round() * self.angle_step
+        } else {
+            angle
+        }
+    }
+
+    fn get_or_insert<F>(&mut self, key: RotozoomKey, render: F) -> crate::Result<&Surface>
+    where
+        F: FnOnce() -> crate::Result<Surface>,
+    {
+        match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(i) => {
+                let entry = self.entries.remove(i).expect("position is in range");
+                self.entries.push_back(entry);
+            }
+            None => {
+                let surface = render()?;
+                if self.entries.len() >= self.capacity {
+                    self.entries.pop_front();
+                }
+                self.entries.push_back((key, surface));
+            }
+        }
+        Ok(&self.entries.back().expect("an entry was just pushed").1)
+    }
+}
+
 pub fn get_zoom_size(width: i32, height: i32, zoomx: f64, zoomy: f64) -> (i32, i32) {
     let mut w: c_int = 0;
     let mut h: c_int = 0;
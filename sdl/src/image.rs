@@ -0,0 +1,32 @@
+//! Loading images in formats other than BMP, with SDL_image.
+
+use std::path::Path;
+
+use crate::sdl;
+use crate::sys::image;
+use crate::video::Surface;
+
+/// Loads an image from a file, guessing the format from its contents.
+pub fn load<P: AsRef<Path>>(path: P) -> sdl::Result<Surface> {
+    let path = sdl::path_to_cstring(path.as_ref())?;
+    let raw = unsafe { image::IMG_Load(path.as_ptr()) };
+
+    if raw.is_null() {
+        Err(sdl::get_error())
+    } else {
+        Ok(Surface::new(raw))
+    }
+}
+
+/// Loads an image from a file which has already been read into memory,
+/// guessing the format from its contents.
+pub fn load_from_memory(data: &[u8]) -> sdl::Result<Surface> {
+    let rw = sdl::rw_from_slice(data)?;
+    let raw = unsafe { image::IMG_Load_RW(rw, 1) };
+
+    if raw.is_null() {
+        Err(sdl::get_error())
+    } else {
+        Ok(Surface::new(raw))
+    }
+}
@@ -0,0 +1,192 @@
+//! Keeps track of which keys and buttons are held down, and which were
+//! pressed or released this frame.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::event::{
+    ActiveEvent, AppState, Button, Event, JoyButtonEvent, KeyboardEvent, MouseButtonEvent,
+    MouseMotionEvent,
+};
+use crate::keyboard::Key;
+
+/// The state of the keyboard, mouse and joystick buttons, built up from
+/// events.
+///
+/// Call [`begin_frame`](FrameInput::begin_frame) at the start of every frame,
+/// then pass it every event with [`handle_event`](FrameInput::handle_event).
+/// After that it can answer whether a button is down, and whether it was
+/// just pressed or released since the frame started. A button which is
+/// pressed and released within the same frame counts as both.
+#[derive(Clone, Debug, Default)]
+pub struct FrameInput {
+    keys: Buttons<Key>,
+    mouse_buttons: Buttons<Button>,
+    // Keyed by device and button.
+    joy_buttons: Buttons<(u8, u8)>,
+    mouse_position: (u16, u16),
+    mouse_motion: (i32, i32),
+}
+
+impl FrameInput {
+    pub fn new() -> FrameInput {
+        FrameInput::default()
+    }
+
+    /// Forgets what was pressed and released during the last frame.
+    pub fn begin_frame(&mut self) {
+        self.keys.begin_frame();
+        self.mouse_buttons.begin_frame();
+        self.joy_buttons.begin_frame();
+        self.mouse_motion = (0, 0);
+    }
+
+    pub fn handle_event<U>(&mut self, event: &Event<U>) {
+        match event {
+            Event::Keyboard(KeyboardEvent::KeyDown(keysym)) => self.keys.press(keysym.key),
+            Event::Keyboard(KeyboardEvent::KeyUp(keysym)) => self.keys.release(keysym.key),
+            Event::MouseButton(MouseButtonEvent {
+                button,
+                pressed,
+                x,
+                y,
+                ..
+            }) => {
+                self.mouse_position = (*x, *y);
+                if *pressed {
+                    self.mouse_buttons.press(*button);
+                } else {
+                    self.mouse_buttons.release(*button);
+                }
+            }
+            Event::MouseMotion(MouseMotionEvent {
+                x, y, xrel, yrel, ..
+            }) => {
+                self.mouse_position = (*x, *y);
+                self.mouse_motion.0 += *xrel as i32;
+                self.mouse_motion.1 += *yrel as i32;
+            }
+            Event::JoyButton(JoyButtonEvent {
+                device,
+                button,
+                pressed,
+            }) => {
+                if *pressed {
+                    self.joy_buttons.press((*device, *button));
+                } else {
+                    self.joy_buttons.release((*device, *button));
+                }
+            }
+            // Releases aren't reported while the window is out of focus, so
+            // everything which is down is released when focus is lost.
+            Event::Active(ActiveEvent { gain: false, state })
+                if state.contains(AppState::INPUT_FOCUS) =>
+            {
+                self.keys.release_all();
+                self.mouse_buttons.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys.is_down(&key)
+    }
+
+    pub fn key_just_pressed(&self, key: Key) -> bool {
+        self.keys.just_pressed(&key)
+    }
+
+    pub fn key_just_released(&self, key: Key) -> bool {
+        self.keys.just_released(&key)
+    }
+
+    pub fn is_mouse_down(&self, button: Button) -> bool {
+        self.mouse_buttons.is_down(&button)
+    }
+
+    pub fn mouse_just_pressed(&self, button: Button) -> bool {
+        self.mouse_buttons.just_pressed(&button)
+    }
+
+    pub fn mouse_just_released(&self, button: Button) -> bool {
+        self.mouse_buttons.just_released(&button)
+    }
+
+    pub fn is_joy_button_down(&self, device: u8, button: u8) -> bool {
+        self.joy_buttons.is_down(&(device, button))
+    }
+
+    pub fn joy_button_just_pressed(&self, device: u8, button: u8) -> bool {
+        self.joy_buttons.just_pressed(&(device, button))
+    }
+
+    pub fn joy_button_just_released(&self, device: u8, button: u8) -> bool {
+        self.joy_buttons.just_released(&(device, button))
+    }
+
+    /// Where the mouse was last seen.
+    pub fn mouse_position(&self) -> (u16, u16) {
+        self.mouse_position
+    }
+
+    /// How far the mouse moved this frame.
+    pub fn mouse_motion(&self) -> (i32, i32) {
+        self.mouse_motion
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Buttons<T> {
+    down: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+// Derived Default would require T: Default.
+impl<T> Default for Buttons<T> {
+    fn default() -> Buttons<T> {
+        Buttons {
+            down: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    fn begin_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    // Key repeat sends more presses for a key which is already down, which
+    // aren't new presses.
+    fn press(&mut self, button: T) {
+        if self.down.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.down.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.down.drain());
+    }
+
+    fn is_down(&self, button: &T) -> bool {
+        self.down.contains(button)
+    }
+
+    fn just_pressed(&self, button: &T) -> bool {
+        self.pressed.contains(button)
+    }
+
+    fn just_released(&self, button: &T) -> bool {
+        self.released.contains(button)
+    }
+}
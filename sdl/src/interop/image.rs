@@ -0,0 +1,154 @@
+//! Conversions to the types in the [`image`](::image_crate) crate, and a
+//! recorder which saves the screen to PNGs.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use ::image_crate::{ImageError, Rgba, RgbaImage};
+
+use crate::sdl;
+use crate::video::{PixelFormatSpec, Surface};
+use crate::Color;
+
+impl From<Color> for Rgba<u8> {
+    fn from(color: Color) -> Rgba<u8> {
+        Rgba([color.r, color.g, color.b, color.a])
+    }
+}
+
+impl From<Rgba<u8>> for Color {
+    fn from(Rgba([r, g, b, a]): Rgba<u8>) -> Color {
+        Color::rgba(r, g, b, a)
+    }
+}
+
+/// Copies the pixels of a surface into an image.
+pub fn to_rgba_image(surface: &mut Surface) -> sdl::Result<RgbaImage> {
+    let (width, height) = (surface.width(), surface.height());
+    let pixels = surface.read_rgba()?;
+    Ok(RgbaImage::from_raw(width, height, pixels).expect("read_rgba returns whole rows"))
+}
+
+/// Copies an image into a new surface, in [`PixelFormatSpec::RGBA8888`].
+pub fn from_rgba_image(image: &RgbaImage) -> sdl::Result<Surface> {
+    let mut surface =
+        Surface::with_format(image.width(), image.height(), PixelFormatSpec::RGBA8888)?;
+    let row_len = image.width() as usize * 4;
+    if row_len > 0 {
+        let mut lock = surface.lock()?;
+        let pitch = lock.pitch();
+        for (dst, src) in lock
+            .pixels_mut()
+            .chunks_mut(pitch)
+            .zip(image.as_raw().chunks_exact(row_len))
+        {
+            dst[..row_len].copy_from_slice(src);
+        }
+    }
+    Ok(surface)
+}
+
+// The number of captured frames which can wait to be saved before capturing
+// blocks, which keeps a slow disk from using up all the memory.
+const QUEUE_LEN: usize = 8;
+
+/// Saves every frame, or every `n`th frame, to numbered PNGs in a directory.
+///
+/// Frames are copied on the calling thread and encoded on a background
+/// thread. Call [`finish`](Recorder::finish) to wait for the remaining
+/// frames to be saved and find out whether anything went wrong; dropping the
+/// recorder waits too, but ignores errors.
+#[derive(Debug)]
+pub struct Recorder {
+    sender: Option<SyncSender<(PathBuf, RgbaImage)>>,
+    thread: Option<JoinHandle<Result<usize, RecorderError>>>,
+    dir: PathBuf,
+    interval: u32,
+    frame: u64,
+}
+
+impl Recorder {
+    /// Starts a recorder which saves frames to `dir`, creating it if needed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Recorder, RecorderError> {
+        let dir = dir.as_ref().to_owned();
+        std::fs::create_dir_all(&dir)?;
+
+        let (sender, receiver) = mpsc::sync_channel::<(PathBuf, RgbaImage)>(QUEUE_LEN);
+        let thread = thread::spawn(move || {
+            let mut saved = 0;
+            for (path, image) in receiver {
+                image.save(path)?;
+                saved += 1;
+            }
+            Ok(saved)
+        });
+
+        Ok(Recorder {
+            sender: Some(sender),
+            thread: Some(thread),
+            dir,
+            interval: 1,
+            frame: 0,
+        })
+    }
+
+    /// Only saves every `n`th captured frame. An `n` of 0 is treated as 1.
+    pub fn every(&mut self, n: u32) -> &mut Recorder {
+        self.interval = n.max(1);
+        self
+    }
+
+    /// Captures a frame from `screen`, if it's one which should be saved.
+    /// Files are numbered by the frame, so skipped frames leave gaps.
+    ///
+    /// If saving an earlier frame failed, recording stops and that error is
+    /// returned.
+    pub fn capture(&mut self, screen: &mut Surface) -> Result<(), RecorderError> {
+        let frame = self.frame;
+        self.frame += 1;
+        if frame % self.interval as u64 != 0 {
+            return Ok(());
+        }
+
+        let sender = self.sender.as_ref().ok_or(RecorderError::Stopped)?;
+        let image = to_rgba_image(screen)?;
+        let path = self.dir.join(format!("frame_{:06}.png", frame));
+        if sender.send((path, image)).is_err() {
+            // The thread only hangs up when it fails.
+            self.finish_thread()?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the captured frames to be saved, and returns how many were.
+    pub fn finish(mut self) -> Result<usize, RecorderError> {
+        self.finish_thread()
+    }
+
+    fn finish_thread(&mut self) -> Result<usize, RecorderError> {
+        self.sender = None;
+        match self.thread.take() {
+            Some(thread) => thread.join().map_err(|_| RecorderError::Stopped)?,
+            None => Err(RecorderError::Stopped),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.finish_thread();
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RecorderError {
+    #[error("the recorder has stopped")]
+    Stopped,
+    #[error("image error: {}", .0)]
+    ImageError(#[from] ImageError),
+    #[error("IO error: {}", .0)]
+    IoError(#[from] std::io::Error),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
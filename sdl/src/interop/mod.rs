@@ -0,0 +1,7 @@
+//! Conversions between this crate's types and those of other crates.
+
+#[cfg(feature = "image-crate")]
+pub mod image;
+
+#[cfg(feature = "sdl2")]
+pub mod sdl2;
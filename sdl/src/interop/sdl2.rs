@@ -0,0 +1,441 @@
+//! Conversions to and from the types in the [`sdl2`](::sdl2) crate.
+//!
+//! These are meant to make it possible to share game code between an SDL 1.2
+//! and an SDL2 backend while porting between the two. SDL 1.2 only ever has a
+//! single window and doesn't timestamp its events, so converted events always
+//! have a `window_id` and `timestamp` of 0, and key events have no scancode,
+//! since the hardware scancodes SDL 1.2 reports mean nothing to SDL2.
+
+use std::convert::TryFrom;
+
+use ::sdl2::event::{Event as Sdl2Event, WindowEvent};
+use ::sdl2::joystick::HatState;
+use ::sdl2::keyboard::{self, Keycode};
+use ::sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+use ::sdl2::pixels;
+use ::sdl2::rect;
+
+use crate::event::{AppState, Button, Event, KeyboardEvent, MouseButtonState};
+use crate::keyboard::{Key, Mod};
+use crate::{Color, Point, Rect};
+
+impl From<Color> for pixels::Color {
+    fn from(color: Color) -> pixels::Color {
+        pixels::Color::RGBA(color.r, color.g, color.b, color.a)
+    }
+}
+
+impl From<pixels::Color> for Color {
+    fn from(color: pixels::Color) -> Color {
+        Color::rgba(color.r, color.g, color.b, color.a)
+    }
+}
+
+impl From<Point> for rect::Point {
+    fn from(point: Point) -> rect::Point {
+        rect::Point::new(point.x as i32, point.y as i32)
+    }
+}
+
+/// SDL2 rects are never empty, since `rect::Rect::new` makes a width or
+/// height of 0 into 1, so empty rects are handed back as the error.
+impl TryFrom<Rect> for rect::Rect {
+    type Error = Rect;
+
+    fn try_from(r: Rect) -> Result<rect::Rect, Rect> {
+        if r.is_empty() {
+            return Err(r);
+        }
+        Ok(rect::Rect::new(
+            r.x as i32, r.y as i32, r.w as u32, r.h as u32,
+        ))
+    }
+}
+
+/// Anything outside of the range of SDL 1.2's 16-bit rects is clamped to it.
+impl From<rect::Rect> for Rect {
+    fn from(r: rect::Rect) -> Rect {
+        let coord = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        let size = |v: u32| v.min(u16::MAX as u32) as u16;
+        Rect::new(
+            coord(r.x()),
+            coord(r.y()),
+            size(r.width()),
+            size(r.height()),
+        )
+    }
+}
+
+macro_rules! keycodes {
+    ($($key:ident => $keycode:ident,)*) => {
+        fn to_keycode(key: Key) -> Option<Keycode> {
+            match key {
+                $(Key::$key => Some(Keycode::$keycode),)*
+                // SDL 1.2 calls the command keys on a Mac meta keys, where
+                // SDL2 counts them as GUI keys along with the Windows keys.
+                Key::LMeta => Some(Keycode::LGui),
+                Key::RMeta => Some(Keycode::RGui),
+                _ => None,
+            }
+        }
+
+        fn from_keycode(keycode: Keycode) -> Key {
+            match keycode {
+                $(Keycode::$keycode => Key::$key,)*
+                _ => Key::Unknown,
+            }
+        }
+    };
+}
+
+// The keys both versions have. SDL 1.2's `World` keys, `Compose`, `Break`
+// and `Euro` have no SDL2 keycode.
+keycodes! {
+    Backspace => Backspace,
+    Tab => Tab,
+    Clear => Clear,
+    Return => Return,
+    Pause => Pause,
+    Escape => Escape,
+    Space => Space,
+    Exclaim => Exclaim,
+    QuoteDbl => Quotedbl,
+    Hash => Hash,
+    Dollar => Dollar,
+    Ampersand => Ampersand,
+    Quote => Quote,
+    LeftParen => LeftParen,
+    RightParen => RightParen,
+    Asterisk => Asterisk,
+    Plus => Plus,
+    Comma => Comma,
+    Minus => Minus,
+    Period => Period,
+    Slash => Slash,
+    Num0 => Num0,
+    Num1 => Num1,
+    Num2 => Num2,
+    Num3 => Num3,
+    Num4 => Num4,
+    Num5 => Num5,
+    Num6 => Num6,
+    Num7 => Num7,
+    Num8 => Num8,
+    Num9 => Num9,
+    Colon => Colon,
+    Semicolon => Semicolon,
+    Less => Less,
+    Equals => Equals,
+    Greater => Greater,
+    Question => Question,
+    At => At,
+    LeftBracket => LeftBracket,
+    Backslash => Backslash,
+    RightBracket => RightBracket,
+    Caret => Caret,
+    Underscore => Underscore,
+    Backquote => Backquote,
+    A => A,
+    B => B,
+    C => C,
+    D => D,
+    E => E,
+    F => F,
+    G => G,
+    H => H,
+    I => I,
+    J => J,
+    K => K,
+    L => L,
+    M => M,
+    N => N,
+    O => O,
+    P => P,
+    Q => Q,
+    R => R,
+    S => S,
+    T => T,
+    U => U,
+    V => V,
+    W => W,
+    X => X,
+    Y => Y,
+    Z => Z,
+    Delete => Delete,
+    Kp0 => Kp0,
+    Kp1 => Kp1,
+    Kp2 => Kp2,
+    Kp3 => Kp3,
+    Kp4 => Kp4,
+    Kp5 => Kp5,
+    Kp6 => Kp6,
+    Kp7 => Kp7,
+    Kp8 => Kp8,
+    Kp9 => Kp9,
+    KpPeriod => KpPeriod,
+    KpDivide => KpDivide,
+    KpMultiply => KpMultiply,
+    KpMinus => KpMinus,
+    KpPlus => KpPlus,
+    KpEnter => KpEnter,
+    KpEquals => KpEquals,
+    Up => Up,
+    Down => Down,
+    Right => Right,
+    Left => Left,
+    Insert => Insert,
+    Home => Home,
+    End => End,
+    PageUp => PageUp,
+    PageDown => PageDown,
+    F1 => F1,
+    F2 => F2,
+    F3 => F3,
+    F4 => F4,
+    F5 => F5,
+    F6 => F6,
+    F7 => F7,
+    F8 => F8,
+    F9 => F9,
+    F10 => F10,
+    F11 => F11,
+    F12 => F12,
+    F13 => F13,
+    F14 => F14,
+    F15 => F15,
+    NumLock => NumLockClear,
+    CapsLock => CapsLock,
+    ScrollLock => ScrollLock,
+    RShift => RShift,
+    LShift => LShift,
+    RCtrl => RCtrl,
+    LCtrl => LCtrl,
+    RAlt => RAlt,
+    LAlt => LAlt,
+    LSuper => LGui,
+    RSuper => RGui,
+    Mode => Mode,
+    Help => Help,
+    Print => PrintScreen,
+    SysReq => Sysreq,
+    // The key next to the right Windows key, which SDL2 names after the
+    // context menu it opens.
+    Menu => Application,
+    Power => Power,
+    Undo => Undo,
+}
+
+/// Keys SDL2 has no keycode for, such as SDL 1.2's `World` keys, are handed
+/// back as the error.
+impl TryFrom<Key> for Keycode {
+    type Error = Key;
+
+    fn try_from(key: Key) -> Result<Keycode, Key> {
+        to_keycode(key).ok_or(key)
+    }
+}
+
+/// Keycodes SDL 1.2 has no key for become [`Key::Unknown`].
+impl From<Keycode> for Key {
+    fn from(keycode: Keycode) -> Key {
+        from_keycode(keycode)
+    }
+}
+
+// SDL2 calls meta keys GUI keys, as with the keycodes.
+const MODS: &[(Mod, keyboard::Mod)] = &[
+    (Mod::LSHIFT, keyboard::Mod::LSHIFTMOD),
+    (Mod::RSHIFT, keyboard::Mod::RSHIFTMOD),
+    (Mod::LCTRL, keyboard::Mod::LCTRLMOD),
+    (Mod::RCTRL, keyboard::Mod::RCTRLMOD),
+    (Mod::LALT, keyboard::Mod::LALTMOD),
+    (Mod::RALT, keyboard::Mod::RALTMOD),
+    (Mod::LMETA, keyboard::Mod::LGUIMOD),
+    (Mod::RMETA, keyboard::Mod::RGUIMOD),
+    (Mod::NUM, keyboard::Mod::NUMMOD),
+    (Mod::CAPS, keyboard::Mod::CAPSMOD),
+    (Mod::MODE, keyboard::Mod::MODEMOD),
+];
+
+impl From<Mod> for keyboard::Mod {
+    fn from(modifiers: Mod) -> keyboard::Mod {
+        MODS.iter()
+            .filter(|(sdl, _)| modifiers.contains(*sdl))
+            .fold(keyboard::Mod::NOMOD, |acc, &(_, sdl2)| acc | sdl2)
+    }
+}
+
+impl From<keyboard::Mod> for Mod {
+    fn from(modifiers: keyboard::Mod) -> Mod {
+        MODS.iter()
+            .filter(|(_, sdl2)| modifiers.contains(*sdl2))
+            .fold(Mod::empty(), |acc, &(sdl, _)| acc | sdl)
+    }
+}
+
+/// SDL2 reports the mouse wheel as its own event rather than as buttons, so
+/// `WheelUp`, `WheelDown` and any unknown buttons become
+/// [`MouseButton::Unknown`].
+impl From<Button> for MouseButton {
+    fn from(button: Button) -> MouseButton {
+        match button {
+            Button::Left => MouseButton::Left,
+            Button::Middle => MouseButton::Middle,
+            Button::Right => MouseButton::Right,
+            Button::X1 => MouseButton::X1,
+            Button::X2 => MouseButton::X2,
+            Button::WheelUp | Button::WheelDown | Button::Other(_) => MouseButton::Unknown,
+        }
+    }
+}
+
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Button {
+        match button {
+            MouseButton::Left => Button::Left,
+            MouseButton::Middle => Button::Middle,
+            MouseButton::Right => Button::Right,
+            MouseButton::X1 => Button::X1,
+            MouseButton::X2 => Button::X2,
+            MouseButton::Unknown => Button::Other(0),
+        }
+    }
+}
+
+/// Converts an event into its closest SDL2 equivalent.
+///
+/// Events which have no SDL2 counterpart (user events, `SysWM` and
+/// `Unknown`) are handed back unchanged as the error. Key events for keys
+/// SDL2 has no keycode for are converted with a `keycode` of `None`.
+impl<T> TryFrom<Event<T>> for Sdl2Event {
+    type Error = Event<T>;
+
+    fn try_from(event: Event<T>) -> Result<Sdl2Event, Event<T>> {
+        let window = |win_event| Sdl2Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event,
+        };
+
+        Ok(match event {
+            // SDL2 reports each state change separately, so only the most
+            // significant one makes it across.
+            Event::Active(ref active) => window(match (active.state, active.gain) {
+                (state, true) if state.contains(AppState::ACTIVE) => WindowEvent::Restored,
+                (state, false) if state.contains(AppState::ACTIVE) => WindowEvent::Minimized,
+                (state, true) if state.contains(AppState::INPUT_FOCUS) => WindowEvent::FocusGained,
+                (state, false) if state.contains(AppState::INPUT_FOCUS) => WindowEvent::FocusLost,
+                (state, true) if state.contains(AppState::MOUSE_FOCUS) => WindowEvent::Enter,
+                (state, false) if state.contains(AppState::MOUSE_FOCUS) => WindowEvent::Leave,
+                _ => return Err(event),
+            }),
+            Event::MouseMotion(motion) => Sdl2Event::MouseMotion {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(sdl2_buttons(motion.buttons)),
+                x: motion.x as i32,
+                y: motion.y as i32,
+                xrel: motion.xrel as i32,
+                yrel: motion.yrel as i32,
+            },
+            Event::MouseButton(button) if button.pressed => Sdl2Event::MouseButtonDown {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: button.button.into(),
+                clicks: 1,
+                x: button.x as i32,
+                y: button.y as i32,
+            },
+            Event::MouseButton(button) => Sdl2Event::MouseButtonUp {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: button.button.into(),
+                clicks: 1,
+                x: button.x as i32,
+                y: button.y as i32,
+            },
+            Event::JoyAxis(axis) => Sdl2Event::JoyAxisMotion {
+                timestamp: 0,
+                which: axis.device as u32,
+                axis_idx: axis.axis,
+                value: axis.value,
+            },
+            Event::JoyButton(button) if button.pressed => Sdl2Event::JoyButtonDown {
+                timestamp: 0,
+                which: button.device as u32,
+                button_idx: button.button,
+            },
+            Event::JoyButton(button) => Sdl2Event::JoyButtonUp {
+                timestamp: 0,
+                which: button.device as u32,
+                button_idx: button.button,
+            },
+            Event::JoyHat(hat) => Sdl2Event::JoyHatMotion {
+                timestamp: 0,
+                which: hat.device as u32,
+                hat_idx: hat.hat,
+                state: HatState::from_raw(hat.value.raw()),
+            },
+            Event::JoyBall(ball) => Sdl2Event::JoyBallMotion {
+                timestamp: 0,
+                which: ball.device as u32,
+                ball_idx: ball.ball,
+                xrel: ball.xrel,
+                yrel: ball.yrel,
+            },
+            Event::Resize(resize) => window(WindowEvent::Resized(resize.w, resize.h)),
+            Event::MouseWheel { delta } => Sdl2Event::MouseWheel {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                x: 0,
+                y: delta,
+                direction: MouseWheelDirection::Normal,
+            },
+            Event::TextInput(c) => Sdl2Event::TextInput {
+                timestamp: 0,
+                window_id: 0,
+                text: c.to_string(),
+            },
+            Event::Expose => window(WindowEvent::Exposed),
+            Event::Keyboard(KeyboardEvent::KeyDown(keysym)) => Sdl2Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: to_keycode(keysym.key),
+                scancode: None,
+                keymod: keysym.modifiers.into(),
+                repeat: false,
+            },
+            Event::Keyboard(KeyboardEvent::KeyUp(keysym)) => Sdl2Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode: to_keycode(keysym.key),
+                scancode: None,
+                keymod: keysym.modifiers.into(),
+                repeat: false,
+            },
+            Event::Quit => Sdl2Event::Quit { timestamp: 0 },
+            Event::Keyboard(KeyboardEvent::Unknown)
+            | Event::SysWM
+            | Event::User(_)
+            | Event::Unknown => return Err(event),
+        })
+    }
+}
+
+// SDL 2 has no wheel buttons, so X1 and X2 are the 4th and 5th bits there.
+fn sdl2_buttons(buttons: MouseButtonState) -> u32 {
+    let mut state = (buttons
+        & (MouseButtonState::LEFT | MouseButtonState::MIDDLE | MouseButtonState::RIGHT))
+        .bits() as u32;
+    if buttons.contains(MouseButtonState::X1) {
+        state |= 1 << 3;
+    }
+    if buttons.contains(MouseButtonState::X2) {
+        state |= 1 << 4;
+    }
+    state
+}
@@ -1,13 +1,18 @@
+use std::ffi::{c_int, CStr};
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
 
 use sys::SDL_InitSubSystem;
 
+use crate::event::HatState;
 use crate::sys;
 use crate::sdl;
+use crate::SDL;
 
 #[derive(Debug)]
 pub struct Subsystem {
     _pinned: std::marker::PhantomPinned,
+    _not_send: sdl::NotSend,
 }
 
 impl Drop for Subsystem {
@@ -25,7 +30,147 @@ impl Subsystem {
         } else {
             Ok(Subsystem {
                 _pinned: PhantomPinned,
+                _not_send: PhantomData,
             })
         }
     }
 }
+
+impl SDL {
+    pub fn joystick(&self) -> sdl::Result<Subsystem> {
+        Subsystem::new()
+    }
+}
+
+impl Subsystem {
+    /// The number of joysticks attached to the system.
+    pub fn num_joysticks(&self) -> u32 {
+        unsafe { sys::SDL_NumJoysticks() }.max(0) as u32
+    }
+
+    /// Whether the joystick at `index` is open.
+    pub fn is_open(&self, index: u32) -> bool {
+        unsafe { sys::SDL_JoystickOpened(index as c_int) != 0 }
+    }
+
+    /// Reads the current state of every open joystick, for the getters on
+    /// [`Joystick`]. Pumping events does this already while joystick events
+    /// are turned on, so this is only needed when they're off.
+    pub fn update(&self) {
+        unsafe { sys::SDL_JoystickUpdate() }
+    }
+
+    /// Turns joystick events on or off. With them off, the state of each
+    /// joystick has to be polled, after calling
+    /// [`update`](Subsystem::update) once a frame.
+    pub fn set_events_enabled(&self, enabled: bool) {
+        let state = if enabled {
+            sys::SDL_ENABLE
+        } else {
+            sys::SDL_IGNORE
+        };
+        unsafe { sys::SDL_JoystickEventState(state as c_int) };
+    }
+
+    /// Whether joystick events are turned on.
+    pub fn events_enabled(&self) -> bool {
+        unsafe { sys::SDL_JoystickEventState(sys::SDL_QUERY) == sys::SDL_ENABLE as c_int }
+    }
+
+    /// Opens the joystick at `index`, counting from 0.
+    pub fn open(&self, index: u32) -> sdl::Result<Joystick> {
+        let raw = unsafe { sys::SDL_JoystickOpen(index as c_int) };
+        if raw.is_null() {
+            Err(sdl::get_error())
+        } else {
+            Ok(Joystick {
+                raw,
+                _not_send: PhantomData,
+            })
+        }
+    }
+}
+
+/// An open joystick, as returned by [`Subsystem::open`]. It shouldn't outlive
+/// the joystick subsystem.
+#[derive(Debug)]
+pub struct Joystick {
+    raw: *mut sys::SDL_Joystick,
+    _not_send: sdl::NotSend,
+}
+
+impl Joystick {
+    pub fn raw(&self) -> *mut sys::SDL_Joystick {
+        self.raw
+    }
+
+    /// The index the joystick was opened with.
+    pub fn index(&self) -> u32 {
+        unsafe { sys::SDL_JoystickIndex(self.raw) as u32 }
+    }
+
+    /// The name the driver gives the joystick.
+    pub fn name(&self) -> String {
+        name(self.index()).unwrap_or_default()
+    }
+
+    pub fn num_axes(&self) -> u32 {
+        unsafe { sys::SDL_JoystickNumAxes(self.raw) }.max(0) as u32
+    }
+
+    pub fn num_buttons(&self) -> u32 {
+        unsafe { sys::SDL_JoystickNumButtons(self.raw) }.max(0) as u32
+    }
+
+    pub fn num_hats(&self) -> u32 {
+        unsafe { sys::SDL_JoystickNumHats(self.raw) }.max(0) as u32
+    }
+
+    pub fn num_balls(&self) -> u32 {
+        unsafe { sys::SDL_JoystickNumBalls(self.raw) }.max(0) as u32
+    }
+
+    /// The position of an axis, from -32768 to 32767. Axes the joystick
+    /// doesn't have read 0.
+    pub fn axis(&self, axis: u32) -> i16 {
+        unsafe { sys::SDL_JoystickGetAxis(self.raw, axis as c_int) }
+    }
+
+    /// Whether a button is held down. Buttons the joystick doesn't have
+    /// read as released.
+    pub fn button(&self, button: u32) -> bool {
+        unsafe { sys::SDL_JoystickGetButton(self.raw, button as c_int) != 0 }
+    }
+
+    /// The position of a hat. Hats the joystick doesn't have read as
+    /// centered.
+    pub fn hat(&self, hat: u32) -> HatState {
+        HatState::from_raw(unsafe { sys::SDL_JoystickGetHat(self.raw, hat as c_int) })
+    }
+
+    /// How far a trackball has moved since it was last read, as `(dx, dy)`.
+    pub fn ball(&self, ball: u32) -> sdl::Result<(i32, i32)> {
+        let (mut dx, mut dy) = (0, 0);
+        if unsafe { sys::SDL_JoystickGetBall(self.raw, ball as c_int, &mut dx, &mut dy) } != 0 {
+            Err(sdl::get_error())
+        } else {
+            Ok((dx, dy))
+        }
+    }
+}
+
+impl Drop for Joystick {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_JoystickClose(self.raw) }
+    }
+}
+
+/// The name of the joystick at `index`, whether it's open or not.
+pub fn name(index: u32) -> Option<String> {
+    let name = unsafe { sys::SDL_JoystickName(index as c_int) };
+    if name.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned())
+    }
+}
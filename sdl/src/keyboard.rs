@@ -0,0 +1,528 @@
+//! Keyboard keys and modifiers, and a helper for typing in text.
+
+use std::ffi::c_int;
+use std::mem;
+use std::ptr;
+
+use bitflags::bitflags;
+
+use crate::event::{Event, KeyboardEvent};
+use crate::sys;
+use crate::VideoSubsystem;
+
+// Generates the Key enum along with the conversions to and from SDLKey and
+// the names SDL gives each key, so the list of keys only has to be written
+// out once.
+macro_rules! keys {
+    ($($key:ident => $sym:ident, $name:literal,)*) => {
+        /// A key, matching `SDLKey`.
+        ///
+        /// Keys are named after their unshifted symbol on a US layout. Letters
+        /// are always reported in lower case, so check the modifiers for
+        /// shift, or use [`Keysym::unicode`] for text.
+        #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum Key {
+            $($key,)*
+        }
+
+        impl Key {
+            /// Converts a raw `SDLKey` value, mapping anything SDL doesn't
+            /// define to [`Key::Unknown`].
+            pub fn from_raw(sym: u32) -> Key {
+                #[allow(non_upper_case_globals)]
+                mod syms {
+                    use crate::sys;
+                    $(pub const $sym: u32 = sys::SDL_Key::$sym as u32;)*
+                }
+
+                match sym {
+                    $(syms::$sym => Key::$key,)*
+                    _ => Key::Unknown,
+                }
+            }
+
+            pub fn raw(self) -> sys::SDL_Key {
+                match self {
+                    $(Key::$key => sys::SDL_Key::$sym,)*
+                }
+            }
+
+            /// The name of the key, as `SDL_GetKeyName` gives it, such as
+            /// `"left shift"` or `"[5]"` for 5 on the keypad. Unlike
+            /// `SDL_GetKeyName`, this doesn't need the video subsystem.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Key::$key => $name,)*
+                }
+            }
+
+            /// Looks up a key by the name [`name`](Key::name) gives it,
+            /// ignoring ASCII case, for reading key bindings back from a
+            /// config file.
+            pub fn from_name(name: &str) -> Option<Key> {
+                const NAMES: &[(&str, Key)] = &[$(($name, Key::$key),)*];
+                NAMES
+                    .iter()
+                    .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+                    .map(|&(_, key)| key)
+            }
+        }
+    };
+}
+
+keys! {
+    Unknown => SDLK_UNKNOWN, "unknown key",
+    Backspace => SDLK_BACKSPACE, "backspace",
+    Tab => SDLK_TAB, "tab",
+    Clear => SDLK_CLEAR, "clear",
+    Return => SDLK_RETURN, "return",
+    Pause => SDLK_PAUSE, "pause",
+    Escape => SDLK_ESCAPE, "escape",
+    Space => SDLK_SPACE, "space",
+    Exclaim => SDLK_EXCLAIM, "!",
+    QuoteDbl => SDLK_QUOTEDBL, "\"",
+    Hash => SDLK_HASH, "#",
+    Dollar => SDLK_DOLLAR, "$",
+    Ampersand => SDLK_AMPERSAND, "&",
+    Quote => SDLK_QUOTE, "'",
+    LeftParen => SDLK_LEFTPAREN, "(",
+    RightParen => SDLK_RIGHTPAREN, ")",
+    Asterisk => SDLK_ASTERISK, "*",
+    Plus => SDLK_PLUS, "+",
+    Comma => SDLK_COMMA, ",",
+    Minus => SDLK_MINUS, "-",
+    Period => SDLK_PERIOD, ".",
+    Slash => SDLK_SLASH, "/",
+    Num0 => SDLK_0, "0",
+    Num1 => SDLK_1, "1",
+    Num2 => SDLK_2, "2",
+    Num3 => SDLK_3, "3",
+    Num4 => SDLK_4, "4",
+    Num5 => SDLK_5, "5",
+    Num6 => SDLK_6, "6",
+    Num7 => SDLK_7, "7",
+    Num8 => SDLK_8, "8",
+    Num9 => SDLK_9, "9",
+    Colon => SDLK_COLON, ":",
+    Semicolon => SDLK_SEMICOLON, ";",
+    Less => SDLK_LESS, "<",
+    Equals => SDLK_EQUALS, "=",
+    Greater => SDLK_GREATER, ">",
+    Question => SDLK_QUESTION, "?",
+    At => SDLK_AT, "@",
+    LeftBracket => SDLK_LEFTBRACKET, "[",
+    Backslash => SDLK_BACKSLASH, "\\",
+    RightBracket => SDLK_RIGHTBRACKET, "]",
+    Caret => SDLK_CARET, "^",
+    Underscore => SDLK_UNDERSCORE, "_",
+    Backquote => SDLK_BACKQUOTE, "`",
+    A => SDLK_a, "a",
+    B => SDLK_b, "b",
+    C => SDLK_c, "c",
+    D => SDLK_d, "d",
+    E => SDLK_e, "e",
+    F => SDLK_f, "f",
+    G => SDLK_g, "g",
+    H => SDLK_h, "h",
+    I => SDLK_i, "i",
+    J => SDLK_j, "j",
+    K => SDLK_k, "k",
+    L => SDLK_l, "l",
+    M => SDLK_m, "m",
+    N => SDLK_n, "n",
+    O => SDLK_o, "o",
+    P => SDLK_p, "p",
+    Q => SDLK_q, "q",
+    R => SDLK_r, "r",
+    S => SDLK_s, "s",
+    T => SDLK_t, "t",
+    U => SDLK_u, "u",
+    V => SDLK_v, "v",
+    W => SDLK_w, "w",
+    X => SDLK_x, "x",
+    Y => SDLK_y, "y",
+    Z => SDLK_z, "z",
+    Delete => SDLK_DELETE, "delete",
+    World0 => SDLK_WORLD_0, "world 0",
+    World1 => SDLK_WORLD_1, "world 1",
+    World2 => SDLK_WORLD_2, "world 2",
+    World3 => SDLK_WORLD_3, "world 3",
+    World4 => SDLK_WORLD_4, "world 4",
+    World5 => SDLK_WORLD_5, "world 5",
+    World6 => SDLK_WORLD_6, "world 6",
+    World7 => SDLK_WORLD_7, "world 7",
+    World8 => SDLK_WORLD_8, "world 8",
+    World9 => SDLK_WORLD_9, "world 9",
+    World10 => SDLK_WORLD_10, "world 10",
+    World11 => SDLK_WORLD_11, "world 11",
+    World12 => SDLK_WORLD_12, "world 12",
+    World13 => SDLK_WORLD_13, "world 13",
+    World14 => SDLK_WORLD_14, "world 14",
+    World15 => SDLK_WORLD_15, "world 15",
+    World16 => SDLK_WORLD_16, "world 16",
+    World17 => SDLK_WORLD_17, "world 17",
+    World18 => SDLK_WORLD_18, "world 18",
+    World19 => SDLK_WORLD_19, "world 19",
+    World20 => SDLK_WORLD_20, "world 20",
+    World21 => SDLK_WORLD_21, "world 21",
+    World22 => SDLK_WORLD_22, "world 22",
+    World23 => SDLK_WORLD_23, "world 23",
+    World24 => SDLK_WORLD_24, "world 24",
+    World25 => SDLK_WORLD_25, "world 25",
+    World26 => SDLK_WORLD_26, "world 26",
+    World27 => SDLK_WORLD_27, "world 27",
+    World28 => SDLK_WORLD_28, "world 28",
+    World29 => SDLK_WORLD_29, "world 29",
+    World30 => SDLK_WORLD_30, "world 30",
+    World31 => SDLK_WORLD_31, "world 31",
+    World32 => SDLK_WORLD_32, "world 32",
+    World33 => SDLK_WORLD_33, "world 33",
+    World34 => SDLK_WORLD_34, "world 34",
+    World35 => SDLK_WORLD_35, "world 35",
+    World36 => SDLK_WORLD_36, "world 36",
+    World37 => SDLK_WORLD_37, "world 37",
+    World38 => SDLK_WORLD_38, "world 38",
+    World39 => SDLK_WORLD_39, "world 39",
+    World40 => SDLK_WORLD_40, "world 40",
+    World41 => SDLK_WORLD_41, "world 41",
+    World42 => SDLK_WORLD_42, "world 42",
+    World43 => SDLK_WORLD_43, "world 43",
+    World44 => SDLK_WORLD_44, "world 44",
+    World45 => SDLK_WORLD_45, "world 45",
+    World46 => SDLK_WORLD_46, "world 46",
+    World47 => SDLK_WORLD_47, "world 47",
+    World48 => SDLK_WORLD_48, "world 48",
+    World49 => SDLK_WORLD_49, "world 49",
+    World50 => SDLK_WORLD_50, "world 50",
+    World51 => SDLK_WORLD_51, "world 51",
+    World52 => SDLK_WORLD_52, "world 52",
+    World53 => SDLK_WORLD_53, "world 53",
+    World54 => SDLK_WORLD_54, "world 54",
+    World55 => SDLK_WORLD_55, "world 55",
+    World56 => SDLK_WORLD_56, "world 56",
+    World57 => SDLK_WORLD_57, "world 57",
+    World58 => SDLK_WORLD_58, "world 58",
+    World59 => SDLK_WORLD_59, "world 59",
+    World60 => SDLK_WORLD_60, "world 60",
+    World61 => SDLK_WORLD_61, "world 61",
+    World62 => SDLK_WORLD_62, "world 62",
+    World63 => SDLK_WORLD_63, "world 63",
+    World64 => SDLK_WORLD_64, "world 64",
+    World65 => SDLK_WORLD_65, "world 65",
+    World66 => SDLK_WORLD_66, "world 66",
+    World67 => SDLK_WORLD_67, "world 67",
+    World68 => SDLK_WORLD_68, "world 68",
+    World69 => SDLK_WORLD_69, "world 69",
+    World70 => SDLK_WORLD_70, "world 70",
+    World71 => SDLK_WORLD_71, "world 71",
+    World72 => SDLK_WORLD_72, "world 72",
+    World73 => SDLK_WORLD_73, "world 73",
+    World74 => SDLK_WORLD_74, "world 74",
+    World75 => SDLK_WORLD_75, "world 75",
+    World76 => SDLK_WORLD_76, "world 76",
+    World77 => SDLK_WORLD_77, "world 77",
+    World78 => SDLK_WORLD_78, "world 78",
+    World79 => SDLK_WORLD_79, "world 79",
+    World80 => SDLK_WORLD_80, "world 80",
+    World81 => SDLK_WORLD_81, "world 81",
+    World82 => SDLK_WORLD_82, "world 82",
+    World83 => SDLK_WORLD_83, "world 83",
+    World84 => SDLK_WORLD_84, "world 84",
+    World85 => SDLK_WORLD_85, "world 85",
+    World86 => SDLK_WORLD_86, "world 86",
+    World87 => SDLK_WORLD_87, "world 87",
+    World88 => SDLK_WORLD_88, "world 88",
+    World89 => SDLK_WORLD_89, "world 89",
+    World90 => SDLK_WORLD_90, "world 90",
+    World91 => SDLK_WORLD_91, "world 91",
+    World92 => SDLK_WORLD_92, "world 92",
+    World93 => SDLK_WORLD_93, "world 93",
+    World94 => SDLK_WORLD_94, "world 94",
+    World95 => SDLK_WORLD_95, "world 95",
+    Kp0 => SDLK_KP0, "[0]",
+    Kp1 => SDLK_KP1, "[1]",
+    Kp2 => SDLK_KP2, "[2]",
+    Kp3 => SDLK_KP3, "[3]",
+    Kp4 => SDLK_KP4, "[4]",
+    Kp5 => SDLK_KP5, "[5]",
+    Kp6 => SDLK_KP6, "[6]",
+    Kp7 => SDLK_KP7, "[7]",
+    Kp8 => SDLK_KP8, "[8]",
+    Kp9 => SDLK_KP9, "[9]",
+    KpPeriod => SDLK_KP_PERIOD, "[.]",
+    KpDivide => SDLK_KP_DIVIDE, "[/]",
+    KpMultiply => SDLK_KP_MULTIPLY, "[*]",
+    KpMinus => SDLK_KP_MINUS, "[-]",
+    KpPlus => SDLK_KP_PLUS, "[+]",
+    KpEnter => SDLK_KP_ENTER, "enter",
+    KpEquals => SDLK_KP_EQUALS, "equals",
+    Up => SDLK_UP, "up",
+    Down => SDLK_DOWN, "down",
+    Right => SDLK_RIGHT, "right",
+    Left => SDLK_LEFT, "left",
+    Insert => SDLK_INSERT, "insert",
+    Home => SDLK_HOME, "home",
+    End => SDLK_END, "end",
+    PageUp => SDLK_PAGEUP, "page up",
+    PageDown => SDLK_PAGEDOWN, "page down",
+    F1 => SDLK_F1, "f1",
+    F2 => SDLK_F2, "f2",
+    F3 => SDLK_F3, "f3",
+    F4 => SDLK_F4, "f4",
+    F5 => SDLK_F5, "f5",
+    F6 => SDLK_F6, "f6",
+    F7 => SDLK_F7, "f7",
+    F8 => SDLK_F8, "f8",
+    F9 => SDLK_F9, "f9",
+    F10 => SDLK_F10, "f10",
+    F11 => SDLK_F11, "f11",
+    F12 => SDLK_F12, "f12",
+    F13 => SDLK_F13, "f13",
+    F14 => SDLK_F14, "f14",
+    F15 => SDLK_F15, "f15",
+    NumLock => SDLK_NUMLOCK, "numlock",
+    CapsLock => SDLK_CAPSLOCK, "caps lock",
+    ScrollLock => SDLK_SCROLLOCK, "scroll lock",
+    RShift => SDLK_RSHIFT, "right shift",
+    LShift => SDLK_LSHIFT, "left shift",
+    RCtrl => SDLK_RCTRL, "right ctrl",
+    LCtrl => SDLK_LCTRL, "left ctrl",
+    RAlt => SDLK_RALT, "right alt",
+    LAlt => SDLK_LALT, "left alt",
+    RMeta => SDLK_RMETA, "right meta",
+    LMeta => SDLK_LMETA, "left meta",
+    LSuper => SDLK_LSUPER, "left super",
+    RSuper => SDLK_RSUPER, "right super",
+    Mode => SDLK_MODE, "alt gr",
+    Compose => SDLK_COMPOSE, "compose",
+    Help => SDLK_HELP, "help",
+    Print => SDLK_PRINT, "print screen",
+    SysReq => SDLK_SYSREQ, "sys req",
+    Break => SDLK_BREAK, "break",
+    Menu => SDLK_MENU, "menu",
+    Power => SDLK_POWER, "power",
+    Euro => SDLK_EURO, "euro",
+    Undo => SDLK_UNDO, "undo",
+}
+
+bitflags! {
+    /// The modifier keys and locks held down, matching `SDLMod`.
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Mod: u32 {
+        const LSHIFT = sys::SDL_KeyMod::KMOD_LSHIFT as u32;
+        const RSHIFT = sys::SDL_KeyMod::KMOD_RSHIFT as u32;
+        const LCTRL = sys::SDL_KeyMod::KMOD_LCTRL as u32;
+        const RCTRL = sys::SDL_KeyMod::KMOD_RCTRL as u32;
+        const LALT = sys::SDL_KeyMod::KMOD_LALT as u32;
+        const RALT = sys::SDL_KeyMod::KMOD_RALT as u32;
+        const LMETA = sys::SDL_KeyMod::KMOD_LMETA as u32;
+        const RMETA = sys::SDL_KeyMod::KMOD_RMETA as u32;
+        const NUM = sys::SDL_KeyMod::KMOD_NUM as u32;
+        const CAPS = sys::SDL_KeyMod::KMOD_CAPS as u32;
+        const MODE = sys::SDL_KeyMod::KMOD_MODE as u32;
+
+        // These are macros rather than part of the enum in SDL_keysym.h.
+        const SHIFT = Self::LSHIFT.bits() | Self::RSHIFT.bits();
+        const CTRL = Self::LCTRL.bits() | Self::RCTRL.bits();
+        const ALT = Self::LALT.bits() | Self::RALT.bits();
+        const META = Self::LMETA.bits() | Self::RMETA.bits();
+    }
+}
+
+/// Another name for [`Mod`], to match [`Key`].
+pub type KeyMod = Mod;
+
+/// A key press or release, wrapping `SDL_keysym`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keysym {
+    pub key: Key,
+    /// The hardware specific scancode.
+    pub scancode: u8,
+    /// The character the key press produced. This is only filled in while
+    /// unicode translation is enabled, and never for releases.
+    pub unicode: Option<char>,
+    pub modifiers: Mod,
+}
+
+impl Keysym {
+    pub(crate) fn from_raw(raw: &sys::SDL_keysym) -> Keysym {
+        // SDL hands out combinations of SDLMod flags, and can hand out keys
+        // it has no name for, neither of which are valid values of the
+        // generated enums. They're read as plain integers instead.
+        let (sym, modifiers) = unsafe {
+            (
+                ptr::addr_of!(raw.sym).cast::<u32>().read(),
+                ptr::addr_of!(raw.mod_).cast::<u32>().read(),
+            )
+        };
+
+        Keysym {
+            key: Key::from_raw(sym),
+            scancode: raw.scancode,
+            unicode: match raw.unicode {
+                0 => None,
+                c => char::from_u32(c as u32),
+            },
+            modifiers: Mod::from_bits_retain(modifiers),
+        }
+    }
+
+    pub(crate) fn raw(&self) -> sys::SDL_keysym {
+        let mut raw: sys::SDL_keysym = unsafe { mem::zeroed() };
+        raw.scancode = self.scancode;
+        raw.sym = self.key.raw();
+        raw.unicode = self.unicode.map_or(0, |c| c as u16);
+        // As in `from_raw`, combinations of modifiers aren't valid values of
+        // the enum, so they're written as a plain integer.
+        unsafe {
+            ptr::addr_of_mut!(raw.mod_)
+                .cast::<u32>()
+                .write(self.modifiers.bits())
+        };
+        raw
+    }
+}
+
+impl VideoSubsystem {
+    /// Turns translation of key presses into characters on or off, and
+    /// returns whether it was on before. This fills in [`Keysym::unicode`],
+    /// and is off by default since it has some overhead.
+    pub fn enable_unicode(&self, enabled: bool) -> bool {
+        unsafe { sys::SDL_EnableUNICODE(enabled as c_int) != 0 }
+    }
+}
+
+/// What a key press did to a [`TextInput`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum TextAction {
+    /// The text changed.
+    Edited,
+    /// The cursor moved, without changing the text.
+    CursorMoved,
+    /// Enter was pressed.
+    Submitted,
+    /// Escape was pressed.
+    Cancelled,
+}
+
+/// A single line of text being typed in, such as a name for a high score
+/// table.
+///
+/// Feed it key presses with [`handle_event`](TextInput::handle_event) while
+/// unicode translation is turned on with
+/// [`VideoSubsystem::enable_unicode`], then draw [`text`](TextInput::text)
+/// with the cursor after [`text_before_cursor`](TextInput::text_before_cursor).
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct TextInput {
+    text: String,
+    // A byte offset into `text`, always on a char boundary.
+    cursor: usize,
+    max_len: Option<usize>,
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput::default()
+    }
+
+    /// Limits the text to `max_len` characters.
+    pub fn with_max_len(max_len: usize) -> TextInput {
+        TextInput {
+            max_len: Some(max_len),
+            ..TextInput::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn text_before_cursor(&self) -> &str {
+        &self.text[..self.cursor]
+    }
+
+    /// The position of the cursor, in characters from the start.
+    pub fn cursor(&self) -> usize {
+        self.text_before_cursor().chars().count()
+    }
+
+    /// Replaces the text, truncating it to the maximum length, and moves the
+    /// cursor to the end.
+    pub fn set_text(&mut self, text: &str) {
+        let len = self.max_len.unwrap_or(usize::MAX);
+        self.text = text.chars().take(len).collect();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Handles a key press. Anything other than a key press is ignored.
+    pub fn handle_event<U>(&mut self, event: &Event<U>) -> Option<TextAction> {
+        match event {
+            Event::Keyboard(KeyboardEvent::KeyDown(keysym)) => self.handle_key(keysym),
+            _ => None,
+        }
+    }
+
+    /// Handles a key press, returning what it did, or `None` if the key
+    /// isn't one a text input uses.
+    pub fn handle_key(&mut self, keysym: &Keysym) -> Option<TextAction> {
+        match keysym.key {
+            Key::Return | Key::KpEnter => Some(TextAction::Submitted),
+            Key::Escape => Some(TextAction::Cancelled),
+            Key::Backspace => {
+                let start = self.prev_boundary()?;
+                self.text.drain(start..self.cursor);
+                self.cursor = start;
+                Some(TextAction::Edited)
+            }
+            Key::Delete => {
+                let end = self.next_boundary()?;
+                self.text.drain(self.cursor..end);
+                Some(TextAction::Edited)
+            }
+            Key::Left => self.move_to(self.prev_boundary()?),
+            Key::Right => self.move_to(self.next_boundary()?),
+            Key::Home => self.move_to(0),
+            Key::End => self.move_to(self.text.len()),
+            _ => self.insert(keysym.unicode?),
+        }
+    }
+
+    fn insert(&mut self, c: char) -> Option<TextAction> {
+        let full = self
+            .max_len
+            .is_some_and(|len| self.text.chars().count() >= len);
+        if c.is_control() || full {
+            return None;
+        }
+
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        Some(TextAction::Edited)
+    }
+
+    fn move_to(&mut self, cursor: usize) -> Option<TextAction> {
+        if cursor == self.cursor {
+            return None;
+        }
+        self.cursor = cursor;
+        Some(TextAction::CursorMoved)
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        let (offset, _) = self.text[..self.cursor].char_indices().next_back()?;
+        Some(offset)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        let c = self.text[self.cursor..].chars().next()?;
+        Some(self.cursor + c.len_utf8())
+    }
+}
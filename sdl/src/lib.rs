@@ -3,17 +3,41 @@ pub use sdl_sys as sys;
 mod sdl;
 pub use crate::sdl::*;
 
+mod rect;
+pub use crate::rect::*;
+
+pub mod app;
+pub use crate::app::{run_with, ControlFlow, LoopEvent};
+pub mod blit;
+pub mod input;
+pub mod keyboard;
+pub mod mouse;
+pub mod palette;
+pub mod pool;
+pub mod syswm;
+pub mod viewport;
+
 // The 7 primary SDL subsystems
+#[cfg(feature = "audio")]
 pub mod audio;
+#[cfg(feature = "cdrom")]
 pub mod cdrom;
 pub mod event;
+#[cfg(feature = "joystick")]
 pub mod joystick;
+#[cfg(feature = "joystick")]
+pub mod gamepad;
+#[cfg(all(feature = "evdev", target_os = "linux"))]
+pub mod rumble;
 pub mod timer;
 pub mod video;
 
 #[cfg(feature = "gfx")]
 pub mod gfx;
 
+#[cfg(any(feature = "sdl2", feature = "image-crate"))]
+pub mod interop;
+
 #[cfg(feature = "image")]
 pub mod image;
 
@@ -22,3 +46,12 @@ pub mod mixer;
 
 #[cfg(feature = "ttf")]
 pub mod ttf;
+
+#[cfg(feature = "assets")]
+pub mod assets;
+
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+
+#[cfg(feature = "testing")]
+pub mod testing;
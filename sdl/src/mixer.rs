@@ -0,0 +1,526 @@
+//! Sound effects and music, with SDL_mixer.
+
+use std::collections::hash_map::RandomState;
+use std::ffi::c_int;
+use std::hash::{BuildHasher, Hasher};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::audio::{AudioFormat, AudioSpec, SpecPolicy};
+use crate::sdl;
+use crate::sys;
+use crate::sys::mixer;
+
+/// The open audio device, as returned by [`open_audio`]. Audio is closed
+/// again once this and every [`Chunk`] and [`Music`] are dropped.
+#[derive(Debug)]
+pub struct Mixer {
+    desired: AudioSpec,
+    obtained: AudioSpec,
+    _not_send: sdl::NotSend,
+}
+
+impl Mixer {
+    /// The spec the device was actually opened with.
+    pub fn spec(&self) -> AudioSpec {
+        self.obtained
+    }
+
+    /// The spec which was passed to [`open_audio`].
+    pub fn desired_spec(&self) -> AudioSpec {
+        self.desired
+    }
+
+    /// Whether the device was opened with exactly the spec which was asked
+    /// for.
+    pub fn has_desired_spec(&self) -> bool {
+        self.desired == self.obtained
+    }
+}
+
+/// Opens the audio device for mixing, initializing the audio subsystem if it
+/// isn't already.
+///
+/// # Arguments
+///
+/// * `desired` - The spec to ask for. 22050 or 44100 Hz with
+///   [`AudioFormat::S16_SYS`] samples is usually a safe choice.
+/// * `chunk_size` - The number of bytes used per output sample. Smaller
+///   values lower latency, but can cause skips on slow machines.
+/// * `policy` - What to do if the device can't be opened with `desired`.
+///
+pub fn open_audio(
+    desired: AudioSpec,
+    chunk_size: i32,
+    policy: SpecPolicy,
+) -> Result<Mixer, OpenAudioError> {
+    let ret = unsafe {
+        mixer::Mix_OpenAudio(
+            desired.frequency as c_int,
+            desired.format.raw(),
+            desired.channels as c_int,
+            chunk_size as c_int,
+        )
+    };
+    if ret != 0 {
+        return Err(sdl::get_mixer_error("Mix_OpenAudio").into());
+    }
+    OPENED.fetch_add(1, Ordering::SeqCst);
+    USERS.fetch_add(1, Ordering::SeqCst);
+
+    // Building the Mixer first means it closes audio again on any error.
+    let mut mixer = Mixer {
+        desired,
+        obtained: desired,
+        _not_send: PhantomData,
+    };
+    mixer.obtained = query_spec()?;
+    if policy == SpecPolicy::Exact && !mixer.has_desired_spec() {
+        return Err(OpenAudioError::SpecMismatch {
+            desired,
+            obtained: mixer.obtained,
+        });
+    }
+    Ok(mixer)
+}
+
+fn query_spec() -> Result<AudioSpec, OpenAudioError> {
+    let (mut frequency, mut format, mut channels) = (0, 0, 0);
+    if unsafe { mixer::Mix_QuerySpec(&mut frequency, &mut format, &mut channels) } == 0 {
+        return Err(sdl::get_mixer_error("Mix_QuerySpec").into());
+    }
+    let format = AudioFormat::from_raw(format).ok_or(OpenAudioError::UnknownFormat(format))?;
+    Ok(AudioSpec::new(frequency, format, channels as u8))
+}
+
+// The number of `Mixer`s, `Chunk`s and `Music`s alive, and of the calls to
+// `Mix_OpenAudio` which haven't been closed yet. Audio is only closed when
+// the last of them is dropped, so nothing is freed after `Mix_CloseAudio`.
+static USERS: AtomicUsize = AtomicUsize::new(0);
+static OPENED: AtomicUsize = AtomicUsize::new(0);
+
+fn release() {
+    if USERS.fetch_sub(1, Ordering::SeqCst) == 1 {
+        for _ in 0..OPENED.swap(0, Ordering::SeqCst) {
+            unsafe { mixer::Mix_CloseAudio() }
+        }
+    }
+}
+
+impl Drop for Mixer {
+    fn drop(&mut self) {
+        release();
+    }
+}
+
+/// A sound effect, fully decoded into memory.
+#[derive(Debug)]
+pub struct Chunk {
+    raw: *mut mixer::Mix_Chunk,
+}
+
+impl Chunk {
+    /// Loads a sound from a WAV, AIFF, VOC or, depending on how SDL_mixer
+    /// was built, OGG file. The audio device has to be open, since the sound
+    /// is converted to its format.
+    pub fn load<P: AsRef<Path>>(path: P) -> sdl::Result<Chunk> {
+        let path = sdl::path_to_cstring(path.as_ref())?;
+        let raw = unsafe {
+            let rw = sys::SDL_RWFromFile(path.as_ptr(), c"rb".as_ptr());
+            if rw.is_null() {
+                return Err(sdl::get_error());
+            }
+            mixer::Mix_LoadWAV_RW(rw, 1)
+        };
+
+        if raw.is_null() {
+            Err(sdl::get_mixer_error("Mix_LoadWAV_RW"))
+        } else {
+            USERS.fetch_add(1, Ordering::SeqCst);
+            Ok(Chunk { raw })
+        }
+    }
+
+    /// Like [`load`](Chunk::load), for a file which has already been read
+    /// into memory.
+    pub fn load_from_memory(data: &[u8]) -> sdl::Result<Chunk> {
+        let rw = sdl::rw_from_slice(data)?;
+        let raw = unsafe { mixer::Mix_LoadWAV_RW(rw, 1) };
+
+        if raw.is_null() {
+            Err(sdl::get_mixer_error("Mix_LoadWAV_RW"))
+        } else {
+            USERS.fetch_add(1, Ordering::SeqCst);
+            Ok(Chunk { raw })
+        }
+    }
+
+    pub fn raw(&self) -> *mut mixer::Mix_Chunk {
+        self.raw
+    }
+
+    /// Returns the decoded samples, which are in the format of `mixer` since
+    /// chunks are converted as they're loaded. For more than one channel the
+    /// samples are interleaved, so for stereo left and right alternate.
+    pub fn samples<'a>(&'a self, mixer: &Mixer) -> Samples<'a> {
+        let bytes = unsafe {
+            let chunk = &*self.raw;
+            if chunk.abuf.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(chunk.abuf as *const u8, chunk.alen as usize)
+            }
+        };
+
+        let format = mixer.spec().format;
+        match format {
+            AudioFormat::U8 => Samples::U8(bytes),
+            AudioFormat::S8 => Samples::S8(unsafe {
+                slice::from_raw_parts(bytes.as_ptr() as *const i8, bytes.len())
+            }),
+            _ if format == AudioFormat::S16_SYS => match unsafe { bytes.align_to::<i16>() } {
+                ([], samples, _) => Samples::S16(samples),
+                _ => Samples::Bytes(format, bytes),
+            },
+            _ if format == AudioFormat::U16_SYS => match unsafe { bytes.align_to::<u16>() } {
+                ([], samples, _) => Samples::U16(samples),
+                _ => Samples::Bytes(format, bytes),
+            },
+            _ => Samples::Bytes(format, bytes),
+        }
+    }
+
+    /// Plays the sound on `channel`, or the first free channel if `None`,
+    /// and returns the channel used. It's played `loops + 1` times, or
+    /// forever if `loops` is -1.
+    pub fn play(&self, channel: Option<i32>, loops: i32) -> sdl::Result<i32> {
+        sdl::check_callbacks()?;
+        let channel = channel.unwrap_or(-1);
+        let ret = unsafe { mixer::Mix_PlayChannelTimed(channel, self.raw, loops, -1) };
+        if ret < 0 {
+            Err(sdl::get_mixer_error("Mix_PlayChannelTimed"))
+        } else {
+            Ok(ret)
+        }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe { mixer::Mix_FreeChunk(self.raw) }
+        release();
+    }
+}
+
+/// The samples of a [`Chunk`], as returned by [`Chunk::samples`].
+#[derive(Copy, Clone, Debug)]
+pub enum Samples<'a> {
+    U8(&'a [u8]),
+    S8(&'a [i8]),
+    U16(&'a [u16]),
+    S16(&'a [i16]),
+    /// 16-bit samples which can't be read in place, because they're in the
+    /// other byte order to this machine's.
+    Bytes(AudioFormat, &'a [u8]),
+}
+
+impl Samples<'_> {
+    /// The number of samples, counting each channel separately.
+    pub fn len(&self) -> usize {
+        match *self {
+            Samples::U8(samples) => samples.len(),
+            Samples::S8(samples) => samples.len(),
+            Samples::U16(samples) => samples.len(),
+            Samples::S16(samples) => samples.len(),
+            Samples::Bytes(format, bytes) => bytes.len() / format.bytes_per_sample(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A piece of music, which is decoded as it plays.
+#[derive(Debug)]
+pub struct Music {
+    raw: *mut mixer::Mix_Music,
+}
+
+impl Music {
+    /// Loads music from a file. Which formats are supported depends on how
+    /// SDL_mixer was built.
+    pub fn load<P: AsRef<Path>>(path: P) -> sdl::Result<Music> {
+        let path = sdl::path_to_cstring(path.as_ref())?;
+        let raw = unsafe { mixer::Mix_LoadMUS(path.as_ptr()) };
+
+        if raw.is_null() {
+            Err(sdl::get_mixer_error("Mix_LoadMUS"))
+        } else {
+            USERS.fetch_add(1, Ordering::SeqCst);
+            Ok(Music { raw })
+        }
+    }
+
+    pub fn raw(&self) -> *mut mixer::Mix_Music {
+        self.raw
+    }
+
+    /// Starts playing the music, stopping whatever was playing before. It's
+    /// played `loops` times, or forever if `loops` is -1.
+    pub fn play(&self, loops: i32) -> sdl::Result<()> {
+        sdl::check_callbacks()?;
+        if unsafe { mixer::Mix_PlayMusic(self.raw, loops) } != 0 {
+            Err(sdl::get_mixer_error("Mix_PlayMusic"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Like [`play`](Music::play), but fades the music in over `fade`.
+    pub fn fade_in(&self, loops: i32, fade: Duration) -> sdl::Result<()> {
+        sdl::check_callbacks()?;
+        if unsafe { mixer::Mix_FadeInMusic(self.raw, loops, to_ms(fade)) } != 0 {
+            Err(sdl::get_mixer_error("Mix_FadeInMusic"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for Music {
+    fn drop(&mut self) {
+        unsafe { mixer::Mix_FreeMusic(self.raw) }
+        release();
+    }
+}
+
+fn to_ms(duration: Duration) -> c_int {
+    duration.as_millis().min(c_int::MAX as u128) as c_int
+}
+
+// Set by the music finished hook. SDL_mixer calls the hook from the audio
+// thread, where calling back into SDL_mixer can deadlock, so all the hook does
+// is set this for Playlist::update to pick up.
+static MUSIC_FINISHED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn music_finished() {
+    MUSIC_FINISHED.store(true, Ordering::SeqCst);
+}
+
+/// Plays a list of [`Music`] one after the other.
+///
+/// SDL_mixer only lets the playlist know a track has finished from the audio
+/// thread, so nothing happens until [`update`](Playlist::update) is called,
+/// which should be done once a frame. The playlist takes over SDL_mixer's
+/// music finished hook, so only one should be used at a time.
+#[derive(Debug)]
+pub struct Playlist {
+    tracks: Vec<Music>,
+    // Indexes into tracks, in the order they're played.
+    order: Vec<usize>,
+    position: usize,
+    playing: bool,
+    shuffle: bool,
+    looping: bool,
+    crossfade: Duration,
+    rng: u64,
+}
+
+impl Playlist {
+    /// Creates a playlist which plays `tracks` in order, once through.
+    pub fn new(tracks: Vec<Music>) -> Playlist {
+        // RandomState is seeded randomly, which saves needing a dependency
+        // just to pick a shuffle seed.
+        let seed = RandomState::new().build_hasher().finish();
+        Playlist {
+            order: (0..tracks.len()).collect(),
+            tracks,
+            position: 0,
+            playing: false,
+            shuffle: false,
+            looping: false,
+            crossfade: Duration::ZERO,
+            rng: seed | 1,
+        }
+    }
+
+    pub fn tracks(&self) -> &[Music] {
+        &self.tracks
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Whether the tracks are played in a random order, which is reshuffled
+    /// each time the playlist starts over. This takes effect the next time
+    /// the playlist is started or loops.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+    }
+
+    /// Whether to start over after the last track, rather than stopping.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Fades each track out over `crossfade` when skipping, and in over
+    /// `crossfade` when it starts. SDL_mixer only plays one piece of music at
+    /// a time, so the tracks don't overlap. Zero, the default, switches
+    /// tracks immediately.
+    pub fn set_crossfade(&mut self, crossfade: Duration) {
+        self.crossfade = crossfade;
+    }
+
+    /// The index in [`tracks`](Playlist::tracks) of the track being played,
+    /// if any.
+    pub fn current(&self) -> Option<usize> {
+        if self.playing {
+            Some(self.order[self.position])
+        } else {
+            None
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Starts playing from the beginning of the playlist, stopping whatever
+    /// music was playing before.
+    pub fn play(&mut self) -> sdl::Result<()> {
+        sdl::check_callbacks()?;
+        if self.tracks.is_empty() {
+            return Ok(());
+        }
+
+        self.halt();
+        if self.shuffle {
+            self.shuffle_order();
+        } else {
+            self.order = (0..self.tracks.len()).collect();
+        }
+        self.position = 0;
+        unsafe { mixer::Mix_HookMusicFinished(Some(music_finished)) };
+        self.start_current()
+    }
+
+    /// Stops playing.
+    pub fn stop(&mut self) {
+        self.halt();
+        unsafe { mixer::Mix_HookMusicFinished(None) };
+    }
+
+    /// Moves on to the next track. With a crossfade set, the current track
+    /// fades out first and the next one starts in a later
+    /// [`update`](Playlist::update).
+    pub fn skip(&mut self) -> sdl::Result<()> {
+        sdl::check_callbacks()?;
+        if !self.playing {
+            return Ok(());
+        }
+
+        // The hook is called once the fade is done. If the music can't be
+        // faded, it's halted instead.
+        if self.crossfade > Duration::ZERO
+            && unsafe { mixer::Mix_FadeOutMusic(to_ms(self.crossfade)) } != 0
+        {
+            return Ok(());
+        }
+
+        // Halting calls the hook, but this is moving on right away.
+        unsafe { mixer::Mix_HaltMusic() };
+        MUSIC_FINISHED.store(false, Ordering::SeqCst);
+        self.advance()
+    }
+
+    /// Starts the next track if the current one has finished. Call this once
+    /// a frame.
+    pub fn update(&mut self) -> sdl::Result<()> {
+        sdl::check_callbacks()?;
+        if self.playing && MUSIC_FINISHED.swap(false, Ordering::SeqCst) {
+            self.advance()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn advance(&mut self) -> sdl::Result<()> {
+        self.position += 1;
+        if self.position >= self.order.len() {
+            if !self.looping {
+                self.stop();
+                return Ok(());
+            }
+            if self.shuffle {
+                let last = self.order[self.order.len() - 1];
+                self.shuffle_order();
+                // Don't play the same track twice in a row across the loop.
+                if self.order.len() > 1 && self.order[0] == last {
+                    self.order.swap(0, 1);
+                }
+            }
+            self.position = 0;
+        }
+        self.start_current()
+    }
+
+    fn start_current(&mut self) -> sdl::Result<()> {
+        let track = &self.tracks[self.order[self.position]];
+        let result = if self.crossfade > Duration::ZERO {
+            track.fade_in(1, self.crossfade)
+        } else {
+            track.play(1)
+        };
+        self.playing = result.is_ok();
+        result
+    }
+
+    fn halt(&mut self) {
+        if self.playing {
+            unsafe { mixer::Mix_HaltMusic() };
+            self.playing = false;
+        }
+        MUSIC_FINISHED.store(false, Ordering::SeqCst);
+    }
+
+    fn shuffle_order(&mut self) {
+        // A Fisher-Yates shuffle, with xorshift for the random numbers.
+        self.order = (0..self.tracks.len()).collect();
+        for i in (1..self.order.len()).rev() {
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            let j = (self.rng % (i as u64 + 1)) as usize;
+            self.order.swap(i, j);
+        }
+    }
+}
+
+impl Drop for Playlist {
+    fn drop(&mut self) {
+        // Stop before the tracks are freed, so nothing is left playing them.
+        self.stop();
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OpenAudioError {
+    #[error("audio opened as {:?} instead of {:?}", .obtained, .desired)]
+    SpecMismatch {
+        desired: AudioSpec,
+        obtained: AudioSpec,
+    },
+    #[error("audio opened in unknown format {:#x}", .0)]
+    UnknownFormat(u16),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
@@ -0,0 +1,117 @@
+//! The mouse cursor, and polling the mouse's position and buttons.
+
+use std::ffi::c_int;
+
+use bitflags::bitflags;
+
+use crate::event::Button;
+use crate::sys;
+use crate::VideoSubsystem;
+
+impl VideoSubsystem {
+    /// Shows or hides the mouse cursor, and returns whether it was showing
+    /// before.
+    pub fn show_cursor(&self, visible: bool) -> bool {
+        unsafe { sys::SDL_ShowCursor(visible as c_int) == 1 }
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        unsafe { sys::SDL_ShowCursor(sys::SDL_QUERY as c_int) == 1 }
+    }
+
+    /// Moves the mouse cursor to `(x, y)` in the window. This sends a
+    /// `MouseMotion` event like any other movement, which also shows up in
+    /// the next [`MouseState`]'s relative motion.
+    pub fn warp_mouse(&self, x: u16, y: u16) {
+        unsafe { sys::SDL_WarpMouse(x, y) }
+    }
+
+    /// Hides the mouse cursor until the returned guard is dropped, which
+    /// puts it back the way it was. Guards can be nested.
+    pub fn hide_cursor(&self) -> HiddenCursor<'_> {
+        HiddenCursor {
+            video: self,
+            was_visible: self.show_cursor(false),
+        }
+    }
+}
+
+/// Keeps the mouse cursor hidden, see [`VideoSubsystem::hide_cursor`].
+#[derive(Debug)]
+#[must_use = "the cursor is shown again as soon as this is dropped"]
+pub struct HiddenCursor<'a> {
+    video: &'a VideoSubsystem,
+    was_visible: bool,
+}
+
+impl Drop for HiddenCursor<'_> {
+    fn drop(&mut self) {
+        self.video.show_cursor(self.was_visible);
+    }
+}
+
+bitflags! {
+    /// The mouse buttons held down, as in SDL's `SDL_BUTTON` masks.
+    #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct MouseButtonState: u8 {
+        // SDL's masks come from the `SDL_BUTTON(X)` macro, which bindgen
+        // can't evaluate, so they're worked out from the button numbers.
+        const LEFT = 1 << (sys::SDL_BUTTON_LEFT - 1);
+        const MIDDLE = 1 << (sys::SDL_BUTTON_MIDDLE - 1);
+        const RIGHT = 1 << (sys::SDL_BUTTON_RIGHT - 1);
+        const WHEEL_UP = 1 << (sys::SDL_BUTTON_WHEELUP - 1);
+        const WHEEL_DOWN = 1 << (sys::SDL_BUTTON_WHEELDOWN - 1);
+        const X1 = 1 << (sys::SDL_BUTTON_X1 - 1);
+        const X2 = 1 << (sys::SDL_BUTTON_X2 - 1);
+    }
+}
+
+impl MouseButtonState {
+    /// Whether `button` is held down. Only the first 8 buttons are tracked,
+    /// so this is always `false` for any others.
+    pub fn is_pressed(self, button: Button) -> bool {
+        match button.raw() {
+            raw @ 1..=8 => self.bits() & (1 << (raw - 1)) != 0,
+            _ => false,
+        }
+    }
+}
+
+/// The mouse's position and buttons, for polling it once a frame rather than
+/// following its events.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MouseState {
+    pub x: i32,
+    pub y: i32,
+    /// How far the mouse has moved since the last `MouseState` was taken.
+    pub xrel: i32,
+    pub yrel: i32,
+    pub buttons: MouseButtonState,
+}
+
+impl MouseState {
+    /// The mouse's state as of the events handled so far.
+    ///
+    /// The relative motion is reset each time, so only one `MouseState`
+    /// should be taken per frame. With the cursor hidden and input grabbed,
+    /// it keeps counting even when the cursor can't move any further.
+    pub fn now() -> MouseState {
+        let (mut x, mut y, mut xrel, mut yrel) = (0, 0, 0, 0);
+        let buttons = unsafe {
+            sys::SDL_GetRelativeMouseState(&mut xrel, &mut yrel);
+            sys::SDL_GetMouseState(&mut x, &mut y)
+        };
+        MouseState {
+            x,
+            y,
+            xrel,
+            yrel,
+            buttons: MouseButtonState::from_bits_retain(buttons),
+        }
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.buttons.is_pressed(button)
+    }
+}
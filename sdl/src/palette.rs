@@ -0,0 +1,119 @@
+//! Palette cycling, the classic way to animate water, fire and the like on
+//! 8-bit surfaces without redrawing them.
+//!
+//! Register each range of palette entries to rotate with a
+//! [`PaletteCycler`], then [`update`](PaletteCycler::update) it once per frame.
+//! On an 8-bit screen with `SDL_HWPALETTE` the changes show up immediately,
+//! while other 8-bit surfaces have to be blitted again.
+
+use std::time::Duration;
+
+use crate::sdl;
+use crate::video::Surface;
+
+/// A range of palette entries which rotates by one entry every `interval`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PaletteCycle {
+    /// The first entry in the range.
+    pub start: u8,
+    /// The number of entries in the range.
+    pub len: u8,
+    pub interval: Duration,
+    /// By default, colors move towards higher entries, with the last color
+    /// wrapping around to the start of the range.
+    pub reverse: bool,
+}
+
+impl PaletteCycle {
+    pub const fn new(start: u8, len: u8, interval: Duration) -> PaletteCycle {
+        PaletteCycle {
+            start,
+            len,
+            interval,
+            reverse: false,
+        }
+    }
+
+    pub const fn reversed(self) -> PaletteCycle {
+        PaletteCycle {
+            reverse: true,
+            ..self
+        }
+    }
+
+    /// Rotates the range of `palette` by `steps` entries. Entries past the end
+    /// of the palette are left out of the range.
+    pub fn rotate(&self, palette: &mut [sdl::Color], steps: usize) {
+        let start = (self.start as usize).min(palette.len());
+        let end = (start + self.len as usize).min(palette.len());
+        let range = &mut palette[start..end];
+        if range.is_empty() {
+            return;
+        }
+
+        let steps = steps % range.len();
+        if self.reverse {
+            range.rotate_left(steps);
+        } else {
+            range.rotate_right(steps);
+        }
+    }
+}
+
+/// Runs any number of [`PaletteCycle`]s on a surface, each at its own speed.
+#[derive(Clone, Debug, Default)]
+pub struct PaletteCycler {
+    cycles: Vec<(PaletteCycle, Duration)>,
+}
+
+impl PaletteCycler {
+    pub fn new() -> PaletteCycler {
+        PaletteCycler::default()
+    }
+
+    pub fn add(&mut self, cycle: PaletteCycle) -> &mut PaletteCycler {
+        self.cycles.push((cycle, Duration::ZERO));
+        self
+    }
+
+    pub fn clear(&mut self) {
+        self.cycles.clear();
+    }
+
+    /// Advances every cycle by `dt` and applies any steps which are due to
+    /// the palette of `surface`. Surfaces without a palette are left alone.
+    ///
+    /// Returns whether the palette changed.
+    pub fn update(&mut self, dt: Duration, surface: &mut Surface) -> bool {
+        let mut palette = match surface.palette() {
+            Some(palette) => palette,
+            None => return false,
+        };
+
+        let mut changed = false;
+        for (cycle, elapsed) in &mut self.cycles {
+            if cycle.interval.is_zero() {
+                continue;
+            }
+
+            *elapsed += dt;
+            let interval = cycle.interval.as_nanos();
+            let steps = elapsed.as_nanos() / interval;
+            if steps == 0 {
+                continue;
+            }
+            *elapsed = Duration::from_nanos((elapsed.as_nanos() % interval) as u64);
+
+            // Only the remainder matters, since a whole rotation is a no-op.
+            let len = (cycle.len as u128).max(1);
+            cycle.rotate(&mut palette, (steps % len) as usize);
+            changed = true;
+        }
+
+        if changed {
+            surface.set_colors(0, &palette);
+        }
+        changed
+    }
+}
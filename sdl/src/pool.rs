@@ -0,0 +1,146 @@
+//! Recycling temporary surfaces, for effects which need scratch space every
+//! frame.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::sdl;
+use crate::video::{PixelFormatSpec, Surface};
+
+/// Hands out temporary surfaces, and keeps them when they're dropped to hand
+/// out again, rather than creating and freeing a surface each time.
+///
+/// Surfaces are matched on their size and format. A recycled surface keeps
+/// the pixels, alpha and color key it was last left with, only its clip
+/// rectangle is reset, so fill it before use if that matters.
+pub struct SurfacePool {
+    idle: RefCell<Vec<(PixelFormatSpec, Surface)>>,
+    max_idle: usize,
+}
+
+impl SurfacePool {
+    /// A pool which keeps up to 16 surfaces that aren't in use.
+    pub fn new() -> SurfacePool {
+        SurfacePool::with_max_idle(16)
+    }
+
+    /// A pool which keeps up to `max_idle` surfaces that aren't in use,
+    /// freeing the longest unused once there are more.
+    pub fn with_max_idle(max_idle: usize) -> SurfacePool {
+        SurfacePool {
+            idle: RefCell::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// Returns a surface of the given size and format, reusing one if the
+    /// pool has one, otherwise creating it. It goes back in the pool when
+    /// dropped.
+    pub fn take(
+        &self,
+        width: u32,
+        height: u32,
+        format: PixelFormatSpec,
+    ) -> sdl::Result<PooledSurface<'_>> {
+        let recycled = {
+            let mut idle = self.idle.borrow_mut();
+            idle.iter()
+                .rposition(|(spec, surface)| {
+                    *spec == format && surface.width() == width && surface.height() == height
+                })
+                .map(|index| idle.remove(index).1)
+        };
+
+        let surface = match recycled {
+            Some(mut surface) => {
+                surface.set_clip_rect(None);
+                surface
+            }
+            None => Surface::with_format(width, height, format)?,
+        };
+
+        Ok(PooledSurface {
+            pool: self,
+            format,
+            surface: Some(surface),
+        })
+    }
+
+    /// The number of surfaces waiting to be reused.
+    pub fn idle_len(&self) -> usize {
+        self.idle.borrow().len()
+    }
+
+    /// Frees every surface that isn't in use, such as after a level where
+    /// the effects needed unusually large surfaces.
+    pub fn clear(&self) {
+        self.idle.borrow_mut().clear();
+    }
+
+    fn put_back(&self, format: PixelFormatSpec, surface: Surface) {
+        if self.max_idle == 0 {
+            return;
+        }
+
+        let mut idle = self.idle.borrow_mut();
+        if idle.len() >= self.max_idle {
+            idle.remove(0);
+        }
+        idle.push((format, surface));
+    }
+}
+
+impl Default for SurfacePool {
+    fn default() -> SurfacePool {
+        SurfacePool::new()
+    }
+}
+
+impl fmt::Debug for SurfacePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SurfacePool")
+            .field("idle", &self.idle_len())
+            .field("max_idle", &self.max_idle)
+            .finish()
+    }
+}
+
+/// A surface borrowed from a [`SurfacePool`], which goes back to the pool
+/// when dropped. It derefs to [`Surface`].
+#[derive(Debug)]
+pub struct PooledSurface<'a> {
+    pool: &'a SurfacePool,
+    format: PixelFormatSpec,
+    // Only `None` once it's been taken out in `into_inner` or `drop`.
+    surface: Option<Surface>,
+}
+
+impl PooledSurface<'_> {
+    /// Keeps the surface rather than handing it back to the pool.
+    pub fn into_inner(mut self) -> Surface {
+        self.surface.take().unwrap()
+    }
+}
+
+impl Deref for PooledSurface<'_> {
+    type Target = Surface;
+
+    fn deref(&self) -> &Surface {
+        self.surface.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledSurface<'_> {
+    fn deref_mut(&mut self) -> &mut Surface {
+        self.surface.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledSurface<'_> {
+    fn drop(&mut self) {
+        if let Some(surface) = self.surface.take() {
+            self.pool.put_back(self.format, surface);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::sys;
+
+/// A point, using the same 16-bit coordinates as SDL itself.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Point {
+    pub const fn new(x: i16, y: i16) -> Point {
+        Point { x, y }
+    }
+}
+
+impl From<(i16, i16)> for Point {
+    fn from((x, y): (i16, i16)) -> Point {
+        Point::new(x, y)
+    }
+}
+
+/// A rectangle, matching `SDL_Rect`.
+///
+/// A rectangle with a width or height of 0 is empty: it contains no points
+/// and never intersects anything.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub x: i16,
+    pub y: i16,
+    pub w: u16,
+    pub h: u16,
+}
+
+impl Rect {
+    pub const fn new(x: i16, y: i16, w: u16, h: u16) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+
+    // The edges are widened to i32, since x + w can overflow an i16.
+    fn right(&self) -> i32 {
+        self.x as i32 + self.w as i32
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y as i32 + self.h as i32
+    }
+
+    pub fn contains_point<P: Into<Point>>(&self, point: P) -> bool {
+        let point = point.into();
+        point.x >= self.x
+            && point.y >= self.y
+            && (point.x as i32) < self.right()
+            && (point.y as i32) < self.bottom()
+    }
+
+    /// Returns whether `other` lies entirely within this rectangle. Empty
+    /// rectangles are never contained, for consistency with
+    /// [`contains_point`](Rect::contains_point).
+    pub fn contains_rect(&self, other: Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    pub fn has_intersection(&self, other: Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Returns the area covered by both rectangles, or `None` if they don't
+    /// overlap.
+    pub fn intersection(&self, other: Rect) -> Option<Rect> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x as i32 || bottom <= y as i32 {
+            return None;
+        }
+
+        Some(Rect::new(
+            x,
+            y,
+            (right - x as i32) as u16,
+            (bottom - y as i32) as u16,
+        ))
+    }
+
+    /// Returns the smallest rectangle containing both rectangles. Empty
+    /// rectangles are ignored, so the union with an empty rectangle is the
+    /// other one. The size saturates if the result is too large to fit.
+    pub fn union(&self, other: Rect) -> Rect {
+        if self.is_empty() {
+            return other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        let clamp = |len: i32| len.min(u16::MAX as i32) as u16;
+
+        Rect::new(x, y, clamp(right - x as i32), clamp(bottom - y as i32))
+    }
+
+    pub(crate) const fn raw(self) -> sys::SDL_Rect {
+        sys::SDL_Rect {
+            x: self.x,
+            y: self.y,
+            w: self.w,
+            h: self.h,
+        }
+    }
+}
+
+impl From<sys::SDL_Rect> for Rect {
+    fn from(raw: sys::SDL_Rect) -> Rect {
+        Rect::new(raw.x, raw.y, raw.w, raw.h)
+    }
+}
+
+impl From<Rect> for sys::SDL_Rect {
+    fn from(rect: Rect) -> sys::SDL_Rect {
+        rect.raw()
+    }
+}
+
+impl From<(i16, i16, u16, u16)> for Rect {
+    fn from((x, y, w, h): (i16, i16, u16, u16)) -> Rect {
+        Rect::new(x, y, w, h)
+    }
+}
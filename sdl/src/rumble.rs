@@ -0,0 +1,214 @@
+//! Rumble for joysticks on Linux, through the kernel's force feedback
+//! interface.
+//!
+//! SDL 1.2 has no force feedback support at all, so this goes around it: the
+//! joystick's `/dev/input/event*` node is found by its name, and rumble
+//! effects are uploaded to it directly. This only works if the user can
+//! write to the node, which usually means being in the `input` group or
+//! having a udev rule for the device.
+
+use std::ffi::c_int;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+
+use crate::joystick::{self, Joystick};
+
+const EV_FF: u16 = 0x15;
+const FF_RUMBLE: u16 = 0x50;
+const FF_MAX: usize = 0x7f;
+
+const IOC_WRITE: u32 = 1;
+const IOC_READ: u32 = 2;
+
+// The ioctl request numbers from linux/input.h, built as the _IOC macro does.
+const fn ioc(dir: u32, nr: u32, size: usize) -> u32 {
+    (dir << 30) | ((size as u32) << 16) | ((b'E' as u32) << 8) | nr
+}
+
+const fn eviocgname(len: usize) -> u32 {
+    ioc(IOC_READ, 0x06, len)
+}
+
+const fn eviocgbit(ev: u16, len: usize) -> u32 {
+    ioc(IOC_READ, 0x20 + ev as u32, len)
+}
+
+const EVIOCSFF: u32 = ioc(IOC_WRITE, 0x80, mem::size_of::<libc::ff_effect>());
+const EVIOCRMFF: u32 = ioc(IOC_WRITE, 0x81, mem::size_of::<c_int>());
+
+/// Rumble for one joystick, as returned by [`Rumble::open`].
+#[derive(Debug)]
+pub struct Rumble {
+    file: File,
+    path: PathBuf,
+    // The id the kernel gave the uploaded effect, once there is one.
+    effect: Option<i16>,
+}
+
+impl Rumble {
+    /// Finds the event device for `joystick` and opens it for rumble.
+    ///
+    /// Devices are matched by name, since SDL doesn't say which device it
+    /// opened. When several joysticks have the same name, they're assumed to
+    /// be in the same order as their event devices.
+    pub fn open(joystick: &Joystick) -> Result<Rumble, RumbleError> {
+        let name = joystick.name();
+        // How many joysticks before this one have the same name.
+        let nth = (0..joystick.index())
+            .filter(|&index| joystick::name(index).as_deref() == Some(&name))
+            .count();
+
+        let path = event_devices()?
+            .into_iter()
+            .filter(|path| device_name(path).as_deref() == Some(&name))
+            .nth(nth)
+            .ok_or(RumbleError::DeviceNotFound(name))?;
+
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        if !supports_rumble(&file) {
+            return Err(RumbleError::Unsupported(path));
+        }
+
+        Ok(Rumble {
+            file,
+            path,
+            effect: None,
+        })
+    }
+
+    /// The event device being used, such as `/dev/input/event5`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rumbles for `duration`, replacing any rumble which is still going.
+    ///
+    /// # Arguments
+    ///
+    /// * `strong` - The strength of the strong, low frequency motor.
+    /// * `weak` - The strength of the weak, high frequency motor.
+    /// * `duration` - How long to rumble for, up to about 65 seconds.
+    ///
+    pub fn play(&mut self, strong: u16, weak: u16, duration: Duration) -> Result<(), RumbleError> {
+        let mut effect: libc::ff_effect = unsafe { mem::zeroed() };
+        effect.type_ = FF_RUMBLE;
+        // -1 asks for a new effect, otherwise the existing one is replaced.
+        effect.id = self.effect.unwrap_or(-1);
+        effect.replay.length = duration.as_millis().min(u16::MAX as u128) as u16;
+        let rumble = libc::ff_rumble_effect {
+            strong_magnitude: strong,
+            weak_magnitude: weak,
+        };
+        // The union the kernel expects is an array in libc, with the rumble
+        // parameters at the start.
+        unsafe {
+            ptr::addr_of_mut!(effect.u)
+                .cast::<libc::ff_rumble_effect>()
+                .write(rumble)
+        };
+
+        if unsafe { libc::ioctl(self.file.as_raw_fd(), EVIOCSFF as _, &mut effect) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        self.effect = Some(effect.id);
+        self.send(effect.id, 1)
+    }
+
+    /// Stops rumbling.
+    pub fn stop(&mut self) -> Result<(), RumbleError> {
+        match self.effect {
+            Some(id) => self.send(id, 0),
+            None => Ok(()),
+        }
+    }
+
+    // Starts (with a value of 1) or stops (0) the effect `id`.
+    fn send(&mut self, id: i16, value: i32) -> Result<(), RumbleError> {
+        let mut event: libc::input_event = unsafe { mem::zeroed() };
+        event.type_ = EV_FF;
+        event.code = id as u16;
+        event.value = value;
+
+        let bytes = unsafe {
+            slice::from_raw_parts(
+                &event as *const libc::input_event as *const u8,
+                mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.file.write_all(bytes)?;
+        Ok(())
+    }
+}
+
+impl Drop for Rumble {
+    fn drop(&mut self) {
+        if let Some(id) = self.effect {
+            unsafe { libc::ioctl(self.file.as_raw_fd(), EVIOCRMFF as _, id as c_int) };
+        }
+    }
+}
+
+// The /dev/input/event* devices, in numeric order.
+fn event_devices() -> io::Result<Vec<PathBuf>> {
+    let mut devices: Vec<(u32, PathBuf)> = fs::read_dir("/dev/input")?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let number = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("event")?
+                .parse()
+                .ok()?;
+            Some((number, path))
+        })
+        .collect();
+    devices.sort();
+    Ok(devices.into_iter().map(|(_, path)| path).collect())
+}
+
+// The name the kernel gives the device at `path`, if it can be read.
+fn device_name(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut name = [0u8; 256];
+    let ret = unsafe {
+        libc::ioctl(
+            file.as_raw_fd(),
+            eviocgname(name.len()) as _,
+            name.as_mut_ptr(),
+        )
+    };
+    if ret < 0 {
+        return None;
+    }
+    let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    Some(String::from_utf8_lossy(&name[..len]).into_owned())
+}
+
+fn supports_rumble(file: &File) -> bool {
+    let mut bits = [0u8; FF_MAX / 8 + 1];
+    let ret = unsafe {
+        libc::ioctl(
+            file.as_raw_fd(),
+            eviocgbit(EV_FF, bits.len()) as _,
+            bits.as_mut_ptr(),
+        )
+    };
+    let rumble = FF_RUMBLE as usize;
+    ret >= 0 && bits[rumble / 8] & (1 << (rumble % 8)) != 0
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RumbleError {
+    #[error("no event device found for joystick {:?}", .0)]
+    DeviceNotFound(String),
+    #[error("{} doesn't support rumble", .0.display())]
+    Unsupported(PathBuf),
+    #[error("IO error: {}", .0)]
+    IoError(#[from] io::Error),
+}
@@ -1,11 +1,33 @@
+use std::cell::RefCell;
+use std::ffi::c_int;
 use std::ffi::CStr;
+use std::ffi::CString;
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
+use std::ops::Add;
+use std::ops::Mul;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
 
 use crate::sys;
+use crate::video::PixelFormat;
 
+// SDL 1.2 requires video and event calls to happen on the thread which
+// initialized it. Raw pointers are neither Send nor Sync, so embedding this
+// marker pins a type to the thread it was created on at compile time.
+pub(crate) type NotSend = PhantomData<*mut ()>;
+
+/// The SDL context.
+///
+/// This, along with every subsystem created from it, is neither `Send` nor
+/// `Sync`, so it can't leave the thread which called [`init`].
 #[derive(Debug)]
 pub struct SDL {
     _pinned: std::marker::PhantomPinned,
+    _not_send: NotSend,
 }
 
 impl Drop for SDL {
@@ -22,6 +44,7 @@ pub fn init() -> Result<SDL> {
     } else {
         Ok(SDL {
             _pinned: PhantomPinned,
+            _not_send: PhantomData,
         })
     }
 }
@@ -30,11 +53,18 @@ impl SDL {
     pub fn video(&self) -> Result<VideoSubsystem> {
         VideoSubsystem::new(&self)
     }
+
+    /// Keeps the timer subsystem up for as long as the returned handle is,
+    /// see [`timer::Subsystem`](crate::timer::Subsystem).
+    pub fn timer(&self) -> Result<crate::timer::Subsystem> {
+        crate::timer::Subsystem::new()
+    }
 }
 
 #[derive(Debug)]
 pub struct VideoSubsystem {
     _pinned: std::marker::PhantomPinned,
+    _not_send: NotSend,
 }
 
 impl Drop for VideoSubsystem {
@@ -50,6 +80,7 @@ impl VideoSubsystem {
         } else {
             Ok(VideoSubsystem {
                 _pinned: PhantomPinned,
+                _not_send: PhantomData,
             })
         }
     }
@@ -57,23 +88,162 @@ impl VideoSubsystem {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+thread_local! {
+    // The message of the last SDL error this crate returned on this thread.
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Copies SDL's error message into an `Error`. SDL keeps a single message
+// which the next failing call overwrites, so this has to be called right
+// after the call which failed, before anything else touches SDL.
 pub(crate) fn get_error() -> Error {
-    ErrorRepr::Other(
-        unsafe { CStr::from_ptr(sys::SDL_GetError()) }
-            .to_string_lossy()
-            .into_owned(),
-    )
+    ErrorRepr::Other(error_message()).into()
+}
+
+// Like `get_error`, for SDL_mixer. Its errors end up in SDL's error message
+// too, but the message alone often doesn't say what was being done, such as
+// loading a file in a format it wasn't built with.
+#[cfg(feature = "mixer")]
+pub(crate) fn get_mixer_error(op: &'static str) -> Error {
+    ErrorRepr::Mixer {
+        op,
+        msg: error_message(),
+    }
     .into()
 }
 
+fn error_message() -> String {
+    let msg = unsafe { CStr::from_ptr(sys::SDL_GetError()) }
+        .to_string_lossy()
+        .into_owned();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(msg.clone()));
+    msg
+}
+
+/// Returns the message of the most recent SDL error returned by this crate
+/// on the current thread, if there has been one.
+///
+/// Unlike `SDL_GetError`, this can't be overwritten by calls on other
+/// threads or by later calls which don't fail, which makes it useful for
+/// diagnostics after the error itself has been handled.
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.with(|last| last.borrow().clone())
+}
+
+/// Forgets the error returned by [`last_error`] on the current thread.
+pub fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+// SDL takes paths as C strings, so they have to be valid UTF-8 without any
+// interior nul bytes.
+pub(crate) fn path_to_cstring(path: &Path) -> Result<CString> {
+    path.to_str()
+        .and_then(|path| CString::new(path).ok())
+        .ok_or_else(|| ErrorRepr::InvalidPath(path.to_owned()).into())
+}
+
+// Opens an `SDL_RWops` reading from `data`, which has to outlive it. The
+// loading functions all take a flag to close it when they're done.
+pub(crate) fn rw_from_slice(data: &[u8]) -> Result<*mut sys::SDL_RWops> {
+    let len = c_int::try_from(data.len())
+        .map_err(|_| ErrorRepr::Other("data too large for SDL".to_owned()))?;
+    let rw = unsafe { sys::SDL_RWFromConstMem(data.as_ptr().cast(), len) };
+    if rw.is_null() {
+        Err(get_error())
+    } else {
+        Ok(rw)
+    }
+}
+
+// The message of the first panic caught in a callback which hasn't been
+// reported yet. Callbacks can run on SDL's own threads, so this is global.
+static CALLBACK_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+// Runs a user callback from an `extern "C"` function. Unwinding into C is
+// undefined behavior, so panics are caught here and recorded, to be reported
+// by the next call to `check_callbacks`. Returns `None` if `f` panicked.
+pub(crate) fn catch_callback_panic<R, F: FnOnce() -> R>(f: F) -> Option<R> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_owned());
+
+            let mut panic = CALLBACK_PANIC.lock().unwrap_or_else(|err| err.into_inner());
+            panic.get_or_insert(msg);
+            None
+        }
+    }
+}
+
+/// Returns an error if a callback passed to SDL (a timer, event filter,
+/// audio callback and so on) has panicked since the last check.
+///
+/// Panics can't unwind through SDL, so they're caught where SDL calls back
+/// into Rust and reported here instead. Only the first panic is kept.
+///
+/// This is also checked by the calls most programs make regularly, so a
+/// panic shows up as an error from one of them: waiting for and peeking at
+/// events, `AudioDevice::lock`, [`timer::main_loop`](crate::timer::main_loop)
+/// and playing sounds and music with the mixer. Polling for events can't
+/// fail, so it doesn't.
+pub fn check_callbacks() -> Result<()> {
+    let mut panic = CALLBACK_PANIC.lock().unwrap_or_else(|err| err.into_inner());
+    match panic.take() {
+        Some(msg) => Err(ErrorRepr::CallbackPanicked(msg).into()),
+        None => Ok(()),
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error(transparent)]
 pub struct Error(#[from] ErrorRepr);
 
+impl Error {
+    /// Which part of SDL the error came from, for telling them apart without
+    /// matching on the message.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            ErrorRepr::ErrorCode(_) | ErrorRepr::Other(_) => ErrorKind::Sdl,
+            ErrorRepr::InvalidPath(_) => ErrorKind::InvalidPath,
+            ErrorRepr::CallbackPanicked(_) => ErrorKind::CallbackPanicked,
+            #[cfg(feature = "mixer")]
+            ErrorRepr::Mixer { op, .. } => ErrorKind::Mixer { op },
+        }
+    }
+}
+
+/// The kind of an [`Error`], as returned by [`Error::kind`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ErrorKind {
+    /// A call into SDL itself failed.
+    Sdl,
+    /// A path couldn't be passed to SDL, since it isn't valid UTF-8 or has a
+    /// nul byte in it.
+    InvalidPath,
+    /// A callback passed to SDL panicked, see [`check_callbacks`].
+    CallbackPanicked,
+    /// A call into SDL_mixer failed. `op` is the function which failed, such
+    /// as `"Mix_LoadWAV_RW"`.
+    #[cfg(feature = "mixer")]
+    Mixer { op: &'static str },
+}
+
 #[derive(thiserror::Error, Debug)]
 enum ErrorRepr {
     #[error("error code: {0}")]
     ErrorCode(#[from] ErrorCode),
+    #[error("invalid path: {}", .0.display())]
+    InvalidPath(PathBuf),
+    #[error("callback panicked: {0}")]
+    CallbackPanicked(String),
+    #[cfg(feature = "mixer")]
+    #[error("SDL_mixer error in {op}: {msg}")]
+    Mixer { op: &'static str, msg: String },
     #[error("unknown error: {0}")]
     Other(String),
 }
@@ -106,6 +276,7 @@ impl From<sys::SDL_errorcode> for ErrorCode {
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -126,6 +297,24 @@ impl Color {
         Color::rgba(255 - self.r, 255 - self.g, 255 - self.b, 255 - self.a)
     }
 
+    pub const fn with_alpha(self, a: u8) -> Color {
+        Color::rgba(self.r, self.g, self.b, a)
+    }
+
+    /// Linearly interpolates every channel, including alpha, towards `other`.
+    /// `t` is clamped between 0, which returns `self`, and 1, which returns
+    /// `other`.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::rgba(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+            channel(self.a, other.a),
+        )
+    }
+
     pub const fn into_rgb(self) -> (u8, u8, u8) {
         (self.r, self.g, self.b)
     }
@@ -134,6 +323,86 @@ impl Color {
         (self.r, self.g, self.b, self.a)
     }
 
+    /// Creates an opaque color from a hue in degrees and a saturation and
+    /// value between 0 and 1. Out of range hues wrap around and everything
+    /// else is clamped.
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Color {
+        let s = s.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+        let c = v * s;
+        Color::from_hue(h, c, v - c)
+    }
+
+    /// Returns the hue in degrees and the saturation and value between 0
+    /// and 1. Alpha is ignored.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue();
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (h, s, max)
+    }
+
+    /// Creates an opaque color from a hue in degrees and a saturation and
+    /// lightness between 0 and 1. Out of range hues wrap around and
+    /// everything else is clamped.
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Color {
+        let s = s.clamp(0.0, 1.0);
+        let l = l.clamp(0.0, 1.0);
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Color::from_hue(h, c, l - c / 2.0)
+    }
+
+    /// Returns the hue in degrees and the saturation and lightness between 0
+    /// and 1. Alpha is ignored.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue();
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (h, s, l)
+    }
+
+    // Shared by HSV and HSL, which only differ in how the chroma and the
+    // amount added to every channel are derived.
+    fn from_hue(h: f32, chroma: f32, m: f32) -> Color {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::rgb(channel(r), channel(g), channel(b))
+    }
+
+    // Returns the hue in degrees along with the largest and smallest channel,
+    // scaled to be between 0 and 1.
+    fn hue(self) -> (f32, f32, f32) {
+        let r = self.r as f32 / 255.0;
+        let g = self.g as f32 / 255.0;
+        let b = self.b as f32 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (h, max, min)
+    }
+
     // Implemented manually and kept private, because reasons
     const fn raw(self) -> sys::SDL_Color {
         sys::SDL_Color {
@@ -154,6 +423,88 @@ impl Color {
     pub const MAGENTA: Color = Color::rgba(255, 0, 255, 255);
     pub const YELLOW: Color = Color::rgba(255, 255, 0, 255);
     pub const CYAN: Color = Color::rgba(0, 255, 255, 255);
+
+    /// Maps the color to a pixel value in the given format, using the
+    /// closest palette entry for 8-bit formats. Alpha is dropped for formats
+    /// without an alpha channel.
+    pub fn to_pixel(self, format: &PixelFormat) -> u32 {
+        unsafe { sys::SDL_MapRGBA(format.raw(), self.r, self.g, self.b, self.a) }
+    }
+
+    /// Reads a pixel value in the given format. Formats without an alpha
+    /// channel always come out opaque.
+    pub fn from_pixel(pixel: u32, format: &PixelFormat) -> Color {
+        let (mut r, mut g, mut b, mut a) = (0, 0, 0, 0);
+        unsafe { sys::SDL_GetRGBA(pixel, format.raw(), &mut r, &mut g, &mut b, &mut a) };
+        Color::rgba(r, g, b, a)
+    }
+
+    /// Looks up one of the named constants above, ignoring case.
+    pub fn from_name(name: &str) -> Option<Color> {
+        let color = match name.to_ascii_lowercase().as_str() {
+            "white" => Color::WHITE,
+            "black" => Color::BLACK,
+            "gray" => Color::GRAY,
+            "grey" => Color::GREY,
+            "red" => Color::RED,
+            "green" => Color::GREEN,
+            "blue" => Color::BLUE,
+            "magenta" => Color::MAGENTA,
+            "yellow" => Color::YELLOW,
+            "cyan" => Color::CYAN,
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+/// Parses `#RGB`, `#RRGGBB` and `#RRGGBBAA` hex colors, or one of the names
+/// accepted by [`Color::from_name`].
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> std::result::Result<Color, ParseColorError> {
+        let hex = match s.strip_prefix('#') {
+            Some(hex) => hex,
+            None => {
+                return Color::from_name(s)
+                    .ok_or_else(|| ParseColorError::UnknownName(s.to_owned()))
+            }
+        };
+
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseColorError::InvalidHex(s.to_owned()));
+        }
+        let channel =
+            |i: usize, len: usize| u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).unwrap();
+
+        match hex.len() {
+            // Each digit is doubled, so #f80 is #ff8800.
+            3 => Ok(Color::rgb(
+                channel(0, 1) * 17,
+                channel(1, 1) * 17,
+                channel(2, 1) * 17,
+            )),
+            6 => Ok(Color::rgb(channel(0, 2), channel(1, 2), channel(2, 2))),
+            8 => Ok(Color::rgba(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                channel(3, 2),
+            )),
+            _ => Err(ParseColorError::InvalidLength(s.to_owned())),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum ParseColorError {
+    #[error("unknown color name: {}", .0)]
+    UnknownName(String),
+    #[error("invalid hex digits in color: {}", .0)]
+    InvalidHex(String),
+    #[error("hex color must have 3, 6 or 8 digits: {}", .0)]
+    InvalidLength(String),
 }
 
 impl Into<sys::SDL_Color> for Color {
@@ -174,12 +525,45 @@ impl From<(u8, u8, u8)> for Color {
     }
 }
 
+/// Additive blending, which saturates every channel, including alpha.
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color::rgba(
+            self.r.saturating_add(other.r),
+            self.g.saturating_add(other.g),
+            self.b.saturating_add(other.b),
+            self.a.saturating_add(other.a),
+        )
+    }
+}
+
+/// Multiplicative blending, which treats every channel, including alpha, as
+/// a fraction of 255. Multiplying by white leaves a color unchanged.
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        let channel = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+        Color::rgba(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+            channel(self.a, other.a),
+        )
+    }
+}
+
 impl From<(u8, u8, u8, u8)> for Color {
     fn from((r, g, b, a): (u8, u8, u8, u8)) -> Color {
         Color::rgba(r, g, b, a)
     }
 }
 
+/// Packs the color as `0xRRGGBBAA`, which is what the `*Color` functions of
+/// SDL_gfx expect no matter the format of the surface they draw to. Use
+/// [`Color::to_pixel`] for a value in a surface's own pixel format.
 impl Into<u32> for Color {
     fn into(self) -> u32 {
         u32::from_be_bytes([self.r, self.g, self.b, self.a])
@@ -0,0 +1,210 @@
+//! The native window behind SDL's video surface, from `SDL_GetWMInfo`.
+//!
+//! SDL 1.2 only fills in the handles for X11 and Windows, the same drivers
+//! `sdl-sys` generates the platform specific part of `SDL_SysWMinfo` for.
+//! Everywhere else [`wm_info`] returns [`WmInfo::Unknown`].
+//!
+//! With the `raw-window-handle` feature, [`Screen`](crate::video::Screen)
+//! implements `HasWindowHandle` and `HasDisplayHandle`, for handing the
+//! window to renderers from other crates.
+
+use std::ffi::c_int;
+use std::mem;
+
+use crate::sdl;
+use crate::sys;
+
+/// The window system SDL's window belongs to, and its handles.
+#[derive(Copy, Clone, Debug)]
+pub enum WmInfo {
+    X11(X11Info),
+    Windows(WindowsInfo),
+    /// SDL doesn't report anything about the window on this platform.
+    Unknown,
+}
+
+/// SDL's X11 display and windows.
+///
+/// SDL uses the display from its own event thread, if it has one, so lock
+/// it with [`lock`](X11Info::lock) around any calls to Xlib.
+#[derive(Copy, Clone, Debug)]
+pub struct X11Info {
+    /// The `Display *` SDL's window is on.
+    pub display: *mut sys::Display,
+    /// The window drawn to, in both windowed and fullscreen mode.
+    pub window: sys::Window,
+    /// The window used for fullscreen mode.
+    pub fullscreen_window: sys::Window,
+    /// The window the window manager sees, which holds the others.
+    pub manager_window: sys::Window,
+    lock_func: Option<unsafe extern "C" fn()>,
+    unlock_func: Option<unsafe extern "C" fn()>,
+}
+
+impl X11Info {
+    /// Locks SDL's display until the returned guard is dropped.
+    pub fn lock(&self) -> X11Lock {
+        if let Some(lock) = self.lock_func {
+            unsafe { lock() };
+        }
+        X11Lock {
+            unlock_func: self.unlock_func,
+        }
+    }
+}
+
+/// Keeps SDL's X11 display locked, see [`X11Info::lock`].
+#[derive(Debug)]
+#[must_use = "the display is unlocked as soon as this is dropped"]
+pub struct X11Lock {
+    unlock_func: Option<unsafe extern "C" fn()>,
+}
+
+impl Drop for X11Lock {
+    fn drop(&mut self) {
+        if let Some(unlock) = self.unlock_func {
+            unsafe { unlock() };
+        }
+    }
+}
+
+/// SDL's window on Windows.
+#[derive(Copy, Clone, Debug)]
+pub struct WindowsInfo {
+    /// The window's `HWND`.
+    pub hwnd: *mut std::ffi::c_void,
+}
+
+/// Returns the native handles of SDL's window. SDL only has a window once
+/// the video mode has been set.
+pub fn wm_info() -> sdl::Result<WmInfo> {
+    let mut info: sys::SDL_SysWMinfo = unsafe { mem::zeroed() };
+    info.version = sys::SDL_version {
+        major: sys::SDL_MAJOR_VERSION as u8,
+        minor: sys::SDL_MINOR_VERSION as u8,
+        patch: sys::SDL_PATCHLEVEL as u8,
+    };
+    match unsafe { sys::SDL_GetWMInfo(&mut info) } {
+        ret if ret < 0 => Err(sdl::get_error()),
+        0 => Ok(WmInfo::Unknown),
+        _ => Ok(imp::from_raw(&info)),
+    }
+}
+
+/// Turns delivery of `Event::SysWM` on or off, and returns whether it was
+/// on before. These carry platform specific window manager messages, which
+/// SDL drops by default.
+pub fn enable_events(enabled: bool) -> bool {
+    let state = if enabled {
+        sys::SDL_ENABLE
+    } else {
+        sys::SDL_IGNORE
+    };
+    let was =
+        unsafe { sys::SDL_EventState(sys::SDL_EventType::SDL_SYSWMEVENT as u8, state as c_int) };
+    was == sys::SDL_ENABLE as u8
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+    target_os = "solaris"
+))]
+mod imp {
+    use super::{WmInfo, X11Info};
+    use crate::sys;
+
+    pub fn from_raw(info: &sys::SDL_SysWMinfo) -> WmInfo {
+        if info.subsystem != sys::SDL_SYSWM_TYPE::SDL_SYSWM_X11 {
+            return WmInfo::Unknown;
+        }
+
+        let x11 = unsafe { info.info.x11 };
+        WmInfo::X11(X11Info {
+            display: x11.display,
+            window: x11.window,
+            fullscreen_window: x11.fswindow,
+            manager_window: x11.wmwindow,
+            lock_func: x11.lock_func,
+            unlock_func: x11.unlock_func,
+        })
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{WindowsInfo, WmInfo};
+    use crate::sys;
+
+    pub fn from_raw(info: &sys::SDL_SysWMinfo) -> WmInfo {
+        WmInfo::Windows(WindowsInfo {
+            hwnd: info.window as *mut std::ffi::c_void,
+        })
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+    target_os = "solaris",
+    windows
+)))]
+mod imp {
+    use super::WmInfo;
+    use crate::sys;
+
+    pub fn from_raw(_info: &sys::SDL_SysWMinfo) -> WmInfo {
+        WmInfo::Unknown
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+mod handle {
+    use std::num::NonZeroIsize;
+    use std::ptr::NonNull;
+
+    use raw_window_handle::{
+        DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, Win32WindowHandle,
+        WindowHandle, WindowsDisplayHandle, XlibDisplayHandle, XlibWindowHandle,
+    };
+
+    use super::{wm_info, WmInfo};
+    use crate::video::Screen;
+
+    // The handles stay valid for as long as the screen is around, since
+    // changing the video mode needs the screen to be dropped first.
+    impl HasWindowHandle for Screen {
+        fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+            let raw = match wm_info().map_err(|_| HandleError::Unavailable)? {
+                WmInfo::X11(x11) => XlibWindowHandle::new(x11.window).into(),
+                WmInfo::Windows(windows) => {
+                    let hwnd =
+                        NonZeroIsize::new(windows.hwnd as isize).ok_or(HandleError::Unavailable)?;
+                    Win32WindowHandle::new(hwnd).into()
+                }
+                WmInfo::Unknown => return Err(HandleError::NotSupported),
+            };
+            Ok(unsafe { WindowHandle::borrow_raw(raw) })
+        }
+    }
+
+    impl HasDisplayHandle for Screen {
+        fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+            let raw = match wm_info().map_err(|_| HandleError::Unavailable)? {
+                WmInfo::X11(x11) => {
+                    let display = NonNull::new(x11.display.cast());
+                    XlibDisplayHandle::new(display, 0).into()
+                }
+                WmInfo::Windows(_) => WindowsDisplayHandle::new().into(),
+                WmInfo::Unknown => return Err(HandleError::NotSupported),
+            };
+            Ok(unsafe { DisplayHandle::borrow_raw(raw) })
+        }
+    }
+}
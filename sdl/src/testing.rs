@@ -0,0 +1,231 @@
+//! Golden-image testing for rendering code.
+//!
+//! [`render`] runs drawing code against an off-screen RGBA surface and
+//! [`assert_golden`] compares the result against a reference image on disk.
+//!
+//! SDL 1.2 can only write BMPs, which have no alpha channel, so references
+//! are stored in a small lossless format of their own: an 8 byte magic
+//! followed by the width and height as little endian `u32`s and the raw RGBA
+//! pixels. When a reference doesn't exist yet, or the `SDL_GOLDEN_UPDATE`
+//! environment variable is set, it is written instead of compared against.
+//! On a mismatch the actual output and a diff, with every differing pixel in
+//! red, are written next to the reference as BMPs for inspection.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::sdl;
+use crate::video::{PixelFormatSpec, Surface};
+use crate::{VideoSubsystem, SDL};
+
+const MAGIC: &[u8; 8] = b"SDLGOLD1";
+
+/// Set to re-record every reference rather than comparing against them.
+pub const UPDATE_VAR: &str = "SDL_GOLDEN_UPDATE";
+
+/// Initializes SDL and the video subsystem with the dummy video driver, so
+/// tests can run without a display.
+pub fn init_headless() -> sdl::Result<(SDL, VideoSubsystem)> {
+    env::set_var("SDL_VIDEODRIVER", "dummy");
+    let sdl_context = crate::init()?;
+    let video = sdl_context.video()?;
+    Ok((sdl_context, video))
+}
+
+/// Creates a 32-bit RGBA surface which stores its pixels in R, G, B, A byte
+/// order, regardless of the platform's endianness.
+pub fn rgba_surface(width: u32, height: u32) -> sdl::Result<Surface> {
+    Surface::with_format(width, height, PixelFormatSpec::RGBA8888)
+}
+
+/// Runs `draw` against a new transparent RGBA surface and returns the result.
+pub fn render<F>(width: u32, height: u32, draw: F) -> sdl::Result<Surface>
+where
+    F: FnOnce(&mut Surface) -> sdl::Result<()>,
+{
+    let mut surface = rgba_surface(width, height)?;
+    draw(&mut surface)?;
+    Ok(surface)
+}
+
+/// An image as tightly packed RGBA bytes.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Reads the pixels of any surface, converting them to RGBA.
+    pub fn from_surface(surface: &mut Surface) -> sdl::Result<Image> {
+        Ok(Image {
+            width: surface.width(),
+            height: surface.height(),
+            pixels: surface.read_rgba()?,
+        })
+    }
+
+    /// Copies the image into a new RGBA surface.
+    pub fn to_surface(&self) -> sdl::Result<Surface> {
+        let mut surface = rgba_surface(self.width, self.height)?;
+        let row_len = self.width as usize * 4;
+
+        let mut lock = surface.lock()?;
+        let pitch = lock.pitch();
+        if row_len > 0 {
+            for (dst, src) in lock
+                .pixels_mut()
+                .chunks_mut(pitch)
+                .zip(self.pixels.chunks(row_len))
+            {
+                dst[..row_len].copy_from_slice(src);
+            }
+        }
+        drop(lock);
+
+        Ok(surface)
+    }
+
+    /// Loads a reference image.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Image> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a golden image",
+            ));
+        }
+
+        let width = reader.read_u32::<LittleEndian>()?;
+        let height = reader.read_u32::<LittleEndian>()?;
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        reader.read_exact(&mut pixels)?;
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Saves the image as a reference.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(self.width)?;
+        writer.write_u32::<LittleEndian>(self.height)?;
+        writer.write_all(&self.pixels)?;
+        writer.flush()
+    }
+
+    /// A 64-bit FNV-1a hash of the image, which is stable across platforms
+    /// and compiler versions.
+    pub fn hash(&self) -> u64 {
+        let header = [self.width.to_le_bytes(), self.height.to_le_bytes()];
+        header
+            .iter()
+            .flatten()
+            .chain(&self.pixels)
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    /// Builds an image which shows the pixels differing from `other` in red
+    /// on top of a faded grayscale copy of this image, along with the number
+    /// of differing pixels. Both images must be the same size.
+    pub fn diff(&self, other: &Image) -> (Image, usize) {
+        let mut differing = 0;
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+
+        for (a, b) in self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4))
+        {
+            if a == b {
+                let luma = (a[0] as u32 * 299 + a[1] as u32 * 587 + a[2] as u32 * 114) / 1000;
+                let faded = (luma / 3) as u8;
+                pixels.extend_from_slice(&[faded, faded, faded, 0xff]);
+            } else {
+                differing += 1;
+                pixels.extend_from_slice(&[0xff, 0, 0, 0xff]);
+            }
+        }
+
+        let image = Image {
+            width: self.width,
+            height: self.height,
+            pixels,
+        };
+        (image, differing)
+    }
+}
+
+/// Compares `surface` against the reference image at `reference`.
+///
+/// See the [module documentation](self) for how references are recorded and
+/// what's written on a mismatch.
+pub fn assert_golden<P: AsRef<Path>>(
+    surface: &mut Surface,
+    reference: P,
+) -> Result<(), GoldenError> {
+    let reference = reference.as_ref();
+    let actual = Image::from_surface(surface)?;
+
+    if env::var_os(UPDATE_VAR).is_some() || !reference.exists() {
+        actual.save(reference)?;
+        return Ok(());
+    }
+
+    let expected = Image::load(reference)?;
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(GoldenError::SizeMismatch {
+            expected: (expected.width, expected.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+
+    if expected.hash() == actual.hash() && expected == actual {
+        return Ok(());
+    }
+
+    let (diff, differing) = actual.diff(&expected);
+    let actual_path = reference.with_extension("actual.bmp");
+    let diff_path = reference.with_extension("diff.bmp");
+    actual.to_surface()?.save_bmp(&actual_path)?;
+    diff.to_surface()?.save_bmp(&diff_path)?;
+
+    Err(GoldenError::Mismatch {
+        differing,
+        actual: actual_path,
+        diff: diff_path,
+    })
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GoldenError {
+    #[error("{differing} pixels differ from the reference, see {}", .diff.display())]
+    Mismatch {
+        differing: usize,
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+    #[error("expected a {}x{} image, got {}x{}", .expected.0, .expected.1, .actual.0, .actual.1)]
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    #[error("IO error: {}", .0)]
+    IoError(#[from] io::Error),
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
@@ -1,31 +1,457 @@
+use std::collections::VecDeque;
+use std::hint;
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use sys::SDL_InitSubSystem;
 
 use crate::sys;
 use crate::sdl;
 
+/// Keeps the timer subsystem initialized while it's around.
+///
+/// This is optional: the timer functions here initialize the subsystem
+/// themselves the first time they need it. SDL 1.2 doesn't count how many
+/// times a subsystem was initialized, so these are counted here instead, and
+/// the subsystem only shuts down once the last one is dropped.
 #[derive(Debug)]
 pub struct Subsystem {
     _pinned: std::marker::PhantomPinned,
+    _not_send: sdl::NotSend,
 }
 
+// The number of `Subsystem`s alive.
+static USERS: AtomicUsize = AtomicUsize::new(0);
+
 impl Drop for Subsystem {
     fn drop(&mut self) {
-        unsafe {
-            sys::SDL_QuitSubSystem(sys::SDL_INIT_TIMER)
+        if USERS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            unsafe { sys::SDL_QuitSubSystem(sys::SDL_INIT_TIMER) }
         }
     }
 }
 
 impl Subsystem {
     pub(crate) fn new() -> sdl::Result<Subsystem> {
-        if unsafe { SDL_InitSubSystem(sys::SDL_INIT_TIMER) } != 0 {
-            Err(sdl::get_error())
-        } else {
-            Ok(Subsystem {
-                _pinned: PhantomPinned,
-            })
+        ensure_init()?;
+        USERS.fetch_add(1, Ordering::SeqCst);
+        Ok(Subsystem {
+            _pinned: PhantomPinned,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+// Initializes the timer subsystem if it isn't already. Calling
+// `SDL_GetTicks` before then gives 0 on some platforms, so anything reading
+// the time goes through here first. Once initialized this way it stays up
+// until `SDL_Quit`, or until the last `Subsystem` is dropped.
+fn ensure_init() -> sdl::Result<()> {
+    if unsafe { sys::SDL_WasInit(sys::SDL_INIT_TIMER) } != 0 {
+        return Ok(());
+    }
+    if unsafe { SDL_InitSubSystem(sys::SDL_INIT_TIMER) } != 0 {
+        Err(sdl::get_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// A time from `SDL_GetTicks`, in milliseconds since SDL was initialized.
+///
+/// The count is 32 bits, so it wraps around to 0 after about 49 days.
+/// Comparisons and differences take that into account, like SDL 2's
+/// `SDL_TICKS_PASSED`, so they stay correct across the wraparound as long as
+/// the times compared are less than about 24 days apart.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ticks(u32);
+
+impl Ticks {
+    /// The current time. This initializes the timer subsystem if it isn't
+    /// already, and reads 0 if that fails.
+    pub fn now() -> Ticks {
+        if ensure_init().is_err() {
+            return Ticks(0);
+        }
+        Ticks(unsafe { sys::SDL_GetTicks() })
+    }
+
+    pub const fn from_raw(ms: u32) -> Ticks {
+        Ticks(ms)
+    }
+
+    pub const fn raw(self) -> u32 {
+        self.0
+    }
+
+    /// Whether this is later than `other`.
+    pub fn is_after(self, other: Ticks) -> bool {
+        self.offset_from(other) > 0
+    }
+
+    /// Whether this is earlier than `other`.
+    pub fn is_before(self, other: Ticks) -> bool {
+        self.offset_from(other) < 0
+    }
+
+    /// Whether the current time has reached this one, such as a deadline.
+    pub fn has_passed(self) -> bool {
+        !self.is_after(Ticks::now())
+    }
+
+    /// The time from `earlier` to this, or zero if `earlier` is actually
+    /// later.
+    pub fn duration_since(self, earlier: Ticks) -> Duration {
+        Duration::from_millis(self.offset_from(earlier).max(0) as u64)
+    }
+
+    /// The time since this, or zero if it's in the future.
+    pub fn elapsed(self) -> Duration {
+        Ticks::now().duration_since(self)
+    }
+
+    // The signed difference, read across the wraparound.
+    fn offset_from(self, other: Ticks) -> i32 {
+        self.0.wrapping_sub(other.0) as i32
+    }
+}
+
+// Durations past the range of a u32 of milliseconds wrap around along with
+// the ticks themselves.
+fn duration_ms(duration: Duration) -> u32 {
+    duration.as_millis() as u32
+}
+
+impl Add<Duration> for Ticks {
+    type Output = Ticks;
+
+    fn add(self, duration: Duration) -> Ticks {
+        Ticks(self.0.wrapping_add(duration_ms(duration)))
+    }
+}
+
+impl AddAssign<Duration> for Ticks {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Ticks {
+    type Output = Ticks;
+
+    fn sub(self, duration: Duration) -> Ticks {
+        Ticks(self.0.wrapping_sub(duration_ms(duration)))
+    }
+}
+
+impl SubAssign<Duration> for Ticks {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub for Ticks {
+    type Output = Duration;
+
+    /// Like [`duration_since`](Ticks::duration_since).
+    fn sub(self, earlier: Ticks) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+// How much of a precise delay is spent spinning rather than sleeping.
+const SPIN_MS: u32 = 1;
+
+/// Waits for `duration`, more precisely than `SDL_Delay` on its own.
+///
+/// `SDL_Delay` can oversleep, which shows up as uneven frame pacing. This
+/// sleeps for all but the last millisecond, then spins on `SDL_GetTicks`
+/// for the rest, so it keeps a CPU core busy for that last millisecond. The
+/// duration is rounded down to whole milliseconds.
+pub fn delay_precise(duration: Duration) {
+    let ms = duration.as_millis().min(u32::MAX as u128) as u32;
+    let deadline = Ticks::now() + Duration::from_millis(ms as u64);
+
+    if ms > SPIN_MS {
+        unsafe { sys::SDL_Delay(ms - SPIN_MS) }
+    }
+    while !deadline.has_passed() {
+        hint::spin_loop();
+    }
+}
+
+/// A frame which took much longer than the frames around it, as recorded by
+/// [`FrameStats`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Stutter {
+    /// The number of the frame, counting from 0 for the first one recorded.
+    pub frame: u64,
+    /// When the frame was recorded.
+    pub ticks: Ticks,
+    pub frame_time: Duration,
+    /// The median frame time at the time, for comparison.
+    pub typical: Duration,
+}
+
+/// Keeps a rolling window of frame times, for finding out how smooth a game
+/// really runs rather than just its average frame rate.
+///
+/// Call [`record`](FrameStats::record) once per frame with the time the frame
+/// took, or let the `gfx` module's `FPSManager` or `FpsOverlay` record it.
+/// Frames which take much longer than the median are also logged as
+/// [`Stutter`]s, so occasional hitches can be tracked down after the fact.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+    frames: VecDeque<Duration>,
+    window: usize,
+    frame_count: u64,
+    stutter_factor: f32,
+    stutters: VecDeque<Stutter>,
+    stutter_log_len: usize,
+}
+
+// Fewer frames than this and the median isn't worth comparing against.
+const MIN_STUTTER_FRAMES: usize = 10;
+
+impl Default for FrameStats {
+    fn default() -> FrameStats {
+        FrameStats::new(120)
+    }
+}
+
+impl FrameStats {
+    /// Creates an empty record which keeps the last `window` frame times. A
+    /// window of 0 is treated as 1.
+    pub fn new(window: usize) -> FrameStats {
+        FrameStats {
+            frames: VecDeque::new(),
+            window: window.max(1),
+            frame_count: 0,
+            stutter_factor: 2.0,
+            stutters: VecDeque::new(),
+            stutter_log_len: 64,
+        }
+    }
+
+    /// Sets how many times longer than the median a frame has to take to
+    /// count as a stutter. The default is 2.
+    pub fn set_stutter_factor(&mut self, factor: f32) -> &mut FrameStats {
+        self.stutter_factor = factor.max(1.0);
+        self
+    }
+
+    /// Sets the number of frame times kept, dropping the oldest ones past
+    /// that. A window of 0 is treated as 1.
+    pub fn set_window(&mut self, window: usize) -> &mut FrameStats {
+        self.window = window.max(1);
+        while self.frames.len() > self.window {
+            self.frames.pop_front();
+        }
+        self
+    }
+
+    /// Sets how many stutters are kept, dropping the oldest ones past that.
+    /// The default is 64.
+    pub fn set_stutter_log_len(&mut self, len: usize) -> &mut FrameStats {
+        self.stutter_log_len = len;
+        while self.stutters.len() > len {
+            self.stutters.pop_front();
+        }
+        self
+    }
+
+    /// Records the time the last frame took.
+    pub fn record(&mut self, frame_time: Duration) {
+        if self.frames.len() >= MIN_STUTTER_FRAMES && self.stutter_log_len > 0 {
+            let typical = self.percentile(50.0).unwrap_or_default();
+            if frame_time > typical.mul_f32(self.stutter_factor) {
+                if self.stutters.len() == self.stutter_log_len {
+                    self.stutters.pop_front();
+                }
+                self.stutters.push_back(Stutter {
+                    frame: self.frame_count,
+                    ticks: Ticks::now(),
+                    frame_time,
+                    typical,
+                });
+            }
+        }
+
+        if self.frames.len() == self.window {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame_time);
+        self.frame_count += 1;
+    }
+
+    /// The number of frames recorded in total, including those which have
+    /// since left the window.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// The time the last frame took, or `None` if nothing has been recorded
+    /// yet.
+    pub fn last(&self) -> Option<Duration> {
+        self.frames.back().copied()
+    }
+
+    /// The average frame time over the window, or `None` if nothing has
+    /// been recorded yet.
+    pub fn mean(&self) -> Option<Duration> {
+        let total: Duration = self.frames.iter().sum();
+        total.checked_div(self.frames.len() as u32)
+    }
+
+    /// The frame time which `percent` percent of the frames in the window
+    /// took at most, or `None` if nothing has been recorded yet.
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let mut frames: Vec<Duration> = self.frames.iter().copied().collect();
+        let rank = (percent.clamp(0.0, 100.0) / 100.0 * frames.len() as f64).ceil() as usize;
+        let index = rank.clamp(1, frames.len()) - 1;
+        Some(*frames.select_nth_unstable(index).1)
+    }
+
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    /// Counts the frames in the window by how long they took, in `buckets`
+    /// buckets `bucket` wide. The first bucket counts frames shorter than
+    /// `bucket`, the next frames shorter than twice that and so on, and the
+    /// last one every frame too slow for the others. At least one bucket is
+    /// always returned.
+    pub fn histogram(&self, bucket: Duration, buckets: usize) -> Vec<usize> {
+        let bucket = bucket.max(Duration::from_micros(1));
+        let mut counts = vec![0; buckets.max(1)];
+        let last = counts.len() - 1;
+        for frame in &self.frames {
+            let index = frame.as_nanos() / bucket.as_nanos();
+            counts[index.min(last as u128) as usize] += 1;
+        }
+        counts
+    }
+
+    /// The logged stutters, oldest first.
+    pub fn stutters(&self) -> impl Iterator<Item = &Stutter> {
+        self.stutters.iter()
+    }
+
+    pub fn clear_stutters(&mut self) {
+        self.stutters.clear();
+    }
+
+    /// Forgets everything recorded so far, such as after loading a level.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.stutters.clear();
+        self.frame_count = 0;
+    }
+}
+
+/// Runs `callback` once per frame until it returns `false`.
+///
+/// On native targets this is a plain loop which waits out the remainder of
+/// each frame with `SDL_Delay`. Browsers don't allow blocking the main thread,
+/// so on emscripten the callback is handed to `emscripten_set_main_loop`
+/// instead and this function never returns.
+///
+/// # Arguments
+///
+/// * `fps` - The target framerate. 0 runs as fast as possible natively, or
+///   in sync with the browser's `requestAnimationFrame` on emscripten.
+/// * `callback` - Called once per frame. Returning `false` ends the loop.
+///
+/// Natively, the loop also ends with an error if a callback passed to SDL
+/// panics, see [`check_callbacks`](crate::sdl::check_callbacks).
+pub fn main_loop<F>(fps: u32, callback: F) -> sdl::Result<()>
+where
+    F: FnMut() -> bool + 'static,
+{
+    imp::main_loop(fps, callback)
+}
+
+#[cfg(not(target_os = "emscripten"))]
+mod imp {
+    use std::time::Duration;
+
+    use super::Ticks;
+    use crate::sdl;
+    use crate::sys;
+
+    pub fn main_loop<F>(fps: u32, mut callback: F) -> sdl::Result<()>
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        let frame_time = Duration::from_millis(1000u64.checked_div(fps as u64).unwrap_or(0));
+
+        loop {
+            sdl::check_callbacks()?;
+            let start = Ticks::now();
+            if !callback() {
+                return Ok(());
+            }
+
+            let elapsed = start.elapsed();
+            if elapsed < frame_time {
+                unsafe { sys::SDL_Delay((frame_time - elapsed).as_millis() as u32) }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+mod imp {
+    use std::cell::RefCell;
+
+    use libc::c_int;
+
+    use crate::sdl;
+    use crate::sys;
+
+    thread_local! {
+        static MAIN_LOOP: RefCell<Option<Box<dyn FnMut() -> bool>>> = RefCell::new(None);
+    }
+
+    unsafe extern "C" fn main_loop_trampoline() {
+        let keep_going = MAIN_LOOP.with(|main_loop| {
+            let mut main_loop = main_loop.borrow_mut();
+            let callback = match main_loop.as_mut() {
+                Some(callback) => callback,
+                None => return false,
+            };
+            // A panic stops the loop, and is reported by `check_callbacks`.
+            sdl::catch_callback_panic(callback).unwrap_or(false)
+        });
+
+        if !keep_going {
+            MAIN_LOOP.with(|main_loop| main_loop.borrow_mut().take());
+            sys::emscripten::emscripten_cancel_main_loop();
+        }
+    }
+
+    pub fn main_loop<F>(fps: u32, callback: F) -> sdl::Result<()>
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        MAIN_LOOP.with(|main_loop| *main_loop.borrow_mut() = Some(Box::new(callback)));
+
+        // Passing 1 for simulate_infinite_loop unwinds the stack here and
+        // hands control back to the browser, so this never returns.
+        unsafe {
+            sys::emscripten::emscripten_set_main_loop(Some(main_loop_trampoline), fps as c_int, 1)
         }
+        Ok(())
     }
 }
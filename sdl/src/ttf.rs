@@ -0,0 +1,433 @@
+//! Rendering text with TrueType fonts, with SDL_ttf.
+
+use std::collections::HashMap;
+use std::ffi::{c_int, CString};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::rect::Point;
+use crate::sdl;
+use crate::sys::ttf;
+use crate::video::Surface;
+
+/// The initialized font library, as returned by [`init`]. Fonts can only be
+/// opened while it's alive, but they keep the library up once they are, so
+/// it can be dropped before them.
+#[derive(Debug)]
+pub struct Ttf {
+    _not_send: sdl::NotSend,
+}
+
+// The number of `Ttf`s and `Font`s alive. SDL_ttf is shut down when the last
+// of them is dropped, so no font is ever closed after `TTF_Quit`.
+static USERS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn init() -> sdl::Result<Ttf> {
+    if unsafe { ttf::TTF_WasInit() } == 0 && unsafe { ttf::TTF_Init() } != 0 {
+        return Err(sdl::get_error());
+    }
+    USERS.fetch_add(1, Ordering::SeqCst);
+    Ok(Ttf {
+        _not_send: PhantomData,
+    })
+}
+
+fn release() {
+    if USERS.fetch_sub(1, Ordering::SeqCst) == 1 {
+        unsafe { ttf::TTF_Quit() }
+    }
+}
+
+impl Drop for Ttf {
+    fn drop(&mut self) {
+        release();
+    }
+}
+
+/// A font loaded at a particular size.
+#[derive(Debug)]
+pub struct Font {
+    raw: *mut ttf::TTF_Font,
+}
+
+impl Font {
+    /// Loads a TTF or FON file at `point_size`, based on 72 DPI.
+    pub fn open<P: AsRef<Path>>(path: P, point_size: u16) -> sdl::Result<Font> {
+        let path = sdl::path_to_cstring(path.as_ref())?;
+        let raw = unsafe { ttf::TTF_OpenFont(path.as_ptr(), point_size as c_int) };
+
+        if raw.is_null() {
+            Err(sdl::get_error())
+        } else {
+            USERS.fetch_add(1, Ordering::SeqCst);
+            Ok(Font { raw })
+        }
+    }
+
+    pub fn raw(&self) -> *mut ttf::TTF_Font {
+        self.raw
+    }
+
+    /// The maximum height of a line of text.
+    pub fn height(&self) -> i32 {
+        unsafe { ttf::TTF_FontHeight(self.raw) }
+    }
+
+    /// The distance from the top of a line to the baseline.
+    pub fn ascent(&self) -> i32 {
+        unsafe { ttf::TTF_FontAscent(self.raw) }
+    }
+
+    /// The recommended distance between the tops of consecutive lines.
+    pub fn line_skip(&self) -> i32 {
+        unsafe { ttf::TTF_FontLineSkip(self.raw) }
+    }
+
+    /// Returns the width and height `text` would be rendered at.
+    pub fn size_of(&self, text: &str) -> sdl::Result<(u32, u32)> {
+        let text = text_to_cstring(text);
+        let (mut w, mut h) = (0, 0);
+        if unsafe { ttf::TTF_SizeUTF8(self.raw, text.as_ptr(), &mut w, &mut h) } != 0 {
+            Err(sdl::get_error())
+        } else {
+            Ok((w as u32, h as u32))
+        }
+    }
+
+    /// Breaks `text` into lines no wider than `max_width` pixels, at spaces
+    /// and newlines. A word too long for a line by itself is broken between
+    /// grapheme clusters, so accents and other combining marks stay with the
+    /// characters they belong to.
+    ///
+    /// With the `unicode-bidi` feature each line is also put into display
+    /// order, so right-to-left text such as Arabic or Hebrew comes out the
+    /// right way round. This is only reordering: SDL_ttf doesn't do shaping,
+    /// so for example Arabic letters aren't joined.
+    pub fn wrap(&self, text: &str, max_width: u32) -> sdl::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                if !line.is_empty() {
+                    let candidate = format!("{line} {word}");
+                    if self.width_of(&candidate)? <= max_width {
+                        line = candidate;
+                        continue;
+                    }
+                    lines.push(mem::take(&mut line));
+                }
+                line = self.break_word(word, max_width, &mut lines)?;
+            }
+            lines.push(line);
+        }
+
+        #[cfg(feature = "unicode-bidi")]
+        for line in &mut lines {
+            *line = display_order(line);
+        }
+
+        Ok(lines)
+    }
+
+    // Adds as much of `word` to `lines` as fills whole lines, and returns
+    // the rest.
+    fn break_word(
+        &self,
+        word: &str,
+        max_width: u32,
+        lines: &mut Vec<String>,
+    ) -> sdl::Result<String> {
+        if self.width_of(word)? <= max_width {
+            return Ok(word.to_owned());
+        }
+
+        let mut piece = String::new();
+        for grapheme in word.graphemes(true) {
+            let candidate = piece.clone() + grapheme;
+            if !piece.is_empty() && self.width_of(&candidate)? > max_width {
+                lines.push(mem::replace(&mut piece, grapheme.to_owned()));
+            } else {
+                piece = candidate;
+            }
+        }
+        Ok(piece)
+    }
+
+    fn width_of(&self, text: &str) -> sdl::Result<u32> {
+        self.size_of(text).map(|(w, _)| w)
+    }
+
+    /// Renders `text` antialiased onto a new surface with a transparent
+    /// background.
+    pub fn render_blended(&self, text: &str, color: sdl::Color) -> sdl::Result<Surface> {
+        let text = text_to_cstring(text);
+        let raw = unsafe { ttf::TTF_RenderUTF8_Blended(self.raw, text.as_ptr(), color.into()) };
+
+        if raw.is_null() {
+            Err(sdl::get_error())
+        } else {
+            Ok(Surface::new(raw))
+        }
+    }
+}
+
+impl Drop for Font {
+    fn drop(&mut self) {
+        unsafe { ttf::TTF_CloseFont(self.raw) }
+        release();
+    }
+}
+
+/// The printable ASCII characters, for [`GlyphCache::prewarm`].
+pub const PRINTABLE_ASCII: RangeInclusive<char> = ' '..='~';
+
+/// Renders text a glyph at a time, keeping each glyph it renders for reuse.
+///
+/// [`Font::render_blended`] renders the whole string every time, which adds
+/// up for text which changes every frame, like a score. Drawing through a
+/// cache only renders each character once, at the cost of SDL_ttf's kerning.
+///
+/// The cache keeps [`stats`](GlyphCache::stats) on how well it's doing, for
+/// picking a capacity and deciding what to
+/// [`prewarm`](GlyphCache::prewarm).
+#[derive(Debug)]
+pub struct GlyphCache<'a> {
+    font: &'a Font,
+    color: sdl::Color,
+    capacity: usize,
+    glyphs: HashMap<char, Glyph>,
+    // Incremented on every lookup, to find the least recently used glyph.
+    clock: u64,
+    stats: CacheStats,
+}
+
+#[derive(Debug)]
+struct Glyph {
+    surface: Surface,
+    offset: Point,
+    advance: i32,
+    last_used: u64,
+}
+
+/// How a [`GlyphCache`] has been doing since it was created or its stats
+/// were last reset.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct CacheStats {
+    /// Glyphs which were already in the cache.
+    pub hits: u64,
+    /// Glyphs which had to be rendered.
+    pub misses: u64,
+    /// Glyphs dropped to make room for others.
+    pub evictions: u64,
+    /// Total time spent rendering glyphs for misses.
+    pub render_time: Duration,
+    /// How long the most recent [`draw`](GlyphCache::draw) took.
+    pub last_draw_time: Duration,
+}
+
+impl CacheStats {
+    /// The fraction of lookups which were hits, or 0 if there haven't been
+    /// any.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl<'a> GlyphCache<'a> {
+    /// Creates a cache of glyphs from `font` rendered in `color`, keeping up
+    /// to `capacity` of them. A capacity of 0 is treated as 1.
+    pub fn new(font: &'a Font, color: sdl::Color, capacity: usize) -> GlyphCache<'a> {
+        GlyphCache {
+            font,
+            color,
+            capacity: capacity.max(1),
+            glyphs: HashMap::new(),
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn font(&self) -> &'a Font {
+        self.font
+    }
+
+    pub fn color(&self) -> sdl::Color {
+        self.color
+    }
+
+    /// Changes the color text is drawn in, which empties the cache.
+    pub fn set_color(&mut self, color: sdl::Color) {
+        if color != self.color {
+            self.color = color;
+            self.glyphs.clear();
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes how many glyphs are kept, dropping the least recently used
+    /// ones if there are now too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.glyphs.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Renders every character in `chars` which isn't cached yet, such as
+    /// [`PRINTABLE_ASCII`] while loading, so the first frames which draw
+    /// text don't stall. This counts towards the stats like any other
+    /// lookup.
+    pub fn prewarm<I: IntoIterator<Item = char>>(&mut self, chars: I) -> sdl::Result<()> {
+        for c in chars {
+            self.glyph(c)?;
+        }
+        Ok(())
+    }
+
+    /// Draws `text` onto `dst` with its top left corner at `pos`. Newlines
+    /// start a new line, [`Font::line_skip`] further down.
+    pub fn draw(&mut self, dst: &mut Surface, pos: Point, text: &str) -> sdl::Result<()> {
+        let start = Instant::now();
+        let line_skip = self.font.line_skip();
+        let (mut x, mut y) = (pos.x as i32, pos.y as i32);
+
+        for c in text.chars() {
+            if c == '\n' {
+                x = pos.x as i32;
+                y += line_skip;
+                continue;
+            }
+
+            let glyph = self.glyph(c)?;
+            let glyph_pos = Point::new(
+                (x + glyph.offset.x as i32) as i16,
+                (y + glyph.offset.y as i32) as i16,
+            );
+            glyph.surface.blit(None, dst, glyph_pos)?;
+            x += glyph.advance;
+        }
+
+        self.stats.last_draw_time = start.elapsed();
+        Ok(())
+    }
+
+    fn glyph(&mut self, c: char) -> sdl::Result<&mut Glyph> {
+        // SDL_ttf only renders characters in the Basic Multilingual Plane.
+        let c = if (c as u32) <= 0xffff { c } else { '\u{fffd}' };
+        self.clock += 1;
+
+        if self.glyphs.contains_key(&c) {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+            let start = Instant::now();
+            let glyph = self.render(c)?;
+            self.stats.render_time += start.elapsed();
+
+            if self.glyphs.len() >= self.capacity {
+                self.evict();
+            }
+            self.glyphs.insert(c, glyph);
+        }
+
+        let glyph = self.glyphs.get_mut(&c).expect("glyph was just cached");
+        glyph.last_used = self.clock;
+        Ok(glyph)
+    }
+
+    fn render(&self, c: char) -> sdl::Result<Glyph> {
+        let ch = c as u16;
+        let (mut minx, mut maxx, mut miny, mut maxy, mut advance) = (0, 0, 0, 0, 0);
+        let ret = unsafe {
+            ttf::TTF_GlyphMetrics(
+                self.font.raw,
+                ch,
+                &mut minx,
+                &mut maxx,
+                &mut miny,
+                &mut maxy,
+                &mut advance,
+            )
+        };
+        if ret != 0 {
+            return Err(sdl::get_error());
+        }
+
+        let raw = unsafe { ttf::TTF_RenderGlyph_Blended(self.font.raw, ch, self.color.into()) };
+        if raw.is_null() {
+            return Err(sdl::get_error());
+        }
+
+        // The rendered glyph is only as big as its outline, so it's placed
+        // relative to the pen position and baseline using its metrics.
+        Ok(Glyph {
+            surface: Surface::new(raw),
+            offset: Point::new(minx as i16, (self.font.ascent() - maxy) as i16),
+            advance,
+            last_used: 0,
+        })
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .glyphs
+            .iter()
+            .min_by_key(|(_, glyph)| glyph.last_used)
+            .map(|(&c, _)| c);
+        if let Some(c) = oldest {
+            self.glyphs.remove(&c);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+// Reorders a line of text from the order it's stored in to the order it's
+// displayed in, left to right.
+#[cfg(feature = "unicode-bidi")]
+fn display_order(line: &str) -> String {
+    let info = unicode_bidi::BidiInfo::new(line, None);
+    info.paragraphs
+        .iter()
+        .map(|paragraph| info.reorder_line(paragraph, paragraph.range.clone()))
+        .collect()
+}
+
+// SDL_ttf takes nul terminated strings, so text is cut off at the first nul.
+fn text_to_cstring(text: &str) -> CString {
+    let text = text.split('\0').next().unwrap_or_default();
+    CString::new(text).expect("nul bytes were removed")
+}
@@ -2,7 +2,16 @@ use std::ffi::c_char;
 use std::ffi::c_int;
 use std::ffi::CString;
 use std::ffi::NulError;
+use std::fmt;
+use std::marker::PhantomData;
 use std::marker::PhantomPinned;
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::path::Path;
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use sys::SDL_Flip;
 use sys::SDL_FreeSurface;
@@ -10,24 +19,1352 @@ use sys::SDL_FreeSurface;
 use crate::get_error;
 use crate::sdl;
 use crate::sys;
-use crate::VideoSubsystem;
+use crate::{Point, Rect, VideoSubsystem};
 
+pub struct Surface {
+    inner: *mut sys::SDL_Surface,
+}
+
+impl fmt::Debug for Surface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let raw = unsafe { &*self.inner };
+        let format = self.format();
+        let (r, g, b, a) = format.masks();
+        f.debug_struct("Surface")
+            .field("width", &raw.w)
+            .field("height", &raw.h)
+            .field("bpp", &format.bits_per_pixel())
+            .field(
+                "masks",
+                &format_args!("({:#010x}, {:#010x}, {:#010x}, {:#010x})", r, g, b, a),
+            )
+            .field("flags", &FlagNames(sys::SDL_SurfaceFlags(raw.flags)))
+            .field("locked", &raw.locked)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+// Shows surface flags by name, rather than as a number.
+struct FlagNames(sys::SDL_SurfaceFlags);
+
+impl fmt::Debug for FlagNames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use sys::SDL_SurfaceFlags as Flags;
+        let names = [
+            (Flags::HWSURFACE, "HWSURFACE"),
+            (Flags::ASYNCBLIT, "ASYNCBLIT"),
+            (Flags::ANYFORMAT, "ANYFORMAT"),
+            (Flags::HWPALETTE, "HWPALETTE"),
+            (Flags::DOUBLEBUF, "DOUBLEBUF"),
+            (Flags::FULLSCREEN, "FULLSCREEN"),
+            (Flags::OPENGL, "OPENGL"),
+            (Flags::RESIZABLE, "RESIZABLE"),
+            (Flags::NOFRAME, "NOFRAME"),
+            (Flags::HWACCEL, "HWACCEL"),
+            (Flags::SRCCOLORKEY, "SRCCOLORKEY"),
+            (Flags::RLEACCELOK, "RLEACCELOK"),
+            (Flags::RLEACCEL, "RLEACCEL"),
+            (Flags::SRCALPHA, "SRCALPHA"),
+            (Flags::PREALLOC, "PREALLOC"),
+        ];
+
+        // SWSURFACE is 0, so it's what's left when nothing else is set.
+        let mut names = names
+            .iter()
+            .filter(|(flag, _)| self.0.contains(*flag))
+            .map(|(_, name)| *name)
+            .peekable();
+        if names.peek().is_none() {
+            return f.write_str("SWSURFACE");
+        }
+        for (i, name) in names.enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Surface {
+    pub(crate) fn new(inner: *mut sys::SDL_Surface) -> Surface {
+        Surface { inner }
+    }
+
+    /// Creates a new software surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - The number of bits per pixel.
+    /// * `rmask`, `gmask`, `bmask`, `amask` - The bits of each pixel which hold
+    ///   each channel. An `amask` of 0 creates a surface without alpha.
+    ///
+    pub fn create(
+        width: u32,
+        height: u32,
+        depth: u8,
+        rmask: u32,
+        gmask: u32,
+        bmask: u32,
+        amask: u32,
+    ) -> sdl::Result<Surface> {
+        let raw = unsafe {
+            sys::SDL_CreateRGBSurface(
+                sys::SDL_WindowFlags::SDL_SWSURFACE as u32,
+                width as c_int,
+                height as c_int,
+                depth as c_int,
+                rmask,
+                gmask,
+                bmask,
+                amask,
+            )
+        };
+
+        if raw.is_null() {
+            Err(get_error())
+        } else {
+            Ok(Surface::new(raw))
+        }
+    }
+
+    /// Creates a new software surface with one of the [`PixelFormatSpec`]
+    /// presets, or a custom format.
+    pub fn with_format(width: u32, height: u32, format: PixelFormatSpec) -> sdl::Result<Surface> {
+        Surface::create(
+            width,
+            height,
+            format.depth,
+            format.rmask,
+            format.gmask,
+            format.bmask,
+            format.amask,
+        )
+    }
+
+    /// Creates a new software surface with the same format as this one,
+    /// including its palette, such as a back buffer for the screen.
+    pub fn create_compatible(&self, width: u32, height: u32) -> sdl::Result<Surface> {
+        let format = self.format();
+        let (rmask, gmask, bmask, amask) = format.masks();
+        let mut surface = Surface::create(
+            width,
+            height,
+            format.bits_per_pixel(),
+            rmask,
+            gmask,
+            bmask,
+            amask,
+        )?;
+        if let Some(palette) = self.palette() {
+            surface.set_colors(0, &palette);
+        }
+        Ok(surface)
+    }
+
+    /// Copies the surface into another pixel format, keeping its color key
+    /// and per-surface alpha.
+    pub fn convert_to(&self, format: &PixelFormat) -> sdl::Result<Surface> {
+        use sys::SDL_SurfaceFlags as Flags;
+
+        let keep = Flags::SRCCOLORKEY | Flags::SRCALPHA | Flags::RLEACCELOK;
+        let flags = Flags(unsafe { (*self.inner).flags }) & keep;
+        let raw = unsafe {
+            sys::SDL_ConvertSurface(
+                self.inner,
+                format.raw() as *mut sys::SDL_PixelFormat,
+                flags.bits(),
+            )
+        };
+
+        if raw.is_null() {
+            Err(get_error())
+        } else {
+            Ok(Surface::new(raw))
+        }
+    }
+
+    /// Loads a surface from a BMP file.
+    pub fn load_bmp<P: AsRef<Path>>(path: P) -> sdl::Result<Surface> {
+        let path = sdl::path_to_cstring(path.as_ref())?;
+        let raw = unsafe {
+            let rw = sys::SDL_RWFromFile(path.as_ptr(), c"rb".as_ptr());
+            if rw.is_null() {
+                return Err(get_error());
+            }
+            sys::SDL_LoadBMP_RW(rw, 1)
+        };
+
+        if raw.is_null() {
+            Err(get_error())
+        } else {
+            Ok(Surface::new(raw))
+        }
+    }
+
+    /// Loads a surface from a BMP file which has already been read into
+    /// memory.
+    pub fn load_bmp_from_memory(data: &[u8]) -> sdl::Result<Surface> {
+        let rw = sdl::rw_from_slice(data)?;
+        let raw = unsafe { sys::SDL_LoadBMP_RW(rw, 1) };
+
+        if raw.is_null() {
+            Err(get_error())
+        } else {
+            Ok(Surface::new(raw))
+        }
+    }
+
+    /// Saves the surface as a BMP file. BMPs have no alpha channel, so it's
+    /// dropped.
+    pub fn save_bmp<P: AsRef<Path>>(&self, path: P) -> sdl::Result<()> {
+        let path = sdl::path_to_cstring(path.as_ref())?;
+        let ret = unsafe {
+            let rw = sys::SDL_RWFromFile(path.as_ptr(), c"wb".as_ptr());
+            if rw.is_null() {
+                return Err(get_error());
+            }
+            sys::SDL_SaveBMP_RW(self.inner, rw, 1)
+        };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(get_error())
+        }
+    }
+
+    pub fn raw(&self) -> *mut sys::SDL_Surface {
+        self.inner
+    }
+
+    /// Copies the surface into the format of the screen, so blitting it to
+    /// the screen is as fast as possible. The alpha channel is kept if the
+    /// surface has one. This can only be done once the video mode is set.
+    pub fn to_display_format(&self) -> sdl::Result<Surface> {
+        let raw = unsafe {
+            if self.format().has_alpha() {
+                sys::SDL_DisplayFormatAlpha(self.inner)
+            } else {
+                sys::SDL_DisplayFormat(self.inner)
+            }
+        };
+
+        if raw.is_null() {
+            Err(get_error())
+        } else {
+            Ok(Surface::new(raw))
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        unsafe { (*self.inner).w as u32 }
+    }
+
+    pub fn height(&self) -> u32 {
+        unsafe { (*self.inner).h as u32 }
+    }
+
+    pub fn format(&self) -> &PixelFormat {
+        unsafe { PixelFormat::from_raw((*self.inner).format) }
+    }
+
+    /// Returns the colors of an 8-bit surface's palette, or `None` for
+    /// surfaces without one.
+    pub fn palette(&self) -> Option<Vec<sdl::Color>> {
+        self.format().palette()
+    }
+
+    /// Replaces the palette entries starting at `first` with `colors`. For
+    /// the screen, this changes both the logical and the physical palette.
+    ///
+    /// Returns whether every color could be set exactly, which isn't the case
+    /// for surfaces without a palette, or a screen which doesn't have
+    /// `SDL_HWPALETTE` set and got a different physical palette.
+    pub fn set_colors(&mut self, first: usize, colors: &[sdl::Color]) -> bool {
+        let mut raw: Vec<sys::SDL_Color> = colors.iter().map(|&c| c.into()).collect();
+        let ret = unsafe {
+            sys::SDL_SetColors(
+                self.inner,
+                raw.as_mut_ptr(),
+                first as c_int,
+                raw.len() as c_int,
+            )
+        };
+        ret == 1
+    }
+
+    /// Fills the whole surface with `color`, mapped to the surface's format.
+    pub fn fill(&mut self, color: sdl::Color) -> sdl::Result<()> {
+        let pixel = color.to_pixel(self.format());
+        if unsafe { sys::SDL_FillRect(self.inner, std::ptr::null_mut(), pixel) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fills `rect` with `color`, mapped to the surface's format. The
+    /// rectangle is clipped to the surface's clip rectangle.
+    pub fn fill_rect(&mut self, rect: Rect, color: sdl::Color) -> sdl::Result<()> {
+        let pixel = color.to_pixel(self.format());
+        let mut raw = rect.raw();
+        if unsafe { sys::SDL_FillRect(self.inner, &mut raw, pixel) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Copies `src_rect` of this surface, or all of it, to `dst` with its top
+    /// left corner at `pos`. The copy is clipped to the clip rectangle of
+    /// `dst`.
+    pub fn blit(&self, src_rect: Option<Rect>, dst: &mut Surface, pos: Point) -> sdl::Result<()> {
+        let mut src_raw = src_rect.map(Rect::raw);
+        let src_ptr = src_raw
+            .as_mut()
+            .map_or(std::ptr::null_mut(), |raw| raw as *mut sys::SDL_Rect);
+        let mut dst_raw = Rect::new(pos.x, pos.y, 0, 0).raw();
+
+        if unsafe { sys::SDL_UpperBlit(self.inner, src_ptr, dst.inner, &mut dst_raw) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the per-surface alpha used when blitting from this surface, or
+    /// turns alpha blending off with `None`. Surfaces with an alpha channel
+    /// use it instead of the per-surface alpha.
+    pub fn set_alpha(&mut self, alpha: Option<u8>) -> sdl::Result<()> {
+        let (flags, alpha) = match alpha {
+            Some(alpha) => (sys::SDL_SRCALPHA, alpha),
+            None => (0, 255),
+        };
+        if unsafe { sys::SDL_SetAlpha(self.inner, flags, alpha) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the color which is left out when blitting from this surface, or
+    /// turns color keying off with `None`. Keyed surfaces are also RLE
+    /// accelerated, which makes blitting them much faster but locking them
+    /// slower.
+    pub fn set_color_key(&mut self, key: Option<sdl::Color>) -> sdl::Result<()> {
+        let (flags, key) = match key {
+            Some(key) => (
+                sys::SDL_SRCCOLORKEY | sys::SDL_RLEACCEL,
+                key.to_pixel(self.format()),
+            ),
+            None => (0, 0),
+        };
+        if unsafe { sys::SDL_SetColorKey(self.inner, flags, key) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The rectangle drawing and blitting to this surface is restricted to.
+    pub fn clip_rect(&self) -> Rect {
+        unsafe { (*self.inner).clip_rect.into() }
+    }
+
+    /// Restricts drawing and blitting to `rect`, clipped to the bounds of the
+    /// surface, or lifts the restriction with `None`.
+    pub fn set_clip_rect(&mut self, rect: Option<Rect>) {
+        let raw = rect.map(Rect::raw);
+        let ptr = raw
+            .as_ref()
+            .map_or(std::ptr::null(), |raw| raw as *const sys::SDL_Rect);
+        unsafe { sys::SDL_SetClipRect(self.inner, ptr) };
+    }
+
+    /// Returns a view of the `rect` part of this surface, which can be drawn
+    /// and blitted to without allocating a new surface. See [`SurfaceView`].
+    pub fn region(&mut self, rect: Rect) -> SurfaceView<'_> {
+        SurfaceView::new(self, rect)
+    }
+
+    /// The length of a row of pixels in bytes, including any padding.
+    pub fn pitch(&self) -> usize {
+        unsafe { (*self.inner).pitch as usize }
+    }
+
+    /// Whether the surface has to be locked before its pixels can be
+    /// accessed, matching `SDL_MUSTLOCK`. This is the case for hardware and
+    /// RLE accelerated surfaces.
+    pub fn must_lock(&self) -> bool {
+        use sys::SDL_SurfaceFlags as Flags;
+
+        let raw = unsafe { &*self.inner };
+        let flags = Flags::HWSURFACE | Flags::ASYNCBLIT | Flags::RLEACCEL;
+        raw.offset != 0 || Flags(raw.flags).intersects(flags)
+    }
+
+    /// Gives direct access to the surface's pixels until the returned guard
+    /// is dropped. Surfaces which [`must_lock`](Surface::must_lock) are locked
+    /// for as long as the guard is around, and the rest aren't locked at all.
+    pub fn lock(&mut self) -> sdl::Result<SurfaceLock<'_>> {
+        let locked = self.must_lock();
+        if locked && unsafe { sys::SDL_LockSurface(self.inner) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(SurfaceLock {
+                surface: self,
+                locked,
+            })
+        }
+    }
+
+    /// Copies the pixels out as RGBA bytes, row by row without any padding,
+    /// whatever the surface's own format is.
+    pub fn read_rgba(&mut self) -> sdl::Result<Vec<u8>> {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        let bpp = self.format().bytes_per_pixel() as usize;
+
+        let lock = self.lock()?;
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for row in lock.pixels().chunks(lock.pitch().max(1)) {
+            for pixel in row[..width * bpp].chunks_exact(bpp) {
+                let value = match pixel {
+                    [a] => *a as u32,
+                    [a, b] => u16::from_ne_bytes([*a, *b]) as u32,
+                    [a, b, c] if cfg!(target_endian = "little") => {
+                        u32::from_le_bytes([*a, *b, *c, 0])
+                    }
+                    [a, b, c] => u32::from_be_bytes([0, *a, *b, *c]),
+                    _ => u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]),
+                };
+
+                let color = sdl::Color::from_pixel(value, lock.format());
+                pixels.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+        Ok(pixels)
+    }
+
+    pub fn flip(&mut self) -> sdl::Result<()> {
+        if unsafe { SDL_Flip(self.inner) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns a copy of the surface mirrored left to right, such as a
+    /// sprite facing the other way. The format, palette, color key and
+    /// per-surface alpha are kept.
+    ///
+    /// This isn't related to [`flip`](Surface::flip), which swaps the
+    /// buffers of a double buffered screen.
+    pub fn flip_horizontal(&mut self) -> sdl::Result<Surface> {
+        self.mirrored(true)
+    }
+
+    /// Returns a copy of the surface mirrored top to bottom. See
+    /// [`flip_horizontal`](Surface::flip_horizontal).
+    pub fn flip_vertical(&mut self) -> sdl::Result<Surface> {
+        self.mirrored(false)
+    }
+
+    fn mirrored(&mut self, horizontal: bool) -> sdl::Result<Surface> {
+        let bpp = self.format().bytes_per_pixel() as usize;
+        let mut dst = self.create_compatible(self.width(), self.height())?;
+        self.copy_blit_settings(&mut dst)?;
+
+        let row_len = self.width() as usize * bpp;
+        {
+            let src = self.lock()?;
+            let src_rows = src.pixels().chunks(src.pitch().max(1));
+            let mut dst_lock = dst.lock()?;
+            let dst_pitch = dst_lock.pitch().max(1);
+            let dst_rows = dst_lock.pixels_mut().chunks_mut(dst_pitch);
+
+            if horizontal {
+                for (src_row, dst_row) in src_rows.zip(dst_rows) {
+                    let src_pixels = src_row[..row_len].chunks_exact(bpp).rev();
+                    let dst_pixels = dst_row[..row_len].chunks_exact_mut(bpp);
+                    for (src_pixel, dst_pixel) in src_pixels.zip(dst_pixels) {
+                        dst_pixel.copy_from_slice(src_pixel);
+                    }
+                }
+            } else {
+                for (src_row, dst_row) in src_rows.rev().zip(dst_rows) {
+                    dst_row[..row_len].copy_from_slice(&src_row[..row_len]);
+                }
+            }
+        }
+        Ok(dst)
+    }
+
+    /// Multiplies each pixel's color by its alpha, in place.
+    ///
+    /// Premultiplied pixels are what's wanted when scaling or filtering a
+    /// surface, for example with rotozoom's smoothing. Averaging pixels with
+    /// straight alpha pulls in the color of fully transparent ones, which
+    /// shows up as dark fringes around sprites. SDL's own blits expect
+    /// straight alpha, so undo this with
+    /// [`unpremultiply_alpha`](Surface::unpremultiply_alpha) before
+    /// blitting.
+    ///
+    /// Only works on 32-bit surfaces with an alpha channel.
+    pub fn premultiply_alpha(&mut self) -> Result<(), AlphaError> {
+        self.map_rgba32(|[r, g, b, a]| {
+            let mul = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+            [mul(r), mul(g), mul(b), a]
+        })
+    }
+
+    /// Divides each pixel's color by its alpha, in place, undoing
+    /// [`premultiply_alpha`](Surface::premultiply_alpha). Some precision is
+    /// lost for pixels which are nearly transparent, and fully transparent
+    /// ones become black.
+    pub fn unpremultiply_alpha(&mut self) -> Result<(), AlphaError> {
+        self.map_rgba32(|[r, g, b, a]| {
+            if a == 0 {
+                return [0, 0, 0, 0];
+            }
+            let div = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+            [div(r), div(g), div(b), a]
+        })
+    }
+
+    /// Returns a copy of a 32-bit surface with alpha as a surface without
+    /// alpha, using a color key for the transparent parts instead.
+    ///
+    /// SDL can accelerate color keyed blits, but blends alpha in software,
+    /// so this is much faster to draw for sprites which don't need partial
+    /// transparency. Pixels with an alpha below `threshold` become
+    /// transparent and the rest fully opaque.
+    ///
+    /// `key` is used as the color key unless an opaque pixel already has
+    /// that color, in which case the closest color which isn't used is.
+    pub fn to_color_keyed(
+        &mut self,
+        key: sdl::Color,
+        threshold: u8,
+    ) -> Result<Surface, AlphaError> {
+        let format = self.format();
+        if format.bytes_per_pixel() != 4 || !format.has_alpha() {
+            return Err(AlphaError::NotRgba32);
+        }
+        let raw = unsafe { *format.raw() };
+        let (rmask, gmask, bmask, amask) = format.masks();
+        let is_opaque = |pixel: u32| (pixel & amask) >> raw.Ashift >= threshold as u32;
+        let channel =
+            |pixel: u32, mask: u32, shift: u8, loss: u8| (((pixel & mask) >> shift) << loss) as u8;
+        let (width, height) = (self.width(), self.height());
+        let row_len = width as usize * 4;
+
+        let mut used = ColorSet::new();
+        {
+            let src = self.lock()?;
+            for row in src.pixels().chunks(src.pitch().max(1)) {
+                for pixel in row[..row_len].chunks_exact(4) {
+                    let pixel = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                    if is_opaque(pixel) {
+                        used.insert(sdl::Color::rgb(
+                            channel(pixel, rmask, raw.Rshift, raw.Rloss),
+                            channel(pixel, gmask, raw.Gshift, raw.Gloss),
+                            channel(pixel, bmask, raw.Bshift, raw.Bloss),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut dst = Surface::create(width, height, 32, rmask, gmask, bmask, 0)?;
+        let key = nearest_unused_color(key, &used).ok_or(AlphaError::NoFreeColorKey)?;
+        let key_pixel = key.to_pixel(dst.format());
+        {
+            let src = self.lock()?;
+            let src_rows = src.pixels().chunks(src.pitch().max(1));
+            let mut dst_lock = dst.lock()?;
+            let dst_pitch = dst_lock.pitch().max(1);
+            let dst_rows = dst_lock.pixels_mut().chunks_mut(dst_pitch);
+
+            for (src_row, dst_row) in src_rows.zip(dst_rows) {
+                let src_pixels = src_row[..row_len].chunks_exact(4);
+                let dst_pixels = dst_row[..row_len].chunks_exact_mut(4);
+                for (src_pixel, dst_pixel) in src_pixels.zip(dst_pixels) {
+                    let pixel = u32::from_ne_bytes([
+                        src_pixel[0],
+                        src_pixel[1],
+                        src_pixel[2],
+                        src_pixel[3],
+                    ]);
+                    let pixel = if is_opaque(pixel) {
+                        pixel & !amask
+                    } else {
+                        key_pixel
+                    };
+                    dst_pixel.copy_from_slice(&pixel.to_ne_bytes());
+                }
+            }
+        }
+
+        dst.set_color_key(Some(key))?;
+        Ok(dst)
+    }
+
+    // Replaces every pixel of a 32-bit surface with alpha with `f` of its
+    // red, green, blue and alpha.
+    fn map_rgba32<F>(&mut self, f: F) -> Result<(), AlphaError>
+    where
+        F: Fn([u8; 4]) -> [u8; 4],
+    {
+        let format = self.format();
+        if format.bytes_per_pixel() != 4 || !format.has_alpha() {
+            return Err(AlphaError::NotRgba32);
+        }
+        let raw = unsafe { *format.raw() };
+        let shifts = [raw.Rshift, raw.Gshift, raw.Bshift, raw.Ashift];
+
+        let row_len = self.width() as usize * 4;
+        let mut lock = self.lock()?;
+        let pitch = lock.pitch().max(1);
+        for row in lock.pixels_mut().chunks_mut(pitch) {
+            for pixel in row[..row_len].chunks_exact_mut(4) {
+                let value = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let channels = shifts.map(|shift| (value >> shift) as u8);
+                let value = f(channels)
+                    .iter()
+                    .zip(shifts)
+                    .fold(0, |value, (&c, shift)| value | ((c as u32) << shift));
+                pixel.copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    // Gives `dst` the same color key and per-surface alpha as this surface.
+    fn copy_blit_settings(&self, dst: &mut Surface) -> sdl::Result<()> {
+        use sys::SDL_SurfaceFlags as Flags;
+
+        let (flags, format) = unsafe { ((*self.inner).flags, &*(*self.inner).format) };
+        let flags = Flags(flags);
+        // Surfaces record a request for RLE acceleration as RLEACCELOK.
+        let rle = if flags.contains(Flags::RLEACCELOK) {
+            Flags::RLEACCEL
+        } else {
+            Flags::empty()
+        };
+        let key_flags = (flags & Flags::SRCCOLORKEY) | rle;
+        let alpha_flags = (flags & Flags::SRCALPHA) | rle;
+        unsafe {
+            if sys::SDL_SetColorKey(dst.inner, key_flags.bits(), format.colorkey) != 0
+                || sys::SDL_SetAlpha(dst.inner, alpha_flags.bits(), format.alpha) != 0
+            {
+                return Err(get_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        unsafe { SDL_FreeSurface(self.inner) }
+    }
+}
+
+/// A rectangular part of a [`Surface`], as returned by [`Surface::region`].
+///
+/// The view sets the surface's clip rectangle to its own bounds, and
+/// translates coordinates so that `(0, 0)` is its top left corner. The
+/// previous clip rectangle is restored when the view is dropped. Views can be
+/// nested with [`SurfaceView::region`], and the inner view is clipped to the
+/// outer one.
+#[derive(Debug)]
+pub struct SurfaceView<'a> {
+    surface: &'a mut Surface,
+    origin: Point,
+    previous_clip: Rect,
+}
+
+impl<'a> SurfaceView<'a> {
+    fn new(surface: &'a mut Surface, rect: Rect) -> SurfaceView<'a> {
+        let previous_clip = surface.clip_rect();
+        let clip = previous_clip.intersection(rect).unwrap_or_default();
+        surface.set_clip_rect(Some(clip));
+
+        SurfaceView {
+            surface,
+            origin: Point::new(rect.x, rect.y),
+            previous_clip,
+        }
+    }
+
+    /// The position of the view's top left corner on the underlying surface.
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// The part of the underlying surface which can be drawn to through this
+    /// view, in the underlying surface's coordinates.
+    pub fn clip_rect(&self) -> Rect {
+        self.surface.clip_rect()
+    }
+
+    /// The underlying surface, with its clip rectangle set to this view.
+    /// Coordinates on it are not translated.
+    pub fn surface(&self) -> &Surface {
+        self.surface
+    }
+
+    /// Translates a point in the view to the underlying surface.
+    pub fn to_surface(&self, point: Point) -> Point {
+        Point::new(
+            point.x.saturating_add(self.origin.x),
+            point.y.saturating_add(self.origin.y),
+        )
+    }
+
+    fn translate(&self, rect: Rect) -> Rect {
+        let origin = self.to_surface(Point::new(rect.x, rect.y));
+        Rect::new(origin.x, origin.y, rect.w, rect.h)
+    }
+
+    pub fn fill(&mut self, color: sdl::Color) -> sdl::Result<()> {
+        let clip = self.clip_rect();
+        self.surface.fill_rect(clip, color)
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: sdl::Color) -> sdl::Result<()> {
+        let rect = self.translate(rect);
+        self.surface.fill_rect(rect, color)
+    }
+
+    /// Blits `src_rect` of `src`, or all of it, into the view with its top
+    /// left corner at `pos`.
+    pub fn blit_from(
+        &mut self,
+        src: &Surface,
+        src_rect: Option<Rect>,
+        pos: Point,
+    ) -> sdl::Result<()> {
+        let pos = self.to_surface(pos);
+        src.blit(src_rect, self.surface, pos)
+    }
+
+    /// Returns a view of the `rect` part of this view.
+    pub fn region(&mut self, rect: Rect) -> SurfaceView<'_> {
+        let rect = self.translate(rect);
+        SurfaceView::new(self.surface, rect)
+    }
+}
+
+impl Drop for SurfaceView<'_> {
+    fn drop(&mut self) {
+        self.surface.set_clip_rect(Some(self.previous_clip));
+    }
+}
+
+/// The depth and channel masks of a surface to create with
+/// [`Surface::with_format`].
+///
+/// The names of the 24 and 32-bit presets give the order of the bytes in
+/// memory, which is what image data loaded from elsewhere usually uses. SDL
+/// masks apply to pixels read as native integers, so the masks for these
+/// depend on the byte order of the platform. The 16-bit preset is a packed
+/// format, the same on every platform.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PixelFormatSpec {
+    pub depth: u8,
+    pub rmask: u32,
+    pub gmask: u32,
+    pub bmask: u32,
+    pub amask: u32,
+}
+
+impl PixelFormatSpec {
+    /// Red, green, blue and alpha bytes, in that order.
+    pub const RGBA8888: PixelFormatSpec = PixelFormatSpec::bytes32([0, 1, 2, 3]);
+    /// Alpha, red, green and blue bytes, in that order.
+    pub const ARGB8888: PixelFormatSpec = PixelFormatSpec::bytes32([1, 2, 3, 0]);
+    /// Blue, green, red and alpha bytes, in that order.
+    pub const BGRA8888: PixelFormatSpec = PixelFormatSpec::bytes32([2, 1, 0, 3]);
+    /// Red, green and blue bytes, in that order, without alpha.
+    pub const RGB888: PixelFormatSpec = PixelFormatSpec {
+        depth: 24,
+        rmask: PixelFormatSpec::byte_mask24(0),
+        gmask: PixelFormatSpec::byte_mask24(1),
+        bmask: PixelFormatSpec::byte_mask24(2),
+        amask: 0,
+    };
+    /// 5 bits of red, 6 of green and 5 of blue, packed into a `u16`.
+    pub const RGB565: PixelFormatSpec = PixelFormatSpec {
+        depth: 16,
+        rmask: 0xf800,
+        gmask: 0x07e0,
+        bmask: 0x001f,
+        amask: 0,
+    };
+    /// 8-bit palette indices.
+    pub const INDEX8: PixelFormatSpec = PixelFormatSpec {
+        depth: 8,
+        rmask: 0,
+        gmask: 0,
+        bmask: 0,
+        amask: 0,
+    };
+
+    /// The format of the screen, which [`Surface::to_display_format`]
+    /// converts to, or `None` if the video mode isn't set.
+    pub fn display() -> Option<PixelFormatSpec> {
+        let screen = unsafe { sys::SDL_GetVideoSurface().as_ref()? };
+        let format = unsafe { PixelFormat::from_raw(screen.format) };
+        let (rmask, gmask, bmask, amask) = format.masks();
+        Some(PixelFormatSpec {
+            depth: format.bits_per_pixel(),
+            rmask,
+            gmask,
+            bmask,
+            amask,
+        })
+    }
+
+    // Takes the position in memory of the red, green, blue and alpha bytes.
+    const fn bytes32([r, g, b, a]: [usize; 4]) -> PixelFormatSpec {
+        PixelFormatSpec {
+            depth: 32,
+            rmask: PixelFormatSpec::byte_mask(r),
+            gmask: PixelFormatSpec::byte_mask(g),
+            bmask: PixelFormatSpec::byte_mask(b),
+            amask: PixelFormatSpec::byte_mask(a),
+        }
+    }
+
+    // The mask selecting the byte at `index` in memory.
+    const fn byte_mask(index: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes[index] = 0xff;
+        u32::from_ne_bytes(bytes)
+    }
+
+    // SDL reads 24-bit pixels as the low three bytes of a native integer, so
+    // on big endian platforms the masks are a byte further down.
+    const fn byte_mask24(index: usize) -> u32 {
+        if cfg!(target_endian = "big") {
+            PixelFormatSpec::byte_mask(index) >> 8
+        } else {
+            PixelFormatSpec::byte_mask(index)
+        }
+    }
+}
+
+/// The layout of the pixels in a [`Surface`].
+///
+/// This is only ever handed out by reference, borrowed from the surface it
+/// describes.
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct PixelFormat {
+    raw: sys::SDL_PixelFormat,
+}
+
+impl PixelFormat {
+    unsafe fn from_raw<'a>(raw: *const sys::SDL_PixelFormat) -> &'a PixelFormat {
+        &*(raw as *const PixelFormat)
+    }
+
+    pub fn raw(&self) -> *const sys::SDL_PixelFormat {
+        &self.raw
+    }
+
+    pub fn bits_per_pixel(&self) -> u8 {
+        self.raw.BitsPerPixel
+    }
+
+    pub fn bytes_per_pixel(&self) -> u8 {
+        self.raw.BytesPerPixel
+    }
+
+    /// The red, green, blue and alpha masks, in that order. These are all 0
+    /// for palettized formats.
+    pub fn masks(&self) -> (u32, u32, u32, u32) {
+        (
+            self.raw.Rmask,
+            self.raw.Gmask,
+            self.raw.Bmask,
+            self.raw.Amask,
+        )
+    }
+
+    /// The colors of the palette, for 8-bit formats.
+    pub fn palette(&self) -> Option<Vec<sdl::Color>> {
+        let palette = unsafe { self.raw.palette.as_ref()? };
+        let colors =
+            unsafe { slice::from_raw_parts(palette.colors, palette.ncolors.max(0) as usize) };
+        Some(colors.iter().map(|&c| c.into()).collect())
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.raw.Amask != 0
+    }
+}
+
+/// Direct access to the pixels of a [`Surface`], see [`Surface::lock`].
+///
+/// Pixels are stored row by row in the surface's own format, with each row
+/// taking up [`pitch`](SurfaceLock::pitch) bytes.
+#[derive(Debug)]
+pub struct SurfaceLock<'a> {
+    surface: &'a mut Surface,
+    locked: bool,
+}
+
+impl SurfaceLock<'_> {
+    pub fn format(&self) -> &PixelFormat {
+        self.surface.format()
+    }
+
+    pub fn pitch(&self) -> usize {
+        self.surface.pitch()
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        unsafe {
+            let raw = *self.surface.inner;
+            slice::from_raw_parts(raw.pixels as *const u8, self.len())
+        }
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        unsafe {
+            let raw = *self.surface.inner;
+            slice::from_raw_parts_mut(raw.pixels as *mut u8, self.len())
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.surface.pitch() * self.surface.height() as usize
+    }
+}
+
+impl Drop for SurfaceLock<'_> {
+    fn drop(&mut self) {
+        if self.locked {
+            unsafe { sys::SDL_UnlockSurface(self.surface.inner) }
+        }
+    }
+}
+
+// Whether a Screen is alive, since SDL 1.2 only has the one window.
+static SCREEN_ALIVE: AtomicBool = AtomicBool::new(false);
+
+/// The display surface, as returned by [`WindowBuilder::build`].
+///
+/// SDL owns the video surface and frees it itself, so unlike a regular
+/// [`Surface`] it isn't freed on drop. Dropping it allows another window to
+/// be built, which reuses the same SDL window. It derefs to [`Surface`], so everything
+/// which can be done with a surface can be done with the screen.
+pub struct Screen {
+    surface: ManuallyDrop<Surface>,
+    requested_flags: sys::SDL_SurfaceFlags,
+    requested_bpp: u8,
+    _not_send: sdl::NotSend,
+}
+
+impl Screen {
+    pub(crate) fn new(
+        inner: *mut sys::SDL_Surface,
+        requested_flags: sys::SDL_SurfaceFlags,
+        requested_bpp: u8,
+    ) -> Screen {
+        Screen {
+            surface: ManuallyDrop::new(Surface::new(inner)),
+            requested_flags,
+            requested_bpp,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// The flags SDL actually set the video mode with, which may differ from
+    /// the ones which were asked for. See
+    /// [`missing_flags`](Screen::missing_flags).
+    pub fn flags(&self) -> sys::SDL_SurfaceFlags {
+        sys::SDL_SurfaceFlags(unsafe { (*self.surface.inner).flags })
+    }
+
+    /// The flags which were passed to `SDL_SetVideoMode`.
+    pub fn requested_flags(&self) -> sys::SDL_SurfaceFlags {
+        self.requested_flags
+    }
+
+    /// The flags which were asked for but not granted, such as a hardware
+    /// surface or double buffering the video driver doesn't support.
+    pub fn missing_flags(&self) -> sys::SDL_SurfaceFlags {
+        // ANYFORMAT only changes how the mode is picked, so it never shows
+        // up on the surface.
+        let requested = self.requested_flags & !sys::SDL_SurfaceFlags::ANYFORMAT;
+        requested & !self.flags()
+    }
+
+    /// The depth which was asked for. The depth SDL picked is in
+    /// [`format`](Surface::format), and the two differ when SDL emulates the
+    /// requested depth with a shadow surface.
+    pub fn requested_bpp(&self) -> u8 {
+        self.requested_bpp
+    }
+
+    /// Whether the screen is in video memory.
+    pub fn is_hardware(&self) -> bool {
+        self.flags().contains(sys::SDL_SurfaceFlags::HWSURFACE)
+    }
+
+    pub fn is_double_buffered(&self) -> bool {
+        self.flags().contains(sys::SDL_SurfaceFlags::DOUBLEBUF)
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.flags().contains(sys::SDL_SurfaceFlags::FULLSCREEN)
+    }
+
+    /// Shows what's been drawn to the screen. Double buffered screens are
+    /// flipped, and the whole of any other screen is updated.
+    pub fn present(&mut self) -> sdl::Result<()> {
+        if self.is_double_buffered() {
+            self.surface.flip()
+        } else {
+            unsafe { sys::SDL_UpdateRect(self.surface.inner, 0, 0, 0, 0) };
+            Ok(())
+        }
+    }
+
+    /// Starts fading the screen out to `color` over `duration`.
+    pub fn fade_to(&self, color: sdl::Color, duration: Duration) -> Fade {
+        Fade::new(color, duration, true)
+    }
+
+    /// Starts fading the screen in from `color` over `duration`.
+    pub fn fade_from(&self, color: sdl::Color, duration: Duration) -> Fade {
+        Fade::new(color, duration, false)
+    }
+}
+
+impl fmt::Debug for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Screen")
+            .field("surface", &*self.surface)
+            .field("requested_flags", &FlagNames(self.requested_flags))
+            .field("requested_bpp", &self.requested_bpp)
+            .finish()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        SCREEN_ALIVE.store(false, Ordering::Release);
+    }
+}
+
+/// A fade between the screen and a solid color, as returned by
+/// [`Screen::fade_to`] and [`Screen::fade_from`].
+///
+/// Every frame, [`update`](Fade::update) it with the time since the last
+/// frame, then [`draw`](Fade::draw) it after the rest of the scene and before
+/// flipping. It's drawn as a color filled overlay with per-surface alpha.
 #[derive(Debug)]
-pub struct Surface {
-    inner: *mut sys::SDL_Surface,
+pub struct Fade {
+    color: sdl::Color,
+    duration: Duration,
+    elapsed: Duration,
+    out: bool,
+    overlay: Option<Surface>,
 }
 
-impl Surface {
-    pub(crate) fn new(inner: *mut sys::SDL_Surface) -> Surface {
-        Surface { inner }
+impl Fade {
+    fn new(color: sdl::Color, duration: Duration, out: bool) -> Fade {
+        Fade {
+            color,
+            duration,
+            elapsed: Duration::ZERO,
+            out,
+            overlay: None,
+        }
     }
 
-    pub fn raw(&self) -> *mut sys::SDL_Surface {
+    pub fn update(&mut self, dt: Duration) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// How far along the fade is, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        }
+    }
+
+    /// The opacity of the overlay for the current frame.
+    pub fn alpha(&self) -> u8 {
+        let opacity = if self.out {
+            self.progress()
+        } else {
+            1.0 - self.progress()
+        };
+        (opacity * 255.0).round() as u8
+    }
+
+    pub fn draw(&mut self, screen: &mut Surface) -> sdl::Result<()> {
+        let alpha = self.alpha();
+        if alpha == 0 {
+            return Ok(());
+        }
+
+        let size = (screen.width(), screen.height());
+        let overlay = match self.overlay.take() {
+            Some(overlay) if (overlay.width(), overlay.height()) == size => overlay,
+            _ => self.create_overlay(screen)?,
+        };
+        let overlay = self.overlay.insert(overlay);
+
+        overlay.set_alpha(Some(alpha))?;
+        overlay.blit(None, screen, Point::new(0, 0))
+    }
+
+    // The overlay matches the screen's format so blitting it doesn't need a
+    // conversion, other than for palettized screens where its palette would
+    // be out of sync with the screen's.
+    fn create_overlay(&self, screen: &Surface) -> sdl::Result<Surface> {
+        let format = screen.format();
+        let (bpp, (rmask, gmask, bmask, _)) = if format.bits_per_pixel() > 8 {
+            (format.bits_per_pixel(), format.masks())
+        } else {
+            (32, (0xff0000, 0xff00, 0xff, 0))
+        };
+
+        let mut overlay =
+            Surface::create(screen.width(), screen.height(), bpp, rmask, gmask, bmask, 0)?;
+        overlay.fill(self.color)?;
+        Ok(overlay)
+    }
+}
+
+impl Deref for Screen {
+    type Target = Surface;
+
+    fn deref(&self) -> &Surface {
+        &self.surface
+    }
+}
+
+impl DerefMut for Screen {
+    fn deref_mut(&mut self) -> &mut Surface {
+        &mut self.surface
+    }
+}
+
+/// An off-screen back buffer the same size and format as the screen, to
+/// draw each frame to before presenting it.
+///
+/// Drawing to a software surface and copying it to the screen once per frame
+/// is usually faster than drawing to the screen directly, which may be in
+/// video memory, and never shows a half drawn frame. The canvas derefs to
+/// [`Surface`], so it can be filled, blitted to and drawn on like one.
+#[derive(Debug)]
+pub struct Canvas {
+    surface: Surface,
+}
+
+impl Canvas {
+    pub fn new(screen: &Screen) -> sdl::Result<Canvas> {
+        Ok(Canvas {
+            surface: screen.create_compatible(screen.width(), screen.height())?,
+        })
+    }
+
+    /// Converts `surface` to the canvas's format. Blitting a surface in
+    /// another format converts it on every blit, so do this once for images
+    /// when they're loaded.
+    pub fn prepare(&self, surface: &Surface) -> sdl::Result<Surface> {
+        surface.convert_to(self.surface.format())
+    }
+
+    /// Copies the canvas to the screen and presents it.
+    ///
+    /// If the screen has changed size, the canvas is resized to match
+    /// afterwards, which clears it.
+    pub fn present(&mut self, screen: &mut Screen) -> sdl::Result<()> {
+        self.surface.blit(None, screen, Point::new(0, 0))?;
+        screen.present()?;
+
+        if (self.surface.width(), self.surface.height()) != (screen.width(), screen.height()) {
+            self.surface = screen.create_compatible(screen.width(), screen.height())?;
+        }
+        Ok(())
+    }
+}
+
+impl Deref for Canvas {
+    type Target = Surface;
+
+    fn deref(&self) -> &Surface {
+        &self.surface
+    }
+}
+
+impl DerefMut for Canvas {
+    fn deref_mut(&mut self) -> &mut Surface {
+        &mut self.surface
+    }
+}
+
+/// The pixel layout of an [`Overlay`].
+///
+/// The planar formats store a full resolution Y plane followed by U and V
+/// planes at half the resolution in each direction. The packed formats store
+/// two pixels in every four bytes, sharing their U and V samples.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum OverlayFormat {
+    /// Planar Y, V, U.
+    Yv12,
+    /// Planar Y, U, V.
+    Iyuv,
+    /// Packed Y0, U0, Y1, V0.
+    Yuy2,
+    /// Packed U0, Y0, V0, Y1.
+    Uyvy,
+    /// Packed Y0, V0, Y1, U0.
+    Yvyu,
+}
+
+impl OverlayFormat {
+    pub fn raw(self) -> u32 {
+        match self {
+            OverlayFormat::Yv12 => sys::SDL_YV12_OVERLAY,
+            OverlayFormat::Iyuv => sys::SDL_IYUV_OVERLAY,
+            OverlayFormat::Yuy2 => sys::SDL_YUY2_OVERLAY,
+            OverlayFormat::Uyvy => sys::SDL_UYVY_OVERLAY,
+            OverlayFormat::Yvyu => sys::SDL_YVYU_OVERLAY,
+        }
+    }
+
+    pub fn from_raw(raw: u32) -> Option<OverlayFormat> {
+        match raw {
+            sys::SDL_YV12_OVERLAY => Some(OverlayFormat::Yv12),
+            sys::SDL_IYUV_OVERLAY => Some(OverlayFormat::Iyuv),
+            sys::SDL_YUY2_OVERLAY => Some(OverlayFormat::Yuy2),
+            sys::SDL_UYVY_OVERLAY => Some(OverlayFormat::Uyvy),
+            sys::SDL_YVYU_OVERLAY => Some(OverlayFormat::Yvyu),
+            _ => None,
+        }
+    }
+
+    pub fn is_planar(self) -> bool {
+        matches!(self, OverlayFormat::Yv12 | OverlayFormat::Iyuv)
+    }
+
+    /// The number of planes the pixels are split into.
+    pub fn planes(self) -> usize {
+        if self.is_planar() {
+            3
+        } else {
+            1
+        }
+    }
+
+    /// Returns the number of bytes of pixel data in each row of `plane`, and
+    /// the number of rows, for an overlay of the given size. This doesn't
+    /// include any padding at the end of the rows.
+    ///
+    /// The chroma planes of the planar formats round down, matching SDL, so
+    /// those formats should be used with even sizes.
+    pub fn plane_size(self, plane: usize, width: u32, height: u32) -> Option<(usize, usize)> {
+        let (width, height) = (width as usize, height as usize);
+        match (self.is_planar(), plane) {
+            (true, 0) => Some((width, height)),
+            (true, 1 | 2) => Some((width / 2, height / 2)),
+            (false, 0) => Some((width * 2, height)),
+            _ => None,
+        }
+    }
+}
+
+impl Screen {
+    /// Creates a YUV overlay which can be drawn to the screen, usually with
+    /// hardware scaling and color conversion.
+    pub fn create_overlay(
+        &self,
+        width: u32,
+        height: u32,
+        format: OverlayFormat,
+    ) -> sdl::Result<Overlay> {
+        let raw = unsafe {
+            sys::SDL_CreateYUVOverlay(
+                width as c_int,
+                height as c_int,
+                format.raw(),
+                self.surface.inner,
+            )
+        };
+
+        if raw.is_null() {
+            Err(get_error())
+        } else {
+            Ok(Overlay {
+                inner: raw,
+                format,
+                _not_send: PhantomData,
+            })
+        }
+    }
+}
+
+/// A YUV video overlay, as returned by [`Screen::create_overlay`].
+#[derive(Debug)]
+pub struct Overlay {
+    inner: *mut sys::SDL_Overlay,
+    format: OverlayFormat,
+    _not_send: sdl::NotSend,
+}
+
+impl Overlay {
+    pub fn raw(&self) -> *mut sys::SDL_Overlay {
         self.inner
     }
 
-    pub fn flip(&mut self) -> sdl::Result<()> {
-        if unsafe { SDL_Flip(self.inner) } != 0 {
+    pub fn width(&self) -> u32 {
+        unsafe { (*self.inner).w as u32 }
+    }
+
+    pub fn height(&self) -> u32 {
+        unsafe { (*self.inner).h as u32 }
+    }
+
+    pub fn format(&self) -> OverlayFormat {
+        self.format
+    }
+
+    /// Whether the overlay is hardware accelerated.
+    pub fn is_hardware(&self) -> bool {
+        unsafe { (*self.inner).hw_overlay() != 0 }
+    }
+
+    /// Locks the overlay for writing until the returned guard is dropped.
+    pub fn lock(&mut self) -> sdl::Result<OverlayLock<'_>> {
+        if unsafe { sys::SDL_LockYUVOverlay(self.inner) } != 0 {
+            Err(get_error())
+        } else {
+            Ok(OverlayLock { overlay: self })
+        }
+    }
+
+    /// Draws the overlay to the screen, scaled to fill `dst`.
+    pub fn display(&mut self, dst: Rect) -> sdl::Result<()> {
+        let mut dst = dst.raw();
+        if unsafe { sys::SDL_DisplayYUVOverlay(self.inner, &mut dst) } != 0 {
             Err(get_error())
         } else {
             Ok(())
@@ -35,9 +1372,76 @@ impl Surface {
     }
 }
 
-impl Drop for Surface {
+impl Drop for Overlay {
     fn drop(&mut self) {
-        unsafe { SDL_FreeSurface(self.inner) }
+        unsafe { sys::SDL_FreeYUVOverlay(self.inner) }
+    }
+}
+
+/// Access to the planes of a locked [`Overlay`], see [`Overlay::lock`].
+///
+/// Each plane is handed out as a slice sized from its pitch and the number
+/// of rows the format gives it, so writing past the end of a plane panics
+/// rather than corrupting memory.
+#[derive(Debug)]
+pub struct OverlayLock<'a> {
+    overlay: &'a mut Overlay,
+}
+
+impl OverlayLock<'_> {
+    /// The number of planes, see [`OverlayFormat::planes`].
+    pub fn planes(&self) -> usize {
+        let raw = unsafe { &*self.overlay.inner };
+        self.overlay.format.planes().min(raw.planes.max(0) as usize)
+    }
+
+    /// The length of a row of `plane` in bytes, including any padding.
+    pub fn pitch(&self, plane: usize) -> Option<usize> {
+        if plane >= self.planes() {
+            return None;
+        }
+        unsafe { Some(*(*self.overlay.inner).pitches.add(plane) as usize) }
+    }
+
+    pub fn plane(&self, plane: usize) -> Option<&[u8]> {
+        let (pixels, len) = self.plane_raw(plane)?;
+        unsafe { Some(slice::from_raw_parts(pixels, len)) }
+    }
+
+    pub fn plane_mut(&mut self, plane: usize) -> Option<&mut [u8]> {
+        let (pixels, len) = self.plane_raw(plane)?;
+        unsafe { Some(slice::from_raw_parts_mut(pixels, len)) }
+    }
+
+    /// Returns every plane at once, so they can be written together. For
+    /// [`OverlayFormat::Yv12`] these are Y, V and U, and for
+    /// [`OverlayFormat::Iyuv`] Y, U and V.
+    pub fn planes_mut(&mut self) -> Vec<&mut [u8]> {
+        // The planes never overlap, so handing them out together is fine.
+        (0..self.planes())
+            .filter_map(|plane| self.plane_raw(plane))
+            .map(|(pixels, len)| unsafe { slice::from_raw_parts_mut(pixels, len) })
+            .collect()
+    }
+
+    fn plane_raw(&self, plane: usize) -> Option<(*mut u8, usize)> {
+        let pitch = self.pitch(plane)?;
+        let (_, rows) =
+            self.overlay
+                .format
+                .plane_size(plane, self.overlay.width(), self.overlay.height())?;
+        let pixels = unsafe { *(*self.overlay.inner).pixels.add(plane) };
+        if pixels.is_null() {
+            None
+        } else {
+            Some((pixels, pitch * rows))
+        }
+    }
+}
+
+impl Drop for OverlayLock<'_> {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_UnlockYUVOverlay(self.overlay.inner) }
     }
 }
 
@@ -47,13 +1451,32 @@ impl VideoSubsystem {
     }
 }
 
+/// What [`WindowBuilder::build`] does when the requested size isn't a video
+/// mode the display supports, which mostly matters in fullscreen.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum ModePolicy {
+    /// Leave it to `SDL_SetVideoMode`, which may pick a bigger mode and
+    /// center the screen in it, or fail.
+    #[default]
+    Unchecked,
+    /// Use the closest supported size instead.
+    Closest,
+    /// Fail with [`WindowBuildError::UnsupportedMode`].
+    Exact,
+}
+
 #[derive(Debug)]
 pub struct WindowBuilder {
     title: String,
     width: u32,
     height: u32,
     window_flags: u32,
+    bpp: u8,
+    mode_policy: ModePolicy,
+    // Set with SDL_GL_SetAttribute just before the video mode is.
+    gl_attributes: Vec<(sys::SDL_GLAttr, c_int)>,
     _marker: PhantomPinned,
+    _not_send: sdl::NotSend,
 }
 
 impl WindowBuilder {
@@ -64,12 +1487,20 @@ impl WindowBuilder {
             width,
             height,
             window_flags: 0,
+            bpp: 32,
+            mode_policy: ModePolicy::Unchecked,
+            gl_attributes: Vec::new(),
             _marker: PhantomPinned,
+            _not_send: PhantomData,
         }
     }
 
     /// Builds the window.
-    pub fn build(&self) -> Result<Surface, WindowBuildError> {
+    ///
+    /// SDL 1.2 only has one window, so this fails with
+    /// [`WindowBuildError::AlreadyExists`] while the [`Screen`] from an
+    /// earlier call is still around.
+    pub fn build(&self) -> Result<Screen, WindowBuildError> {
         use self::WindowBuildError::*;
         let title = match CString::new(self.title.clone()) {
             Ok(t) => t,
@@ -81,23 +1512,93 @@ impl WindowBuilder {
         if self.height >= (1 << 31) {
             return Err(HeightOverflows(self.width));
         }
+        let (width, height) = self.checked_size()?;
+        if SCREEN_ALIVE
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(AlreadyExists);
+        }
+
+        for &(attr, value) in &self.gl_attributes {
+            if unsafe { sys::SDL_GL_SetAttribute(attr, value) } != 0 {
+                SCREEN_ALIVE.store(false, Ordering::Release);
+                return Err(sdl::get_error().into());
+            }
+        }
 
         unsafe {
             let raw = sys::SDL_SetVideoMode(
-                self.width as c_int,
-                self.height as c_int,
-                32,
+                width as c_int,
+                height as c_int,
+                self.bpp as c_int,
                 self.window_flags,
             );
+            if raw.is_null() {
+                SCREEN_ALIVE.store(false, Ordering::Release);
+                return Err(sdl::get_error().into());
+            }
 
             sys::SDL_WM_SetCaption(title.as_ptr() as *const c_char, std::ptr::null());
+            let requested = sys::SDL_SurfaceFlags(self.window_flags);
+            Ok(Screen::new(raw, requested, self.bpp))
+        }
+    }
 
-            if raw.is_null() {
-                Err(sdl::get_error().into())
-            } else {
-                Ok(Surface::new(raw))
+    /// Returns the sizes the display supports with the flags set so far,
+    /// largest first, or `None` if any size will do, which is usually the
+    /// case for windows.
+    pub fn supported_modes(&self) -> Option<Vec<(u32, u32)>> {
+        let modes = unsafe { sys::SDL_ListModes(std::ptr::null_mut(), self.window_flags) };
+        if modes as isize == -1 {
+            return None;
+        }
+
+        let mut sizes = Vec::new();
+        if !modes.is_null() {
+            unsafe {
+                let mut mode = modes;
+                while !(*mode).is_null() {
+                    sizes.push(((**mode).w as u32, (**mode).h as u32));
+                    mode = mode.add(1);
+                }
             }
         }
+        Some(sizes)
+    }
+
+    // Applies the mode policy to the requested size.
+    fn checked_size(&self) -> Result<(u32, u32), WindowBuildError> {
+        let requested = (self.width, self.height);
+        if self.mode_policy == ModePolicy::Unchecked {
+            return Ok(requested);
+        }
+
+        let ok = unsafe {
+            sys::SDL_VideoModeOK(
+                self.width as c_int,
+                self.height as c_int,
+                self.bpp as c_int,
+                self.window_flags,
+            )
+        };
+        let supported = match self.supported_modes() {
+            Some(supported) => supported,
+            None if ok != 0 => return Ok(requested),
+            None => Vec::new(),
+        };
+        if ok != 0 && supported.contains(&requested) {
+            return Ok(requested);
+        }
+
+        match (self.mode_policy, closest_mode(requested, &supported)) {
+            (ModePolicy::Closest, Some(mode)) => Ok(mode),
+            _ => Err(WindowBuildError::UnsupportedMode {
+                width: self.width,
+                height: self.height,
+                supported,
+            }),
+        }
     }
 
     pub fn fullscreen(&mut self) -> &mut WindowBuilder {
@@ -110,6 +1611,94 @@ impl WindowBuilder {
         self
     }
 
+    /// Sets an OpenGL attribute for the context the window is created with,
+    /// replacing any earlier value for it. SDL only reads these when the
+    /// video mode is set, so they're held on to until then.
+    pub fn gl_attribute(&mut self, attr: sys::SDL_GLAttr, value: i32) -> &mut WindowBuilder {
+        self.gl_attributes.retain(|&(a, _)| a != attr);
+        self.gl_attributes.push((attr, value as c_int));
+        self
+    }
+
+    /// An OpenGL window with 8 bits for each of red, green, blue and alpha,
+    /// a 24-bit depth buffer and double buffering.
+    pub fn opengl_32bit_depth24(&mut self) -> &mut WindowBuilder {
+        use sys::SDL_GLAttr::*;
+        self.opengl()
+            .gl_attribute(SDL_GL_RED_SIZE, 8)
+            .gl_attribute(SDL_GL_GREEN_SIZE, 8)
+            .gl_attribute(SDL_GL_BLUE_SIZE, 8)
+            .gl_attribute(SDL_GL_ALPHA_SIZE, 8)
+            .gl_attribute(SDL_GL_DEPTH_SIZE, 24)
+            .gl_attribute(SDL_GL_DOUBLEBUFFER, 1)
+    }
+
+    /// Like [`opengl_32bit_depth24`](WindowBuilder::opengl_32bit_depth24),
+    /// with an 8-bit stencil buffer as well.
+    pub fn opengl_32bit_depth24_stencil8(&mut self) -> &mut WindowBuilder {
+        self.opengl_32bit_depth24()
+            .gl_attribute(sys::SDL_GLAttr::SDL_GL_STENCIL_SIZE, 8)
+    }
+
+    /// An OpenGL window with 5-6-5 bit color, a 16-bit depth buffer and
+    /// double buffering, for older hardware.
+    pub fn opengl_16bit_depth16(&mut self) -> &mut WindowBuilder {
+        use sys::SDL_GLAttr::*;
+        self.opengl()
+            .gl_attribute(SDL_GL_RED_SIZE, 5)
+            .gl_attribute(SDL_GL_GREEN_SIZE, 6)
+            .gl_attribute(SDL_GL_BLUE_SIZE, 5)
+            .gl_attribute(SDL_GL_DEPTH_SIZE, 16)
+            .gl_attribute(SDL_GL_DOUBLEBUFFER, 1)
+    }
+
+    /// Asks for multisample antialiasing with `samples` samples per pixel.
+    /// 0 turns it off.
+    pub fn opengl_multisample(&mut self, samples: u8) -> &mut WindowBuilder {
+        use sys::SDL_GLAttr::*;
+        self.opengl()
+            .gl_attribute(SDL_GL_MULTISAMPLEBUFFERS, (samples > 0) as i32)
+            .gl_attribute(SDL_GL_MULTISAMPLESAMPLES, samples as i32)
+    }
+
+    /// Turns vsync on or off, where the driver supports it.
+    pub fn opengl_vsync(&mut self, enabled: bool) -> &mut WindowBuilder {
+        self.opengl()
+            .gl_attribute(sys::SDL_GLAttr::SDL_GL_SWAP_CONTROL, enabled as i32)
+    }
+
+    /// Asks for the screen to be in video memory. Check
+    /// [`Screen::is_hardware`] to find out whether it is.
+    pub fn hardware(&mut self) -> &mut WindowBuilder {
+        self.window_flags |= sys::SDL_WindowFlags::SDL_HWSURFACE as u32;
+        self
+    }
+
+    /// Asks for a double buffered screen, which also needs a hardware
+    /// surface. Check [`Screen::is_double_buffered`] to find out whether
+    /// it is.
+    pub fn double_buffered(&mut self) -> &mut WindowBuilder {
+        self.window_flags |=
+            sys::SDL_WindowFlags::SDL_HWSURFACE as u32 | sys::SDL_WindowFlags::SDL_DOUBLEBUF as u32;
+        self
+    }
+
+    /// Sets the depth of the screen, which is 32 by default. If the display
+    /// can't do it SDL emulates it, unless [`any_format`] is set.
+    ///
+    /// [`any_format`]: WindowBuilder::any_format
+    pub fn bits_per_pixel(&mut self, bpp: u8) -> &mut WindowBuilder {
+        self.bpp = bpp;
+        self
+    }
+
+    /// Lets SDL use the display's own depth instead of emulating the
+    /// requested one, which is faster.
+    pub fn any_format(&mut self) -> &mut WindowBuilder {
+        self.window_flags |= sys::SDL_WindowFlags::SDL_ANYFORMAT as u32;
+        self
+    }
+
     pub fn borderless(&mut self) -> &mut WindowBuilder {
         self.window_flags |= sys::SDL_WindowFlags::SDL_NOFRAME as u32;
         self
@@ -121,9 +1710,109 @@ impl WindowBuilder {
         self
     }
 
+    /// Sets what happens if the requested size isn't a supported video mode.
+    /// By default it isn't checked.
+    pub fn mode_policy(&mut self, policy: ModePolicy) -> &mut WindowBuilder {
+        self.mode_policy = policy;
+        self
+    }
+
     // TODO: set icon
 }
 
+// Picks the smallest mode the requested size fits in, or the biggest mode if
+// it doesn't fit in any of them.
+fn closest_mode(requested: (u32, u32), modes: &[(u32, u32)]) -> Option<(u32, u32)> {
+    let area = |&(w, h): &(u32, u32)| w as u64 * h as u64;
+    modes
+        .iter()
+        .filter(|&&(w, h)| w >= requested.0 && h >= requested.1)
+        .min_by_key(|mode| area(mode))
+        .or_else(|| modes.iter().max_by_key(|mode| area(mode)))
+        .copied()
+}
+
+// A set of RGB colors, with a bit for each of the 2^24 of them.
+struct ColorSet(Vec<u64>);
+
+impl ColorSet {
+    fn new() -> ColorSet {
+        ColorSet(vec![0; (1 << 24) / 64])
+    }
+
+    fn index(color: sdl::Color) -> usize {
+        (color.r as usize) << 16 | (color.g as usize) << 8 | color.b as usize
+    }
+
+    fn insert(&mut self, color: sdl::Color) {
+        let index = ColorSet::index(color);
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    fn contains(&self, color: sdl::Color) -> bool {
+        let index = ColorSet::index(color);
+        self.0[index / 64] & 1 << (index % 64) != 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.0.iter().all(|&bits| bits == u64::MAX)
+    }
+}
+
+// Finds the color closest to `color` which isn't `used`, searching outwards
+// in growing cubes around it.
+fn nearest_unused_color(color: sdl::Color, used: &ColorSet) -> Option<sdl::Color> {
+    if used.is_full() {
+        return None;
+    }
+
+    let center = [color.r, color.g, color.b].map(|c| c as i32);
+    let in_range = |c: i32| (0..=255).contains(&c);
+    for radius in 0..=255i32 {
+        for dr in -radius..=radius {
+            for dg in -radius..=radius {
+                // Only the surface of the cube, the inside was already
+                // searched. Away from its faces in r and g, that's just the
+                // two ends in b.
+                let step = if dr.abs() == radius || dg.abs() == radius {
+                    1
+                } else {
+                    2 * radius as usize
+                };
+                for db in (-radius..=radius).step_by(step) {
+                    let [r, g, b] = [center[0] + dr, center[1] + dg, center[2] + db];
+                    if !(in_range(r) && in_range(g) && in_range(b)) {
+                        continue;
+                    }
+                    let candidate = sdl::Color::rgb(r as u8, g as u8, b as u8);
+                    if !used.contains(candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn format_modes(modes: &[(u32, u32)]) -> String {
+    if modes.is_empty() {
+        return "none".to_owned();
+    }
+    let modes: Vec<String> = modes.iter().map(|(w, h)| format!("{}x{}", w, h)).collect();
+    modes.join(", ")
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AlphaError {
+    #[error("surface isn't 32 bits per pixel with an alpha channel")]
+    NotRgba32,
+    #[error("every color is used, so none is left for the color key")]
+    NoFreeColorKey,
+    #[error("SDL error: {}", .0)]
+    SdlError(#[from] sdl::Error),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum WindowBuildError {
     #[error("window height overflow: {}", .0)]
@@ -132,6 +1821,19 @@ pub enum WindowBuildError {
     WidthOverflows(u32),
     #[error("invalid window title: {}", .0)]
     InvalidTitle(NulError),
+    #[error(
+        "unsupported video mode {}x{}, supported modes are: {}",
+        .width,
+        .height,
+        format_modes(.supported)
+    )]
+    UnsupportedMode {
+        width: u32,
+        height: u32,
+        supported: Vec<(u32, u32)>,
+    },
+    #[error("a window already exists, and SDL 1.2 only supports one")]
+    AlreadyExists,
     #[error("SDL error: {}", .0)]
     SdlError(#[from] sdl::Error),
 }
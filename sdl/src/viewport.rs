@@ -0,0 +1,160 @@
+//! Drawing at a fixed resolution and scaling it up to fill the screen, with
+//! bars around it where the aspect ratio doesn't match.
+
+use crate::sdl;
+use crate::sys;
+use crate::video::{Screen, Surface};
+use crate::{Point, Rect};
+
+/// How a [`Viewport`] is scaled up to the screen.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScaleMode {
+    /// Only scale by whole numbers, so every pixel ends up the same size.
+    /// This keeps pixel art crisp, but can leave wider bars. Screens smaller
+    /// than the viewport fall back to [`ScaleMode::Fit`].
+    #[default]
+    Integer,
+    /// Scale as large as fits while keeping the aspect ratio.
+    Fit,
+}
+
+/// An off-screen surface to draw the game to at a fixed resolution, which is
+/// then scaled up and centered on the screen, whatever mode it's in.
+///
+/// Draw to [`surface`](Viewport::surface) each frame, then call
+/// [`present`](Viewport::present) instead of presenting the screen directly.
+/// Scaling uses `SDL_SoftStretch`, which picks the nearest pixel.
+#[derive(Debug)]
+pub struct Viewport {
+    surface: Surface,
+    mode: ScaleMode,
+    bar_color: sdl::Color,
+}
+
+impl Viewport {
+    /// Creates a viewport of the given size in the same format as `screen`,
+    /// which `SDL_SoftStretch` needs.
+    pub fn new(screen: &Screen, width: u32, height: u32, mode: ScaleMode) -> sdl::Result<Viewport> {
+        let surface = screen.create_compatible(width, height)?;
+        Ok(Viewport {
+            surface,
+            mode,
+            bar_color: sdl::Color::BLACK,
+        })
+    }
+
+    /// The surface to draw to.
+    pub fn surface(&mut self) -> &mut Surface {
+        &mut self.surface
+    }
+
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.mode
+    }
+
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        self.mode = mode;
+    }
+
+    /// Sets the color of the bars around the viewport, black by default.
+    pub fn set_bar_color(&mut self, color: sdl::Color) {
+        self.bar_color = color;
+    }
+
+    /// Returns where on a screen of the given size the viewport is drawn.
+    pub fn dest_rect(&self, screen_width: u32, screen_height: u32) -> Rect {
+        let (width, height) = (self.surface.width(), self.surface.height());
+        if width == 0 || height == 0 {
+            return Rect::default();
+        }
+
+        let (scaled_width, scaled_height) = {
+            let scale_x = screen_width as f64 / width as f64;
+            let scale_y = screen_height as f64 / height as f64;
+            let mut scale = scale_x.min(scale_y);
+            if self.mode == ScaleMode::Integer && scale >= 1.0 {
+                scale = scale.floor();
+            }
+            (
+                ((width as f64 * scale) as u32).min(screen_width),
+                ((height as f64 * scale) as u32).min(screen_height),
+            )
+        };
+
+        Rect::new(
+            ((screen_width - scaled_width) / 2) as i16,
+            ((screen_height - scaled_height) / 2) as i16,
+            scaled_width as u16,
+            scaled_height as u16,
+        )
+    }
+
+    /// Scales the viewport onto the screen, fills in the bars around it and
+    /// presents the screen.
+    pub fn present(&mut self, screen: &mut Screen) -> sdl::Result<()> {
+        let (screen_width, screen_height) = (screen.width(), screen.height());
+        let dst = self.dest_rect(screen_width, screen_height);
+
+        for bar in bars(dst, screen_width, screen_height) {
+            screen.fill_rect(bar, self.bar_color)?;
+        }
+
+        if !dst.is_empty() {
+            let mut dst_raw = dst.raw();
+            let ret = unsafe {
+                sys::SDL_SoftStretch(
+                    self.surface.raw(),
+                    std::ptr::null_mut(),
+                    screen.raw(),
+                    &mut dst_raw,
+                )
+            };
+            if ret != 0 {
+                return Err(sdl::get_error());
+            }
+        }
+
+        screen.present()
+    }
+
+    /// Converts a point on a screen of the given size, such as the mouse
+    /// position, to a point in the viewport. Returns `None` for points on
+    /// the bars.
+    pub fn to_viewport(
+        &self,
+        screen_width: u32,
+        screen_height: u32,
+        point: Point,
+    ) -> Option<Point> {
+        let dst = self.dest_rect(screen_width, screen_height);
+        if !dst.contains_point(point) {
+            return None;
+        }
+
+        let x = (point.x - dst.x) as u32 * self.surface.width() / dst.w as u32;
+        let y = (point.y - dst.y) as u32 * self.surface.height() / dst.h as u32;
+        Some(Point::new(x as i16, y as i16))
+    }
+}
+
+// The parts of the screen around `dst`: the bars above and below it, then
+// the ones to either side.
+fn bars(dst: Rect, screen_width: u32, screen_height: u32) -> impl Iterator<Item = Rect> {
+    let (x, y) = (dst.x as u32, dst.y as u32);
+    let right = x + dst.w as u32;
+    let bottom = y + dst.h as u32;
+    [
+        Rect::new(0, 0, screen_width as u16, y as u16),
+        Rect::new(
+            0,
+            bottom as i16,
+            screen_width as u16,
+            (screen_height - bottom) as u16,
+        ),
+        Rect::new(0, y as i16, x as u16, dst.h),
+        Rect::new(right as i16, y as i16, (screen_width - right) as u16, dst.h),
+    ]
+    .into_iter()
+    .filter(|bar| !bar.is_empty())
+}